@@ -0,0 +1,647 @@
+//! A fluent API for building trees without a parser, for tests and code generators.
+//!
+//! Every node made by a [Builder] gets the builder's span, which is [Span::native] unless it was
+//! changed with [at](Builder::at). Statements with blocks are built in steps:
+//!
+//! ```
+//! # use basil_ast::builder::Builder;
+//! # use basil_ast::operators::Operator;
+//! let b = Builder::new();
+//! let statement = b
+//!     .if_(b.binary(b.identifier("x"), Operator::Lt, b.integer(0)))
+//!     .then(b.assign(b.identifier("x"), b.integer(0)))
+//!     .elif(b.identifier("y"), vec![b.pass()])
+//!     .else_(b.return_(b.identifier("x")));
+//! ```
+//!
+//! Anything that takes a block accepts a [Block](NodeType::Block) node, a list of statements, or a
+//! single statement, which is put in a block of its own.
+
+use basil_core::span::Span;
+use num_bigint::BigInt;
+use num_rational::Rational;
+
+use crate::node::{Node, NodeType};
+use crate::operators::Operator;
+
+#[derive(Debug, Clone)]
+pub struct Builder {
+    span: Span,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder::new()
+    }
+}
+
+impl Builder {
+    /// Creates a builder whose nodes have [native](Span::native) spans
+    pub fn new() -> Self {
+        Builder {
+            span: Span::native(),
+        }
+    }
+
+    /// Creates a builder whose nodes have the given span
+    pub fn at(&self, span: Span) -> Self {
+        Builder { span }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn node(&self, node_type: NodeType) -> Node {
+        Node::new(self.span.clone(), node_type)
+    }
+
+    pub fn identifier<S: Into<String>>(&self, name: S) -> Node {
+        self.node(NodeType::Identifier(name.into()))
+    }
+
+    pub fn integer<I: Into<BigInt>>(&self, value: I) -> Node {
+        self.node(NodeType::Integer(value.into()))
+    }
+
+    pub fn float(&self, value: Rational) -> Node {
+        self.node(NodeType::Float(value))
+    }
+
+    pub fn string<S: Into<String>>(&self, value: S) -> Node {
+        self.node(NodeType::String(value.into()))
+    }
+
+    pub fn boolean(&self, value: bool) -> Node {
+        self.node(NodeType::Boolean(value))
+    }
+
+    pub fn none(&self) -> Node {
+        self.node(NodeType::None)
+    }
+
+    /// `parent.child`
+    pub fn member<S: Into<String>>(&self, parent: Node, child: S) -> Node {
+        self.node(NodeType::QualifiedIdentifier {
+            parent,
+            child: self.identifier(child),
+        })
+    }
+
+    /// `value[index]`
+    pub fn index(&self, value: Node, index: Node) -> Node {
+        self.node(NodeType::Index { value, index })
+    }
+
+    /// `name(arguments)`. Keyword arguments can be made with
+    /// [keyword_argument](Builder::keyword_argument).
+    pub fn call(&self, name: Node, arguments: Vec<Node>) -> Node {
+        self.node(NodeType::FunctionCall {
+            name,
+            parameters: arguments,
+        })
+    }
+
+    pub fn keyword_argument<S: Into<String>>(&self, name: S, value: Node) -> Node {
+        self.node(NodeType::KeywordArgument {
+            name: self.identifier(name),
+            value,
+        })
+    }
+
+    /// `lambda parameters: body`. Parameters with defaults can be made with
+    /// [parameter](Builder::parameter).
+    pub fn lambda(&self, parameters: Vec<Node>, body: Node) -> Node {
+        self.node(NodeType::Lambda { parameters, body })
+    }
+
+    /// A parameter of a lambda
+    pub fn parameter<S: Into<String>>(&self, name: S, default: Option<Node>) -> Node {
+        self.node(NodeType::Parameter {
+            name: self.identifier(name),
+            annotation: None,
+            default,
+        })
+    }
+
+    pub fn binary(&self, lhs: Node, op: Operator, rhs: Node) -> Node {
+        self.node(NodeType::BinaryExpression {
+            lhs,
+            rhs,
+            op: self.node(NodeType::Operator(op)),
+        })
+    }
+
+    pub fn unary(&self, op: Operator, val: Node) -> Node {
+        self.node(NodeType::UnaryExpression {
+            val,
+            op: self.node(NodeType::Operator(op)),
+        })
+    }
+
+    pub fn list(&self, elements: Vec<Node>) -> Node {
+        self.node(NodeType::List(elements))
+    }
+
+    pub fn tuple(&self, elements: Vec<Node>) -> Node {
+        self.node(NodeType::Tuple(elements))
+    }
+
+    pub fn set(&self, elements: Vec<Node>) -> Node {
+        self.node(NodeType::Set(elements))
+    }
+
+    pub fn dictionary(&self, entries: Vec<(Node, Node)>) -> Node {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| self.node(NodeType::DictionaryEntry { key, value }))
+            .collect();
+        self.node(NodeType::Dictionary(entries))
+    }
+
+    pub fn assign(&self, lhs: Node, rhs: Node) -> Node {
+        self.node(NodeType::Assignment {
+            lhs,
+            var_type: None,
+            rhs,
+        })
+    }
+
+    /// `lhs: var_type = rhs`
+    pub fn assign_annotated(&self, lhs: Node, var_type: Node, rhs: Node) -> Node {
+        self.node(NodeType::Assignment {
+            lhs,
+            var_type: Some(var_type),
+            rhs,
+        })
+    }
+
+    pub fn pass(&self) -> Node {
+        self.node(NodeType::Pass)
+    }
+
+    pub fn break_(&self) -> Node {
+        self.node(NodeType::Break)
+    }
+
+    pub fn continue_(&self) -> Node {
+        self.node(NodeType::Continue)
+    }
+
+    pub fn return_(&self, value: Node) -> Node {
+        self.node(NodeType::Return(value))
+    }
+
+    pub fn yield_(&self, value: Node) -> Node {
+        self.node(NodeType::Yield(value))
+    }
+
+    pub fn raise(&self, value: Node) -> Node {
+        self.node(NodeType::Raise(value))
+    }
+
+    /// `@decorator` lines in front of a function or class definition, outermost first
+    pub fn decorated(&self, decorators: Vec<Node>, definition: Node) -> Node {
+        self.node(NodeType::Decorated {
+            decorators,
+            definition,
+        })
+    }
+
+    pub fn block<B: IntoBlock>(&self, statements: B) -> Node {
+        statements.into_block(self)
+    }
+
+    /// A whole file, which is a block of its statements
+    pub fn module(&self, statements: Vec<Node>) -> Node {
+        self.block(statements)
+    }
+
+    /// Starts an `if` statement, which is finished by giving it a block with
+    /// [then](IfBuilder::then)
+    pub fn if_(&self, condition: Node) -> IfBuilder {
+        IfBuilder {
+            builder: self.clone(),
+            condition,
+        }
+    }
+
+    pub fn while_(&self, condition: Node) -> WhileBuilder {
+        WhileBuilder {
+            builder: self.clone(),
+            condition,
+        }
+    }
+
+    /// Starts a `for identifier in iterator` loop
+    pub fn for_(&self, identifier: Node, iterator: Node) -> ForBuilder {
+        ForBuilder {
+            builder: self.clone(),
+            identifier,
+            iterator,
+        }
+    }
+
+    /// Starts a `match` statement, which is finished with [build](MatchBuilder::build) once its
+    /// cases have been added
+    pub fn match_(&self, subject: Node) -> MatchBuilder {
+        MatchBuilder {
+            builder: self.clone(),
+            subject,
+            cases: vec![],
+        }
+    }
+
+    /// Starts a `try` statement, which is finished with [build](TryBuilder::build) once its
+    /// clauses have been added
+    pub fn try_<B: IntoBlock>(&self, block: B) -> TryBuilder {
+        TryBuilder {
+            builder: self.clone(),
+            block: block.into_block(self),
+            handlers: vec![],
+            r#else: None,
+            finally: None,
+        }
+    }
+
+    pub fn function<S: Into<String>>(&self, name: S) -> FunctionBuilder {
+        FunctionBuilder {
+            builder: self.clone(),
+            name: self.identifier(name),
+            parameters: vec![],
+            returns: None,
+        }
+    }
+
+    /// Starts a class. Unlike the other statements it has to be finished with
+    /// [build](ClassBuilder::build), since a class can have no definitions.
+    pub fn class<S: Into<String>>(&self, name: S) -> ClassBuilder {
+        ClassBuilder {
+            builder: self.clone(),
+            name: self.identifier(name),
+            parent: None,
+            defs: vec![],
+        }
+    }
+}
+
+/// Something that can be used as a block
+pub trait IntoBlock {
+    fn into_block(self, builder: &Builder) -> Node;
+}
+
+impl IntoBlock for Node {
+    /// Block nodes are used as is, and any other node becomes the only statement of a block
+    fn into_block(self, builder: &Builder) -> Node {
+        match self.node_type() {
+            NodeType::Block(_) => self,
+            _ => builder.node(NodeType::Block(vec![self])),
+        }
+    }
+}
+
+impl IntoBlock for Vec<Node> {
+    fn into_block(self, builder: &Builder) -> Node {
+        builder.node(NodeType::Block(self))
+    }
+}
+
+pub struct IfBuilder {
+    builder: Builder,
+    condition: Node,
+}
+
+impl IfBuilder {
+    pub fn then<B: IntoBlock>(self, block: B) -> IfThenBuilder {
+        let block = block.into_block(&self.builder);
+        IfThenBuilder {
+            builder: self.builder,
+            branches: vec![(self.condition, block)],
+        }
+    }
+}
+
+/// An `if` statement with at least one branch, which can be finished with
+/// [else_](IfThenBuilder::else_) or [build](IfThenBuilder::build)
+pub struct IfThenBuilder {
+    builder: Builder,
+    branches: Vec<(Node, Node)>,
+}
+
+impl IfThenBuilder {
+    pub fn elif<B: IntoBlock>(mut self, condition: Node, block: B) -> Self {
+        let block = block.into_block(&self.builder);
+        self.branches.push((condition, block));
+        self
+    }
+
+    pub fn else_<B: IntoBlock>(self, block: B) -> Node {
+        let block = block.into_block(&self.builder);
+        self.finish(Some(block))
+    }
+
+    /// Finishes the statement without an `else`
+    pub fn build(self) -> Node {
+        self.finish(None)
+    }
+
+    /// Each `elif` is an `if` statement in the `else` of the branch before it
+    fn finish(self, r#else: Option<Node>) -> Node {
+        let builder = self.builder;
+        self.branches
+            .into_iter()
+            .rev()
+            .fold(r#else, |r#else, (condition, block)| {
+                Some(builder.node(NodeType::If {
+                    condition,
+                    block,
+                    r#else,
+                }))
+            })
+            .unwrap()
+    }
+}
+
+impl From<IfThenBuilder> for Node {
+    fn from(builder: IfThenBuilder) -> Self {
+        builder.build()
+    }
+}
+
+pub struct WhileBuilder {
+    builder: Builder,
+    condition: Node,
+}
+
+impl WhileBuilder {
+    pub fn body<B: IntoBlock>(self, block: B) -> Node {
+        let block = block.into_block(&self.builder);
+        self.builder.node(NodeType::While {
+            condition: self.condition,
+            block,
+            r#else: None,
+        })
+    }
+
+    /// Finishes the loop with an `else` block, which runs if the loop doesn't `break`
+    pub fn body_else<B: IntoBlock, E: IntoBlock>(self, block: B, r#else: E) -> Node {
+        let block = block.into_block(&self.builder);
+        let r#else = r#else.into_block(&self.builder);
+        self.builder.node(NodeType::While {
+            condition: self.condition,
+            block,
+            r#else: Some(r#else),
+        })
+    }
+}
+
+pub struct ForBuilder {
+    builder: Builder,
+    identifier: Node,
+    iterator: Node,
+}
+
+impl ForBuilder {
+    pub fn body<B: IntoBlock>(self, block: B) -> Node {
+        let block = block.into_block(&self.builder);
+        self.builder.node(NodeType::For {
+            identifier: self.identifier,
+            iterator: self.iterator,
+            block,
+            r#else: None,
+        })
+    }
+
+    /// Finishes the loop with an `else` block, which runs if the loop doesn't `break`
+    pub fn body_else<B: IntoBlock, E: IntoBlock>(self, block: B, r#else: E) -> Node {
+        let block = block.into_block(&self.builder);
+        let r#else = r#else.into_block(&self.builder);
+        self.builder.node(NodeType::For {
+            identifier: self.identifier,
+            iterator: self.iterator,
+            block,
+            r#else: Some(r#else),
+        })
+    }
+}
+
+pub struct MatchBuilder {
+    builder: Builder,
+    subject: Node,
+    cases: Vec<Node>,
+}
+
+impl MatchBuilder {
+    /// `case pattern:`
+    pub fn case<B: IntoBlock>(self, pattern: Node, block: B) -> Self {
+        self.add_case(pattern, None, block)
+    }
+
+    /// `case pattern if guard:`
+    pub fn guarded_case<B: IntoBlock>(self, pattern: Node, guard: Node, block: B) -> Self {
+        self.add_case(pattern, Some(guard), block)
+    }
+
+    fn add_case<B: IntoBlock>(mut self, pattern: Node, guard: Option<Node>, block: B) -> Self {
+        let block = block.into_block(&self.builder);
+        let case = self.builder.node(NodeType::MatchCase {
+            pattern,
+            guard,
+            block,
+        });
+        self.cases.push(case);
+        self
+    }
+
+    pub fn build(self) -> Node {
+        self.builder.node(NodeType::Match {
+            subject: self.subject,
+            cases: self.cases,
+        })
+    }
+}
+
+pub struct TryBuilder {
+    builder: Builder,
+    block: Node,
+    handlers: Vec<Node>,
+    r#else: Option<Node>,
+    finally: Option<Node>,
+}
+
+impl TryBuilder {
+    /// `except:`, which catches every exception
+    pub fn except<B: IntoBlock>(self, block: B) -> Self {
+        self.add_handler(None, None, block)
+    }
+
+    /// `except class:`
+    pub fn except_class<B: IntoBlock>(self, class: Node, block: B) -> Self {
+        self.add_handler(Some(class), None, block)
+    }
+
+    /// `except class as name:`
+    pub fn except_as<S: Into<String>, B: IntoBlock>(self, class: Node, name: S, block: B) -> Self {
+        let name = self.builder.identifier(name);
+        self.add_handler(Some(class), Some(name), block)
+    }
+
+    fn add_handler<B: IntoBlock>(
+        mut self,
+        class: Option<Node>,
+        name: Option<Node>,
+        block: B,
+    ) -> Self {
+        let block = block.into_block(&self.builder);
+        let handler = self
+            .builder
+            .node(NodeType::ExceptHandler { class, name, block });
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn else_<B: IntoBlock>(mut self, block: B) -> Self {
+        self.r#else = Some(block.into_block(&self.builder));
+        self
+    }
+
+    pub fn finally<B: IntoBlock>(mut self, block: B) -> Self {
+        self.finally = Some(block.into_block(&self.builder));
+        self
+    }
+
+    pub fn build(self) -> Node {
+        self.builder.node(NodeType::Try {
+            block: self.block,
+            handlers: self.handlers,
+            r#else: self.r#else,
+            finally: self.finally,
+        })
+    }
+}
+
+pub struct FunctionBuilder {
+    builder: Builder,
+    name: Node,
+    parameters: Vec<Node>,
+    returns: Option<Node>,
+}
+
+impl FunctionBuilder {
+    pub fn parameter<S: Into<String>>(self, name: S) -> Self {
+        self.add_parameter(name, None)
+    }
+
+    pub fn parameter_with_default<S: Into<String>>(self, name: S, default: Node) -> Self {
+        self.add_parameter(name, Some(default))
+    }
+
+    /// `name: annotation`
+    pub fn annotated_parameter<S: Into<String>>(mut self, name: S, annotation: Node) -> Self {
+        let parameter = self.builder.node(NodeType::Parameter {
+            name: self.builder.identifier(name),
+            annotation: Some(annotation),
+            default: None,
+        });
+        self.parameters.push(parameter);
+        self
+    }
+
+    fn add_parameter<S: Into<String>>(mut self, name: S, default: Option<Node>) -> Self {
+        let parameter = self.builder.parameter(name, default);
+        self.parameters.push(parameter);
+        self
+    }
+
+    /// `-> annotation`
+    pub fn returns(mut self, annotation: Node) -> Self {
+        self.returns = Some(annotation);
+        self
+    }
+
+    pub fn body<B: IntoBlock>(self, block: B) -> Node {
+        let block = block.into_block(&self.builder);
+        self.builder.node(NodeType::Function {
+            name: self.name,
+            parameters: self.parameters,
+            returns: self.returns,
+            block,
+        })
+    }
+}
+
+pub struct ClassBuilder {
+    builder: Builder,
+    name: Node,
+    parent: Option<Node>,
+    defs: Vec<Node>,
+}
+
+impl ClassBuilder {
+    pub fn parent(mut self, parent: Node) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Adds a definition to the body of the class
+    pub fn def(mut self, definition: Node) -> Self {
+        self.defs.push(definition);
+        self
+    }
+
+    pub fn build(self) -> Node {
+        self.builder.node(NodeType::Class {
+            name: self.name,
+            parent: self.parent,
+            defs: self.defs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::to_sexpr;
+
+    #[test]
+    fn builds_trees() {
+        let b = Builder::new();
+        let x = || b.identifier("x");
+        let module = b.module(vec![
+            b.class("A")
+                .parent(b.identifier("B"))
+                .def(b.assign(b.identifier("y"), b.integer(1)))
+                .build(),
+            b.function("f")
+                .parameter("x")
+                .parameter_with_default("n", b.none())
+                .body(vec![
+                    b.if_(b.binary(x(), Operator::Lt, b.integer(0)))
+                        .then(b.return_(b.unary(Operator::Sub, x())))
+                        .elif(b.member(x(), "empty"), b.pass())
+                        .else_(b.raise(b.call(b.identifier("E"), vec![b.string("no")]))),
+                    b.for_(x(), b.list(vec![b.integer(1), b.integer(2)]))
+                        .body(b.while_(b.boolean(true)).body(b.break_())),
+                    b.assign(
+                        b.index(x(), b.integer(0)),
+                        b.dictionary(vec![(b.string("k"), b.tuple(vec![x()]))]),
+                    ),
+                ]),
+        ]);
+        // the tree that the parser produces for the same code
+        assert_eq!(
+            to_sexpr(&module, false),
+            "(Block [(Class (Identifier \"A\") (Identifier \"B\") [(Assignment (Identifier \"y\") \
+             _ (Integer 1))]) (Function (Identifier \"f\") [(Parameter (Identifier \"x\") _ _) \
+             (Parameter (Identifier \"n\") _ (None))] _ (Block [(If (BinaryExpression \
+             (Identifier \"x\") (Integer 0) (Operator Lt)) (Block [(Return (UnaryExpression \
+             (Identifier \"x\") (Operator Sub)))]) (If (QualifiedIdentifier (Identifier \"x\") \
+             (Identifier \"empty\")) (Block [(Pass)]) (Block [(Raise (FunctionCall (Identifier \
+             \"E\") [(String \"no\")]))]))) (For (Identifier \"x\") (List [(Integer 1) (Integer \
+             2)]) (Block [(While (Boolean true) (Block [(Break)]) _)]) _) (Assignment (Index \
+             (Identifier \"x\") (Integer 0)) _ (Dictionary [(DictionaryEntry (String \"k\") \
+             (Tuple [(Identifier \"x\")]))]))]))])"
+        );
+        assert_eq!(module.span(), &Span::native());
+    }
+}
@@ -0,0 +1,866 @@
+//! Dumps of [Node] trees, for debugging the frontend, golden-file tests and tools that want the
+//! tree without linking to basil.
+//!
+//! In [JSON](to_json), every node is an object with its `type`, its `span` and a key for each field
+//! of its [NodeType]:
+//!
+//! ```text
+//! {"type":"Return","span":{"file":0,"start":0,"end":8},"value":{"type":"Integer",...}}
+//! ```
+//!
+//! In the [S-expression](to_sexpr) form, every node is a list of its type, its span and its
+//! fields, in the order they're declared in [NodeType]. Lists of nodes are written in `[]`, a
+//! missing optional node is `_`, strings are quoted with Rust escapes, floats are written as
+//! fractions and operators by name:
+//!
+//! ```text
+//! (Assignment @0:0..5 (Identifier @0:0..1 "x") _ (Integer @0:4..5 1))
+//! ```
+//!
+//! Spans are written as `@file:start..end`, where `file` is the [index](FileId::index) of the
+//! source or `native`. They can be left out, and [from_sexpr] gives nodes without one a
+//! [native](Span::native) span. Trivia isn't part of either dump.
+
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+
+use basil_core::source_map::FileId;
+use basil_core::span::Span;
+use num_bigint::BigInt;
+use num_rational::Rational;
+
+use crate::node::{Node, NodeType};
+use crate::operators::Operator;
+
+/// A field of a [NodeType], borrowed from a node
+enum Field<'n> {
+    Node(&'n Node),
+    Optional(Option<&'n Node>),
+    Nodes(&'n [Node]),
+    String(&'n str),
+    Integer(&'n BigInt),
+    Float(&'n Rational),
+    Boolean(bool),
+    Operator(Operator),
+}
+
+/// The name and the named fields of a node type, in declaration order
+fn fields(node_type: &NodeType) -> (&'static str, Vec<(&'static str, Field<'_>)>) {
+    use Field::*;
+    match node_type {
+        NodeType::Identifier(name) => ("Identifier", vec![("name", String(name))]),
+        NodeType::Integer(value) => ("Integer", vec![("value", Integer(value))]),
+        NodeType::Float(value) => ("Float", vec![("value", Float(value))]),
+        NodeType::String(value) => ("String", vec![("value", String(value))]),
+        NodeType::FormatString(parts) => ("FormatString", vec![("parts", Nodes(parts))]),
+        NodeType::Interpolation { value, repr } => (
+            "Interpolation",
+            vec![("value", Node(value)), ("repr", Boolean(*repr))],
+        ),
+        NodeType::Boolean(value) => ("Boolean", vec![("value", Boolean(*value))]),
+        NodeType::None => ("None", vec![]),
+        NodeType::QualifiedIdentifier { parent, child } => (
+            "QualifiedIdentifier",
+            vec![("parent", Node(parent)), ("child", Node(child))],
+        ),
+        NodeType::Index { value, index } => (
+            "Index",
+            vec![("value", Node(value)), ("index", Node(index))],
+        ),
+        NodeType::Assignment { lhs, var_type, rhs } => (
+            "Assignment",
+            vec![
+                ("lhs", Node(lhs)),
+                ("var_type", Optional(var_type.as_ref())),
+                ("rhs", Node(rhs)),
+            ],
+        ),
+        NodeType::Function {
+            name,
+            parameters,
+            returns,
+            block,
+        } => (
+            "Function",
+            vec![
+                ("name", Node(name)),
+                ("parameters", Nodes(parameters)),
+                ("returns", Optional(returns.as_ref())),
+                ("block", Node(block)),
+            ],
+        ),
+        NodeType::Lambda { parameters, body } => (
+            "Lambda",
+            vec![("parameters", Nodes(parameters)), ("body", Node(body))],
+        ),
+        NodeType::Parameter {
+            name,
+            annotation,
+            default,
+        } => (
+            "Parameter",
+            vec![
+                ("name", Node(name)),
+                ("annotation", Optional(annotation.as_ref())),
+                ("default", Optional(default.as_ref())),
+            ],
+        ),
+        NodeType::FunctionCall { name, parameters } => (
+            "FunctionCall",
+            vec![("name", Node(name)), ("parameters", Nodes(parameters))],
+        ),
+        NodeType::KeywordArgument { name, value } => (
+            "KeywordArgument",
+            vec![("name", Node(name)), ("value", Node(value))],
+        ),
+        NodeType::BinaryExpression { lhs, rhs, op } => (
+            "BinaryExpression",
+            vec![("lhs", Node(lhs)), ("rhs", Node(rhs)), ("op", Node(op))],
+        ),
+        NodeType::UnaryExpression { val, op } => (
+            "UnaryExpression",
+            vec![("val", Node(val)), ("op", Node(op))],
+        ),
+        NodeType::Operator(op) => ("Operator", vec![("operator", Operator(*op))]),
+        NodeType::List(elements) => ("List", vec![("elements", Nodes(elements))]),
+        NodeType::Tuple(elements) => ("Tuple", vec![("elements", Nodes(elements))]),
+        NodeType::Set(elements) => ("Set", vec![("elements", Nodes(elements))]),
+        NodeType::Dictionary(entries) => ("Dictionary", vec![("entries", Nodes(entries))]),
+        NodeType::DictionaryEntry { key, value } => (
+            "DictionaryEntry",
+            vec![("key", Node(key)), ("value", Node(value))],
+        ),
+        NodeType::ListComprehension { element, clauses } => (
+            "ListComprehension",
+            vec![("element", Node(element)), ("clauses", Nodes(clauses))],
+        ),
+        NodeType::SetComprehension { element, clauses } => (
+            "SetComprehension",
+            vec![("element", Node(element)), ("clauses", Nodes(clauses))],
+        ),
+        NodeType::DictionaryComprehension { element, clauses } => (
+            "DictionaryComprehension",
+            vec![("element", Node(element)), ("clauses", Nodes(clauses))],
+        ),
+        NodeType::ComprehensionFor {
+            identifier,
+            iterator,
+        } => (
+            "ComprehensionFor",
+            vec![
+                ("identifier", Node(identifier)),
+                ("iterator", Node(iterator)),
+            ],
+        ),
+        NodeType::ComprehensionIf(condition) => {
+            ("ComprehensionIf", vec![("condition", Node(condition))])
+        }
+        NodeType::If {
+            condition,
+            block,
+            r#else,
+        } => (
+            "If",
+            vec![
+                ("condition", Node(condition)),
+                ("block", Node(block)),
+                ("else", Optional(r#else.as_ref())),
+            ],
+        ),
+        NodeType::While {
+            condition,
+            block,
+            r#else,
+        } => (
+            "While",
+            vec![
+                ("condition", Node(condition)),
+                ("block", Node(block)),
+                ("else", Optional(r#else.as_ref())),
+            ],
+        ),
+        NodeType::For {
+            identifier,
+            iterator,
+            block,
+            r#else,
+        } => (
+            "For",
+            vec![
+                ("identifier", Node(identifier)),
+                ("iterator", Node(iterator)),
+                ("block", Node(block)),
+                ("else", Optional(r#else.as_ref())),
+            ],
+        ),
+        NodeType::Match { subject, cases } => (
+            "Match",
+            vec![("subject", Node(subject)), ("cases", Nodes(cases))],
+        ),
+        NodeType::MatchCase {
+            pattern,
+            guard,
+            block,
+        } => (
+            "MatchCase",
+            vec![
+                ("pattern", Node(pattern)),
+                ("guard", Optional(guard.as_ref())),
+                ("block", Node(block)),
+            ],
+        ),
+        NodeType::Try {
+            block,
+            handlers,
+            r#else,
+            finally,
+        } => (
+            "Try",
+            vec![
+                ("block", Node(block)),
+                ("handlers", Nodes(handlers)),
+                ("else", Optional(r#else.as_ref())),
+                ("finally", Optional(finally.as_ref())),
+            ],
+        ),
+        NodeType::ExceptHandler { class, name, block } => (
+            "ExceptHandler",
+            vec![
+                ("class", Optional(class.as_ref())),
+                ("name", Optional(name.as_ref())),
+                ("block", Node(block)),
+            ],
+        ),
+        NodeType::Break => ("Break", vec![]),
+        NodeType::Continue => ("Continue", vec![]),
+        NodeType::Pass => ("Pass", vec![]),
+        NodeType::Yield(value) => ("Yield", vec![("value", Node(value))]),
+        NodeType::Return(value) => ("Return", vec![("value", Node(value))]),
+        NodeType::Raise(value) => ("Raise", vec![("value", Node(value))]),
+        NodeType::Class { name, parent, defs } => (
+            "Class",
+            vec![
+                ("name", Node(name)),
+                ("parent", Optional(parent.as_ref())),
+                ("defs", Nodes(defs)),
+            ],
+        ),
+        NodeType::Decorated {
+            decorators,
+            definition,
+        } => (
+            "Decorated",
+            vec![
+                ("decorators", Nodes(decorators)),
+                ("definition", Node(definition)),
+            ],
+        ),
+        NodeType::Block(statements) => ("Block", vec![("statements", Nodes(statements))]),
+        NodeType::Import(path) => ("Import", vec![("path", Node(path))]),
+        NodeType::Error => ("Error", vec![]),
+    }
+}
+
+const OPERATORS: [Operator; 14] = [
+    Operator::Eq,
+    Operator::Neq,
+    Operator::Lt,
+    Operator::Gt,
+    Operator::Lte,
+    Operator::Gte,
+    Operator::Add,
+    Operator::Sub,
+    Operator::Div,
+    Operator::Mul,
+    Operator::Rem,
+    Operator::And,
+    Operator::Or,
+    Operator::Not,
+];
+
+fn operator_name(op: Operator) -> String {
+    format!("{:?}", op)
+}
+
+/// Writes a tree as JSON
+pub fn to_json(node: &Node) -> String {
+    let mut output = String::new();
+    write_json(node, &mut output);
+    output
+}
+
+fn write_json(node: &Node, output: &mut String) {
+    let span = node.span();
+    let (name, fields) = fields(node.node_type());
+    let file = if span.file().is_native() {
+        "null".to_string()
+    } else {
+        span.file().index().to_string()
+    };
+    let _ = write!(
+        output,
+        "{{\"type\":\"{}\",\"span\":{{\"file\":{},\"start\":{},\"end\":{}}}",
+        name,
+        file,
+        span.start(),
+        span.end()
+    );
+    for (key, field) in fields {
+        let _ = write!(output, ",\"{}\":", key);
+        match field {
+            Field::Node(node) | Field::Optional(Some(node)) => write_json(node, output),
+            Field::Optional(None) => output.push_str("null"),
+            Field::Nodes(nodes) => {
+                output.push('[');
+                for (index, node) in nodes.iter().enumerate() {
+                    if index > 0 {
+                        output.push(',');
+                    }
+                    write_json(node, output);
+                }
+                output.push(']');
+            }
+            Field::String(string) => write_json_string(string, output),
+            Field::Integer(integer) => {
+                let _ = write!(output, "{}", integer);
+            }
+            Field::Float(float) => {
+                let _ = write!(
+                    output,
+                    "{{\"numerator\":{},\"denominator\":{}}}",
+                    float.numer(),
+                    float.denom()
+                );
+            }
+            Field::Boolean(boolean) => {
+                let _ = write!(output, "{}", boolean);
+            }
+            Field::Operator(op) => write_json_string(&operator_name(op), output),
+        }
+    }
+    output.push('}');
+}
+
+fn write_json_string(string: &str, output: &mut String) {
+    output.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// Writes a tree as an S-expression on a single line, with or without the spans of its nodes
+pub fn to_sexpr(node: &Node, spans: bool) -> String {
+    let mut output = String::new();
+    write_sexpr(node, spans, &mut output);
+    output
+}
+
+fn write_sexpr(node: &Node, spans: bool, output: &mut String) {
+    let (name, fields) = fields(node.node_type());
+    output.push('(');
+    output.push_str(name);
+    if spans {
+        let span = node.span();
+        if span.file().is_native() {
+            let _ = write!(output, " @native:{}..{}", span.start(), span.end());
+        } else {
+            let _ = write!(
+                output,
+                " @{}:{}..{}",
+                span.file().index(),
+                span.start(),
+                span.end()
+            );
+        }
+    }
+    for (_, field) in fields {
+        output.push(' ');
+        match field {
+            Field::Node(node) | Field::Optional(Some(node)) => write_sexpr(node, spans, output),
+            Field::Optional(None) => output.push('_'),
+            Field::Nodes(nodes) => {
+                output.push('[');
+                for (index, node) in nodes.iter().enumerate() {
+                    if index > 0 {
+                        output.push(' ');
+                    }
+                    write_sexpr(node, spans, output);
+                }
+                output.push(']');
+            }
+            Field::String(string) => {
+                let _ = write!(output, "{:?}", string);
+            }
+            Field::Integer(integer) => {
+                let _ = write!(output, "{}", integer);
+            }
+            Field::Float(float) => {
+                let _ = write!(output, "{}/{}", float.numer(), float.denom());
+            }
+            Field::Boolean(boolean) => {
+                let _ = write!(output, "{}", boolean);
+            }
+            Field::Operator(op) => output.push_str(&operator_name(op)),
+        }
+    }
+    output.push(')');
+}
+
+/// Reads a tree written by [to_sexpr]
+pub fn from_sexpr(source: &str) -> Result<Node, SExprError> {
+    let mut reader = Reader {
+        source,
+        position: 0,
+    };
+    let node = reader.node()?;
+    reader.skip_whitespace();
+    if reader.position < source.len() {
+        return Err(reader.error("expected the end of the input"));
+    }
+    Ok(node)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SExprError {
+    message: String,
+    offset: usize,
+}
+
+impl SExprError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte offset in the input where the error was found
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for SExprError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for SExprError {}
+
+struct Reader<'s> {
+    source: &'s str,
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn error<S: Into<String>>(&self, message: S) -> SExprError {
+        SExprError {
+            message: message.into(),
+            offset: self.position,
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SExprError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c)))
+        }
+    }
+
+    /// Reads everything up to the next whitespace or bracket
+    fn atom(&mut self) -> Result<&str, SExprError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let length = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || "()[]".contains(c))
+            .unwrap_or(self.rest().len());
+        self.position += length;
+        if length == 0 {
+            return Err(self.error("expected a value"));
+        }
+        Ok(&self.source[start..self.position])
+    }
+
+    fn parsed<T: FromStr>(&mut self, what: &str) -> Result<T, SExprError> {
+        let start = self.position;
+        let atom = self.atom()?;
+        atom.parse().map_err(|_| SExprError {
+            message: format!("expected {}, found `{}`", what, atom),
+            offset: start,
+        })
+    }
+
+    fn node(&mut self) -> Result<Node, SExprError> {
+        self.expect('(')?;
+        self.skip_whitespace();
+        let start = self.position;
+        let name = self.atom()?.to_string();
+        let span = self.span()?;
+        let node_type = match name.as_str() {
+            "Identifier" => NodeType::Identifier(self.string()?),
+            "Integer" => NodeType::Integer(self.parsed("an integer")?),
+            "Float" => NodeType::Float(self.parsed("a fraction")?),
+            "String" => NodeType::String(self.string()?),
+            "FormatString" => NodeType::FormatString(self.nodes()?),
+            "Interpolation" => NodeType::Interpolation {
+                value: self.node()?,
+                repr: self.parsed("a boolean")?,
+            },
+            "Boolean" => NodeType::Boolean(self.parsed("a boolean")?),
+            "None" => NodeType::None,
+            "QualifiedIdentifier" => NodeType::QualifiedIdentifier {
+                parent: self.node()?,
+                child: self.node()?,
+            },
+            "Index" => NodeType::Index {
+                value: self.node()?,
+                index: self.node()?,
+            },
+            "Assignment" => NodeType::Assignment {
+                lhs: self.node()?,
+                var_type: self.optional()?,
+                rhs: self.node()?,
+            },
+            "Function" => NodeType::Function {
+                name: self.node()?,
+                parameters: self.nodes()?,
+                returns: self.optional()?,
+                block: self.node()?,
+            },
+            "Lambda" => NodeType::Lambda {
+                parameters: self.nodes()?,
+                body: self.node()?,
+            },
+            "Parameter" => NodeType::Parameter {
+                name: self.node()?,
+                annotation: self.optional()?,
+                default: self.optional()?,
+            },
+            "FunctionCall" => NodeType::FunctionCall {
+                name: self.node()?,
+                parameters: self.nodes()?,
+            },
+            "KeywordArgument" => NodeType::KeywordArgument {
+                name: self.node()?,
+                value: self.node()?,
+            },
+            "BinaryExpression" => NodeType::BinaryExpression {
+                lhs: self.node()?,
+                rhs: self.node()?,
+                op: self.node()?,
+            },
+            "UnaryExpression" => NodeType::UnaryExpression {
+                val: self.node()?,
+                op: self.node()?,
+            },
+            "Operator" => NodeType::Operator(self.operator()?),
+            "List" => NodeType::List(self.nodes()?),
+            "Tuple" => NodeType::Tuple(self.nodes()?),
+            "Set" => NodeType::Set(self.nodes()?),
+            "Dictionary" => NodeType::Dictionary(self.nodes()?),
+            "DictionaryEntry" => NodeType::DictionaryEntry {
+                key: self.node()?,
+                value: self.node()?,
+            },
+            "ListComprehension" => NodeType::ListComprehension {
+                element: self.node()?,
+                clauses: self.nodes()?,
+            },
+            "SetComprehension" => NodeType::SetComprehension {
+                element: self.node()?,
+                clauses: self.nodes()?,
+            },
+            "DictionaryComprehension" => NodeType::DictionaryComprehension {
+                element: self.node()?,
+                clauses: self.nodes()?,
+            },
+            "ComprehensionFor" => NodeType::ComprehensionFor {
+                identifier: self.node()?,
+                iterator: self.node()?,
+            },
+            "ComprehensionIf" => NodeType::ComprehensionIf(self.node()?),
+            "If" => NodeType::If {
+                condition: self.node()?,
+                block: self.node()?,
+                r#else: self.optional()?,
+            },
+            "While" => NodeType::While {
+                condition: self.node()?,
+                block: self.node()?,
+                r#else: self.optional()?,
+            },
+            "For" => NodeType::For {
+                identifier: self.node()?,
+                iterator: self.node()?,
+                block: self.node()?,
+                r#else: self.optional()?,
+            },
+            "Match" => NodeType::Match {
+                subject: self.node()?,
+                cases: self.nodes()?,
+            },
+            "MatchCase" => NodeType::MatchCase {
+                pattern: self.node()?,
+                guard: self.optional()?,
+                block: self.node()?,
+            },
+            "Try" => NodeType::Try {
+                block: self.node()?,
+                handlers: self.nodes()?,
+                r#else: self.optional()?,
+                finally: self.optional()?,
+            },
+            "ExceptHandler" => NodeType::ExceptHandler {
+                class: self.optional()?,
+                name: self.optional()?,
+                block: self.node()?,
+            },
+            "Break" => NodeType::Break,
+            "Continue" => NodeType::Continue,
+            "Pass" => NodeType::Pass,
+            "Yield" => NodeType::Yield(self.node()?),
+            "Return" => NodeType::Return(self.node()?),
+            "Raise" => NodeType::Raise(self.node()?),
+            "Class" => NodeType::Class {
+                name: self.node()?,
+                parent: self.optional()?,
+                defs: self.nodes()?,
+            },
+            "Decorated" => NodeType::Decorated {
+                decorators: self.nodes()?,
+                definition: self.node()?,
+            },
+            "Block" => NodeType::Block(self.nodes()?),
+            "Import" => NodeType::Import(self.node()?),
+            "Error" => NodeType::Error,
+            other => {
+                return Err(SExprError {
+                    message: format!("unknown node type `{}`", other),
+                    offset: start,
+                })
+            }
+        };
+        self.expect(')')?;
+        Ok(Node::new(span, node_type))
+    }
+
+    /// Reads an optional `@file:start..end` span, giving a native span if there isn't one
+    fn span(&mut self) -> Result<Span, SExprError> {
+        if !self.eat('@') {
+            return Ok(Span::native());
+        }
+        let start = self.position;
+        let atom = self.atom()?;
+        let invalid = || SExprError {
+            message: format!("expected a span, found `@{}`", atom),
+            offset: start,
+        };
+        let (file, range) = atom.split_once(':').ok_or_else(invalid)?;
+        let (begin, end) = range.split_once("..").ok_or_else(invalid)?;
+        let file = match file {
+            "native" => FileId::NATIVE,
+            index => FileId::from_index(index.parse().map_err(|_| invalid())?),
+        };
+        let begin: usize = begin.parse().map_err(|_| invalid())?;
+        let end: usize = end.parse().map_err(|_| invalid())?;
+        if end < begin {
+            return Err(invalid());
+        }
+        Ok(Span::new(file, begin, end))
+    }
+
+    fn optional(&mut self) -> Result<Option<Node>, SExprError> {
+        if self.eat('_') {
+            Ok(None)
+        } else {
+            self.node().map(Some)
+        }
+    }
+
+    fn nodes(&mut self) -> Result<Vec<Node>, SExprError> {
+        self.expect('[')?;
+        let mut nodes = vec![];
+        while !self.eat(']') {
+            nodes.push(self.node()?);
+        }
+        Ok(nodes)
+    }
+
+    fn operator(&mut self) -> Result<Operator, SExprError> {
+        let start = self.position;
+        let name = self.atom()?;
+        OPERATORS
+            .iter()
+            .copied()
+            .find(|op| operator_name(*op) == name)
+            .ok_or_else(|| SExprError {
+                message: format!("unknown operator `{}`", name),
+                offset: start,
+            })
+    }
+
+    /// Reads a string quoted with the escapes of Rust's `Debug`
+    fn string(&mut self) -> Result<String, SExprError> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.position += c.len_utf8();
+            match c {
+                '"' => return Ok(string),
+                '\\' => string.push(self.escape()?),
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, SExprError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.position += c.len_utf8();
+        let escaped = match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' | '"' | '\'' => c,
+            'u' => {
+                let rest = self.rest();
+                let digits = rest
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.split_once('}'))
+                    .map(|(digits, _)| digits)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+                let escaped = u32::from_str_radix(digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?;
+                self.position += digits.len() + 2;
+                escaped
+            }
+            other => return Err(self.error(format!("invalid escape `\\{}`", other))),
+        };
+        Ok(escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps() {
+        // x = -1
+        let module = from_sexpr(
+            "(Block @0:0..7 [(Assignment @0:0..6 (Identifier @0:0..1 \"x\") _ \
+             (UnaryExpression @0:4..6 (Integer @0:5..6 1) (Operator @0:4..5 Sub)))])",
+        )
+        .unwrap();
+        assert_eq!(
+            to_sexpr(&module, false),
+            "(Block [(Assignment (Identifier \"x\") _ (UnaryExpression (Integer 1) \
+             (Operator Sub)))])"
+        );
+        assert_eq!(
+            to_sexpr(&module, true),
+            "(Block @0:0..7 [(Assignment @0:0..6 (Identifier @0:0..1 \"x\") _ \
+             (UnaryExpression @0:4..6 (Integer @0:5..6 1) (Operator @0:4..5 Sub)))])"
+        );
+        assert_eq!(
+            to_json(&module),
+            "{\"type\":\"Block\",\"span\":{\"file\":0,\"start\":0,\"end\":7},\"statements\":[\
+             {\"type\":\"Assignment\",\"span\":{\"file\":0,\"start\":0,\"end\":6},\
+             \"lhs\":{\"type\":\"Identifier\",\"span\":{\"file\":0,\"start\":0,\"end\":1},\"name\":\"x\"},\
+             \"var_type\":null,\
+             \"rhs\":{\"type\":\"UnaryExpression\",\"span\":{\"file\":0,\"start\":4,\"end\":6},\
+             \"val\":{\"type\":\"Integer\",\"span\":{\"file\":0,\"start\":5,\"end\":6},\"value\":1},\
+             \"op\":{\"type\":\"Operator\",\"span\":{\"file\":0,\"start\":4,\"end\":5},\"operator\":\"Sub\"}}}]}"
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        // the tree the parser produces for a module that uses every kind of node
+        let dump = "(Block [(Import (QualifiedIdentifier (Identifier \"a\") (Identifier \"b\"))) \
+             (Class (Identifier \"A\") (Identifier \"B\") [(Assignment (Identifier \"x\") _ \
+             (Float 3/2)) (Function (Identifier \"f\") [(Parameter (Identifier \"self\") _ _) \
+             (Parameter (Identifier \"n\") _ (None))] _ (Block [(Yield (Index (Identifier \
+             \"self\") (Identifier \"n\")))]))]) (If (UnaryExpression (Identifier \"x\") \
+             (Operator Not)) (Block [(Pass)]) (If (Dictionary []) (Block [(Break)]) (Block \
+             [(Raise (FunctionCall (Identifier \"E\") [(FormatString [(String \"\\t\\\"\") \
+             (Interpolation (Identifier \"x\") true) (String \"\\\" { \u{e9}\")])]))]))) (While \
+             (BinaryExpression (BinaryExpression (Identifier \"x\") (Integer 2) (Operator Neq)) \
+             (Boolean true) (Operator Or)) (Block [(Assignment (Identifier \"y\") _ \
+             (DictionaryComprehension (DictionaryEntry (Identifier \"k\") (ListComprehension \
+             (Identifier \"v\") [(ComprehensionFor (Identifier \"v\") (Identifier \"x\")) \
+             (ComprehensionIf (Identifier \"v\"))])) [(ComprehensionFor (Identifier \"k\") (Set \
+             [(Integer 1) (Tuple [(Integer 2)])]))]))]) _) (For (Identifier \"i\") (FunctionCall \
+             (Identifier \"range\") [(Integer 10) (KeywordArgument (Identifier \"step\") (Integer \
+             2))]) (Block [(FunctionCall (Identifier \"print\") [(Dictionary [(DictionaryEntry \
+             (String \"a\") (BinaryExpression (Identifier \"i\") (Integer 3) (Operator \
+             Rem)))])])]) _)])";
+        let module = from_sexpr(dump).unwrap();
+        assert_eq!(to_sexpr(&module, false), dump);
+        let with_spans = to_sexpr(&module, true);
+        let read = from_sexpr(&with_spans).unwrap();
+        assert_eq!(to_sexpr(&read, true), with_spans);
+        assert_eq!(to_json(&read), to_json(&module));
+
+        let read = from_sexpr("(Return (String \"\\u{1f600}\\n\"))").unwrap();
+        assert_eq!(read.span(), &Span::native());
+        assert_eq!(to_sexpr(&read, false), "(Return (String \"\u{1f600}\\n\"))");
+    }
+
+    #[test]
+    fn errors() {
+        let error = |dump: &str| from_sexpr(dump).unwrap_err().to_string();
+        assert_eq!(error("(Block [(Pass)"), "expected `(` at byte 14");
+        assert_eq!(
+            error("(Plus (Integer 1))"),
+            "unknown node type `Plus` at byte 1"
+        );
+        assert_eq!(
+            error("(Integer @0:5..1 1)"),
+            "expected a span, found `@0:5..1` at byte 10"
+        );
+        assert_eq!(error("(Operator Pow)"), "unknown operator `Pow` at byte 10");
+        assert_eq!(error("(None) x"), "expected the end of the input at byte 7");
+    }
+}
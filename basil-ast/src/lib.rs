@@ -1,3 +1,6 @@
+pub mod builder;
+pub mod dump;
 pub mod node;
 pub mod operators;
-pub mod builder;
\ No newline at end of file
+pub mod trivia;
+pub mod visit;
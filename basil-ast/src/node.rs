@@ -1,34 +1,572 @@
 use crate::operators::Operator;
+use crate::trivia::Trivia;
 use basil_core::span::Span;
-use std::fmt::Display;
+use num_bigint::BigInt;
+use num_rational::Rational;
+
+/// Collects the children of a node type. The bindings are references or mutable references
+/// depending on the reference that's matched on, so this works for both
+/// [children](Node::children) and [children_mut](Node::children_mut).
+macro_rules! children {
+    ($node_type:expr) => {{
+        let mut children = Vec::new();
+        match $node_type {
+            NodeType::Identifier(_)
+            | NodeType::Integer(_)
+            | NodeType::Float(_)
+            | NodeType::String(_)
+            | NodeType::Boolean(_)
+            | NodeType::None
+            | NodeType::Operator(_)
+            | NodeType::Break
+            | NodeType::Continue
+            | NodeType::Pass
+            | NodeType::Error => {}
+            NodeType::FormatString(nodes)
+            | NodeType::List(nodes)
+            | NodeType::Tuple(nodes)
+            | NodeType::Set(nodes)
+            | NodeType::Dictionary(nodes)
+            | NodeType::Block(nodes) => children.extend(nodes),
+            NodeType::Interpolation { value, .. }
+            | NodeType::ComprehensionIf(value)
+            | NodeType::Yield(value)
+            | NodeType::Return(value)
+            | NodeType::Raise(value)
+            | NodeType::Import(value) => children.push(value),
+            NodeType::QualifiedIdentifier {
+                parent: first,
+                child: second,
+            }
+            | NodeType::Index {
+                value: first,
+                index: second,
+            }
+            | NodeType::DictionaryEntry {
+                key: first,
+                value: second,
+            }
+            | NodeType::KeywordArgument {
+                name: first,
+                value: second,
+            }
+            | NodeType::UnaryExpression {
+                op: first,
+                val: second,
+            }
+            | NodeType::ComprehensionFor {
+                identifier: first,
+                iterator: second,
+            } => {
+                children.push(first);
+                children.push(second);
+            }
+            NodeType::Assignment { lhs, var_type, rhs } => {
+                children.push(lhs);
+                children.extend(var_type);
+                children.push(rhs);
+            }
+            NodeType::Function {
+                name,
+                parameters,
+                returns,
+                block,
+            } => {
+                children.push(name);
+                children.extend(parameters);
+                children.extend(returns);
+                children.push(block);
+            }
+            NodeType::Lambda { parameters, body } => {
+                children.extend(parameters);
+                children.push(body);
+            }
+            NodeType::Parameter {
+                name,
+                annotation,
+                default,
+            } => {
+                children.push(name);
+                children.extend(annotation);
+                children.extend(default);
+            }
+            NodeType::FunctionCall { name, parameters } => {
+                children.push(name);
+                children.extend(parameters);
+            }
+            NodeType::BinaryExpression { lhs, rhs, op } => {
+                children.push(lhs);
+                children.push(op);
+                children.push(rhs);
+            }
+            NodeType::ListComprehension { element, clauses }
+            | NodeType::SetComprehension { element, clauses }
+            | NodeType::DictionaryComprehension { element, clauses } => {
+                children.push(element);
+                children.extend(clauses);
+            }
+            NodeType::If {
+                condition,
+                block,
+                r#else,
+            } => {
+                children.push(condition);
+                children.push(block);
+                children.extend(r#else);
+            }
+            NodeType::While {
+                condition,
+                block,
+                r#else,
+            } => {
+                children.push(condition);
+                children.push(block);
+                children.extend(r#else);
+            }
+            NodeType::For {
+                identifier,
+                iterator,
+                block,
+                r#else,
+            } => {
+                children.push(identifier);
+                children.push(iterator);
+                children.push(block);
+                children.extend(r#else);
+            }
+            NodeType::Match { subject, cases } => {
+                children.push(subject);
+                children.extend(cases);
+            }
+            NodeType::MatchCase {
+                pattern,
+                guard,
+                block,
+            } => {
+                children.push(pattern);
+                children.extend(guard);
+                children.push(block);
+            }
+            NodeType::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                children.push(block);
+                children.extend(handlers);
+                children.extend(r#else);
+                children.extend(finally);
+            }
+            NodeType::ExceptHandler { class, name, block } => {
+                children.extend(class);
+                children.extend(name);
+                children.push(block);
+            }
+            NodeType::Class { name, parent, defs } => {
+                children.push(name);
+                children.extend(parent);
+                children.extend(defs);
+            }
+            NodeType::Decorated {
+                decorators,
+                definition,
+            } => {
+                children.extend(decorators);
+                children.push(definition);
+            }
+        }
+        children
+    }};
+}
 
 #[derive(Debug)]
 pub struct Node {
     span: Span,
     node_type: Box<NodeType>,
+    trivia: Trivia,
+}
+
+impl Node {
+    pub fn new(span: Span, node_type: NodeType) -> Self {
+        Node {
+            span,
+            node_type: Box::new(node_type),
+            trivia: Trivia::default(),
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn node_type(&self) -> &NodeType {
+        &self.node_type
+    }
+
+    pub fn node_type_mut(&mut self) -> &mut NodeType {
+        &mut self.node_type
+    }
+
+    pub fn span_mut(&mut self) -> &mut Span {
+        &mut self.span
+    }
+
+    /// The comments and blank lines attached to this node
+    pub fn trivia(&self) -> &Trivia {
+        &self.trivia
+    }
+
+    pub fn trivia_mut(&mut self) -> &mut Trivia {
+        &mut self.trivia
+    }
+
+    /// The direct children of this node, in the order they appear in the source
+    pub fn children(&self) -> Vec<&Node> {
+        children!(self.node_type.as_ref())
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        children!(self.node_type.as_mut())
+    }
+
+    pub fn into_node_type(self) -> NodeType {
+        *self.node_type
+    }
+
+    /// Replaces each direct child of this node with the result of `f`, in the order they appear in
+    /// the source. The span and trivia of this node are kept.
+    pub fn map_children<F: FnMut(Node) -> Node>(self, mut f: F) -> Node {
+        fn all<F: FnMut(Node) -> Node>(nodes: Vec<Node>, f: &mut F) -> Vec<Node> {
+            nodes.into_iter().map(f).collect()
+        }
+        let node_type = match *self.node_type {
+            leaf @ (NodeType::Identifier(_)
+            | NodeType::Integer(_)
+            | NodeType::Float(_)
+            | NodeType::String(_)
+            | NodeType::Boolean(_)
+            | NodeType::None
+            | NodeType::Operator(_)
+            | NodeType::Break
+            | NodeType::Continue
+            | NodeType::Pass
+            | NodeType::Error) => leaf,
+            NodeType::FormatString(nodes) => NodeType::FormatString(all(nodes, &mut f)),
+            NodeType::List(nodes) => NodeType::List(all(nodes, &mut f)),
+            NodeType::Tuple(nodes) => NodeType::Tuple(all(nodes, &mut f)),
+            NodeType::Set(nodes) => NodeType::Set(all(nodes, &mut f)),
+            NodeType::Dictionary(nodes) => NodeType::Dictionary(all(nodes, &mut f)),
+            NodeType::Block(nodes) => NodeType::Block(all(nodes, &mut f)),
+            NodeType::Interpolation { value, repr } => NodeType::Interpolation {
+                value: f(value),
+                repr,
+            },
+            NodeType::ComprehensionIf(value) => NodeType::ComprehensionIf(f(value)),
+            NodeType::Yield(value) => NodeType::Yield(f(value)),
+            NodeType::Return(value) => NodeType::Return(f(value)),
+            NodeType::Raise(value) => NodeType::Raise(f(value)),
+            NodeType::Import(value) => NodeType::Import(f(value)),
+            NodeType::QualifiedIdentifier { parent, child } => {
+                let parent = f(parent);
+                NodeType::QualifiedIdentifier {
+                    parent,
+                    child: f(child),
+                }
+            }
+            NodeType::Index { value, index } => {
+                let value = f(value);
+                NodeType::Index {
+                    value,
+                    index: f(index),
+                }
+            }
+            NodeType::DictionaryEntry { key, value } => {
+                let key = f(key);
+                NodeType::DictionaryEntry {
+                    key,
+                    value: f(value),
+                }
+            }
+            NodeType::KeywordArgument { name, value } => {
+                let name = f(name);
+                NodeType::KeywordArgument {
+                    name,
+                    value: f(value),
+                }
+            }
+            NodeType::UnaryExpression { op, val } => {
+                let op = f(op);
+                NodeType::UnaryExpression { op, val: f(val) }
+            }
+            NodeType::While {
+                condition,
+                block,
+                r#else,
+            } => {
+                let condition = f(condition);
+                let block = f(block);
+                NodeType::While {
+                    condition,
+                    block,
+                    r#else: r#else.map(&mut f),
+                }
+            }
+            NodeType::ComprehensionFor {
+                identifier,
+                iterator,
+            } => {
+                let identifier = f(identifier);
+                NodeType::ComprehensionFor {
+                    identifier,
+                    iterator: f(iterator),
+                }
+            }
+            NodeType::Assignment { lhs, var_type, rhs } => {
+                let lhs = f(lhs);
+                let var_type = var_type.map(&mut f);
+                NodeType::Assignment {
+                    lhs,
+                    var_type,
+                    rhs: f(rhs),
+                }
+            }
+            NodeType::Function {
+                name,
+                parameters,
+                returns,
+                block,
+            } => {
+                let name = f(name);
+                let parameters = all(parameters, &mut f);
+                let returns = returns.map(&mut f);
+                NodeType::Function {
+                    name,
+                    parameters,
+                    returns,
+                    block: f(block),
+                }
+            }
+            NodeType::Lambda { parameters, body } => {
+                let parameters = all(parameters, &mut f);
+                NodeType::Lambda {
+                    parameters,
+                    body: f(body),
+                }
+            }
+            NodeType::Parameter {
+                name,
+                annotation,
+                default,
+            } => {
+                let name = f(name);
+                let annotation = annotation.map(&mut f);
+                NodeType::Parameter {
+                    name,
+                    annotation,
+                    default: default.map(&mut f),
+                }
+            }
+            NodeType::FunctionCall { name, parameters } => {
+                let name = f(name);
+                NodeType::FunctionCall {
+                    name,
+                    parameters: all(parameters, &mut f),
+                }
+            }
+            NodeType::BinaryExpression { lhs, rhs, op } => {
+                let lhs = f(lhs);
+                let op = f(op);
+                NodeType::BinaryExpression {
+                    lhs,
+                    op,
+                    rhs: f(rhs),
+                }
+            }
+            NodeType::ListComprehension { element, clauses } => {
+                let element = f(element);
+                NodeType::ListComprehension {
+                    element,
+                    clauses: all(clauses, &mut f),
+                }
+            }
+            NodeType::SetComprehension { element, clauses } => {
+                let element = f(element);
+                NodeType::SetComprehension {
+                    element,
+                    clauses: all(clauses, &mut f),
+                }
+            }
+            NodeType::DictionaryComprehension { element, clauses } => {
+                let element = f(element);
+                NodeType::DictionaryComprehension {
+                    element,
+                    clauses: all(clauses, &mut f),
+                }
+            }
+            NodeType::If {
+                condition,
+                block,
+                r#else,
+            } => {
+                let condition = f(condition);
+                let block = f(block);
+                NodeType::If {
+                    condition,
+                    block,
+                    r#else: r#else.map(&mut f),
+                }
+            }
+            NodeType::For {
+                identifier,
+                iterator,
+                block,
+                r#else,
+            } => {
+                let identifier = f(identifier);
+                let iterator = f(iterator);
+                let block = f(block);
+                NodeType::For {
+                    identifier,
+                    iterator,
+                    block,
+                    r#else: r#else.map(&mut f),
+                }
+            }
+            NodeType::Match { subject, cases } => {
+                let subject = f(subject);
+                NodeType::Match {
+                    subject,
+                    cases: all(cases, &mut f),
+                }
+            }
+            NodeType::MatchCase {
+                pattern,
+                guard,
+                block,
+            } => {
+                let pattern = f(pattern);
+                let guard = guard.map(&mut f);
+                NodeType::MatchCase {
+                    pattern,
+                    guard,
+                    block: f(block),
+                }
+            }
+            NodeType::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                let block = f(block);
+                let handlers = all(handlers, &mut f);
+                let r#else = r#else.map(&mut f);
+                NodeType::Try {
+                    block,
+                    handlers,
+                    r#else,
+                    finally: finally.map(&mut f),
+                }
+            }
+            NodeType::ExceptHandler { class, name, block } => {
+                let class = class.map(&mut f);
+                let name = name.map(&mut f);
+                NodeType::ExceptHandler {
+                    class,
+                    name,
+                    block: f(block),
+                }
+            }
+            NodeType::Class { name, parent, defs } => {
+                let name = f(name);
+                let parent = parent.map(&mut f);
+                NodeType::Class {
+                    name,
+                    parent,
+                    defs: all(defs, &mut f),
+                }
+            }
+            NodeType::Decorated {
+                decorators,
+                definition,
+            } => {
+                let decorators = all(decorators, &mut f);
+                NodeType::Decorated {
+                    decorators,
+                    definition: f(definition),
+                }
+            }
+        };
+        Node {
+            span: self.span,
+            node_type: Box::new(node_type),
+            trivia: self.trivia,
+        }
+    }
 }
 
 /// The type of the node, which determines the children of the node
 #[derive(Debug)]
 pub enum NodeType {
     Identifier(String),
+    Integer(BigInt),
+    Float(Rational),
+    String(String),
+    /// An interpolated string, made of [String](NodeType::String) and
+    /// [Interpolation](NodeType::Interpolation) nodes
+    FormatString(Vec<Node>),
+    /// An expression embedded in an interpolated string, converted with `repr` if `repr` is true
+    /// and `str` otherwise
+    Interpolation {
+        value: Node,
+        repr: bool,
+    },
+    Boolean(bool),
+    None,
     QualifiedIdentifier {
         parent: Node,
         child: Node,
     },
+    Index {
+        value: Node,
+        index: Node,
+    },
+    /// `lhs = rhs`, or `lhs: var_type = rhs` with a type annotation
     Assignment {
         lhs: Node,
         var_type: Option<Node>,
         rhs: Node,
     },
+    /// `def name(parameters) -> returns:`, where every parameter is a
+    /// [Parameter](NodeType::Parameter)
     Function {
         name: Node,
         parameters: Vec<Node>,
+        returns: Option<Node>,
+        block: Node,
+    },
+    /// `lambda parameters: body`, where every parameter is a [Parameter](NodeType::Parameter)
+    Lambda {
+        parameters: Vec<Node>,
+        body: Node,
+    },
+    /// A parameter in a function definition, as in `name: annotation = default`. Lambda
+    /// parameters can't be annotated.
+    Parameter {
+        name: Node,
+        annotation: Option<Node>,
+        default: Option<Node>,
     },
     FunctionCall {
         name: Node,
         parameters: Vec<Node>,
     },
+    /// A `name=value` argument in a function call
+    KeywordArgument {
+        name: Node,
+        value: Node,
+    },
     BinaryExpression {
         lhs: Node,
         rhs: Node,
@@ -39,27 +577,103 @@ pub enum NodeType {
         op: Node,
     },
     Operator(Operator),
+    /// `[a, b]`
+    List(Vec<Node>),
+    /// `(a, b)`
+    Tuple(Vec<Node>),
+    /// `{a, b}`
+    Set(Vec<Node>),
+    /// `{k: v}`, where every element is a [DictionaryEntry](NodeType::DictionaryEntry)
+    Dictionary(Vec<Node>),
+    DictionaryEntry {
+        key: Node,
+        value: Node,
+    },
+    /// `[element for x in y if z]`. The clauses are [ComprehensionFor](NodeType::ComprehensionFor)
+    /// and [ComprehensionIf](NodeType::ComprehensionIf) nodes, in the order they were written.
+    ListComprehension {
+        element: Node,
+        clauses: Vec<Node>,
+    },
+    SetComprehension {
+        element: Node,
+        clauses: Vec<Node>,
+    },
+    /// `{k: v for x in y}`, where the element is a [DictionaryEntry](NodeType::DictionaryEntry)
+    DictionaryComprehension {
+        element: Node,
+        clauses: Vec<Node>,
+    },
+    ComprehensionFor {
+        identifier: Node,
+        iterator: Node,
+    },
+    ComprehensionIf(Node),
     If {
         condition: Node,
         block: Node,
         r#else: Option<Node>,
     },
+    /// `while condition:`. The `else` block runs when the loop finishes without a `break`.
     While {
         condition: Node,
         block: Node,
+        r#else: Option<Node>,
     },
+    /// `for identifier in iterator:`. The `else` block runs when the loop finishes without a
+    /// `break`.
     For {
         identifier: Node,
         iterator: Node,
+        block: Node,
+        r#else: Option<Node>,
+    },
+    /// `match subject:`, where every case is a [MatchCase](NodeType::MatchCase)
+    Match {
+        subject: Node,
+        cases: Vec<Node>,
+    },
+    /// `case pattern if guard:`. Patterns are parsed as expressions, so `Point(x=0)` is a
+    /// [FunctionCall](NodeType::FunctionCall) and `[a, b]` is a [List](NodeType::List).
+    MatchCase {
+        pattern: Node,
+        guard: Option<Node>,
+        block: Node,
+    },
+    /// `try:`, where every `except` clause is an [ExceptHandler](NodeType::ExceptHandler). The
+    /// `else` block runs when the `try` block raises nothing, and the `finally` block runs however
+    /// the statement is left.
+    Try {
+        block: Node,
+        handlers: Vec<Node>,
+        r#else: Option<Node>,
+        finally: Option<Node>,
+    },
+    /// `except class as name:`, where both the class and the name are optional
+    ExceptHandler {
+        class: Option<Node>,
+        name: Option<Node>,
+        block: Node,
     },
     Break,
+    Continue,
+    Pass,
     Yield(Node),
     Return(Node),
+    Raise(Node),
     Class {
         name: Node,
         parent: Option<Node>,
         defs: Vec<Node>,
     },
+    /// A [Function](NodeType::Function) or [Class](NodeType::Class) with `@decorator` lines in
+    /// front of it, outermost first
+    Decorated {
+        decorators: Vec<Node>,
+        definition: Node,
+    },
     Block(Vec<Node>),
     Import(Node),
+    /// Stands in for a part of the tree that couldn't be parsed
+    Error,
 }
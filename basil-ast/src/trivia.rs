@@ -0,0 +1,32 @@
+/// A piece of source text that doesn't change what a program means
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriviaPiece {
+    /// A `#` comment, including the `#`
+    Comment(String),
+    /// A line with nothing but whitespace on it
+    BlankLine,
+}
+
+/// The comments and blank lines attached to a statement, so the source can be printed again
+/// without losing them.
+///
+/// Only statements carry trivia. Comments inside of a statement, like the ones between the
+/// elements of a list that's split over several lines, are kept with the statement's leading
+/// trivia.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trivia {
+    /// The comments and blank lines before the statement
+    pub leading: Vec<TriviaPiece>,
+    /// A comment at the end of the statement's line. For statements with a block, this is the
+    /// comment after the `:` that opens the block.
+    pub trailing: Option<String>,
+    /// Comments after the last statement of a block or class body, which aren't followed by a
+    /// statement in the same block
+    pub dangling: Vec<TriviaPiece>,
+}
+
+impl Trivia {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_none() && self.dangling.is_empty()
+    }
+}
@@ -0,0 +1,161 @@
+//! Traversals of a [Node] tree.
+//!
+//! Each trait has a single method that is called for every node. The default implementations
+//! only recurse into the children of the node, so an implementation can handle the node types it
+//! cares about and call the matching `walk` function for everything else:
+//!
+//! ```
+//! # use basil_ast::node::{Node, NodeType};
+//! # use basil_ast::visit::{walk, Visitor};
+//! struct Names(Vec<String>);
+//!
+//! impl Visitor for Names {
+//!     fn visit_node(&mut self, node: &Node) {
+//!         if let NodeType::Identifier(name) = node.node_type() {
+//!             self.0.push(name.clone());
+//!         }
+//!         walk(self, node);
+//!     }
+//! }
+//! ```
+
+use crate::node::Node;
+
+/// Visits the nodes of a tree by reference
+pub trait Visitor {
+    fn visit_node(&mut self, node: &Node) {
+        walk(self, node)
+    }
+}
+
+/// Visits every child of a node, in the order they appear in the source
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    for child in node.children() {
+        visitor.visit_node(child);
+    }
+}
+
+/// Visits the nodes of a tree by mutable reference, so they can be changed in place
+pub trait VisitorMut {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+}
+
+/// Visits every child of a node mutably, in the order they appear in the source
+pub fn walk_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut Node) {
+    for child in node.children_mut() {
+        visitor.visit_node_mut(child);
+    }
+}
+
+/// Rebuilds a tree, replacing each node with the node returned for it. Unlike [VisitorMut], a
+/// node can be replaced by one of a different type, or by one of its own children.
+pub trait Fold {
+    fn fold_node(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+}
+
+/// Folds every child of a node, in the order they appear in the source
+pub fn fold_children<F: Fold + ?Sized>(folder: &mut F, node: Node) -> Node {
+    node.map_children(|child| folder.fold_node(child))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+    use crate::dump::to_sexpr;
+    use crate::node::NodeType;
+    use crate::operators::Operator;
+
+    struct Names(Vec<String>);
+
+    impl Visitor for Names {
+        fn visit_node(&mut self, node: &Node) {
+            if let NodeType::Identifier(name) = node.node_type() {
+                self.0.push(name.clone());
+            }
+            walk(self, node);
+        }
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_node_mut(&mut self, node: &mut Node) {
+            if let NodeType::Identifier(name) = node.node_type_mut() {
+                name.make_ascii_uppercase();
+            }
+            walk_mut(self, node);
+        }
+    }
+
+    /// Replaces `not not x` with `x`
+    struct DoubleNegation;
+
+    impl Fold for DoubleNegation {
+        fn fold_node(&mut self, node: Node) -> Node {
+            let node = fold_children(self, node);
+            let is_not = |node: &Node| match node.node_type() {
+                NodeType::UnaryExpression { op, .. } => {
+                    matches!(op.node_type(), NodeType::Operator(Operator::Not))
+                }
+                _ => false,
+            };
+            let double = match node.node_type() {
+                NodeType::UnaryExpression { val, .. } => is_not(&node) && is_not(val),
+                _ => false,
+            };
+            if !double {
+                return node;
+            }
+            match node.into_node_type() {
+                NodeType::UnaryExpression { val, .. } => match val.into_node_type() {
+                    NodeType::UnaryExpression { val, .. } => val,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn not(b: &Builder, node: Node) -> Node {
+        b.unary(Operator::Not, node)
+    }
+
+    #[test]
+    fn visitors() {
+        // def f(a, b=c):
+        //     return d.e(not not f)
+        let b = Builder::new();
+        let mut module = b.module(vec![b
+            .function("f")
+            .parameter("a")
+            .parameter_with_default("b", b.identifier("c"))
+            .body(b.return_(b.call(
+                b.member(b.identifier("d"), "e"),
+                vec![not(&b, not(&b, b.identifier("f")))],
+            )))]);
+        let mut names = Names(vec![]);
+        names.visit_node(&module);
+        assert_eq!(names.0, ["f", "a", "b", "c", "d", "e", "f"]);
+
+        Rename.visit_node_mut(&mut module);
+        let module = DoubleNegation.fold_node(module);
+        assert_eq!(
+            to_sexpr(&module, false),
+            "(Block [(Function (Identifier \"F\") [(Parameter (Identifier \"A\") _ _) (Parameter \
+             (Identifier \"B\") _ (Identifier \"C\"))] _ (Block [(Return (FunctionCall \
+             (QualifiedIdentifier (Identifier \"D\") (Identifier \"E\")) \
+             [(Identifier \"F\")]))]))])"
+        );
+        let triple = not(&b, not(&b, not(&b, b.identifier("x"))));
+        let module = DoubleNegation.fold_node(b.module(vec![triple]));
+        assert_eq!(
+            to_sexpr(&module, false),
+            "(Block [(UnaryExpression (Identifier \"x\") (Operator Not))])"
+        );
+    }
+}
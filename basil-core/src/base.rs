@@ -1,20 +1,9 @@
 use crate::class::Class;
-use crate::function::Function;
-use crate::statements::Statement;
 
 pub struct Base;
 
 impl Base {
     pub fn base_class(&self) -> Class {
-        unimplemented!()
-    }
-
-    fn repr_function(&self) -> Function {
-        let statements: Vec<Statement> = vec![
-
-        ];
-
-
         unimplemented!()
     }
 }
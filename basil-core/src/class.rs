@@ -8,6 +8,7 @@ static CLASS_ID: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug)]
 pub struct Class {
+    name: String,
     parents: Vec<Explicit>,
     id: Explicit,
     created: RefCell<bool>,
@@ -15,9 +16,10 @@ pub struct Class {
 }
 
 impl Class {
-    pub fn new(parents: Vec<Explicit>, definitions: Vec<(String, Object)>) -> Self {
+    pub fn new(name: String, parents: Vec<Explicit>, definitions: Vec<(String, Object)>) -> Self {
         let id = CLASS_ID.fetch_add(1, Ordering::Acquire);
         Class {
+            name,
             parents,
             id: Explicit::new(id),
             created: RefCell::new(false),
@@ -35,6 +37,10 @@ impl Class {
         *self.created.borrow()
     }
 
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
     pub fn parents(&self) -> &Vec<Explicit> {
         &self.parents
     }
@@ -54,6 +60,7 @@ impl Clone for Class {
     fn clone(&self) -> Self {
         let id = CLASS_ID.fetch_add(1, Ordering::Acquire);
         Self {
+            name: self.name.clone(),
             parents: self.parents.clone(),
             id: Explicit::new(id),
             created: RefCell::new(false),
@@ -1,7 +1,5 @@
-use crate::object::Object;
 use crate::span::WithSpan;
 use crate::statements::Statement;
-use crate::variable::Variable;
 
 #[derive(Debug, Clone)]
 pub struct CodeBlock {
@@ -15,11 +13,15 @@ impl CodeBlock {
 
     pub fn no_span<I: IntoIterator<Item = Statement>>(iter: I) -> Self {
         CodeBlock {
-            statements: iter.into_iter().map(|s| WithSpan::empty(s)).collect(),
+            statements: iter.into_iter().map(WithSpan::empty).collect(),
         }
     }
 
     pub fn statements(&self) -> &Vec<WithSpan<Statement>> {
         &self.statements
     }
+
+    pub fn statements_mut(&mut self) -> &mut Vec<WithSpan<Statement>> {
+        &mut self.statements
+    }
 }
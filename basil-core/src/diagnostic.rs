@@ -0,0 +1,638 @@
+//! Errors and warnings that point at source code.
+//!
+//! A [Diagnostic] has a primary span, which is where the problem is, and any number of secondary
+//! labeled spans that explain it. It can be rendered for a terminal, with the lines it points at
+//! and carets under the spans, or as JSON for tools.
+
+use std::fmt::{Display, Formatter, Write};
+
+use crate::source_map::{SourceFile, SourceMap};
+use crate::span::Span;
+use crate::traceback::Traceback;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn color(self) -> &'static str {
+        match self {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A span with an optional message that is printed next to its underline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+}
+
+impl Label {
+    pub fn new(span: Span) -> Self {
+        Label {
+            span,
+            message: None,
+        }
+    }
+
+    pub fn with_message<S: Into<String>>(span: Span, message: S) -> Self {
+        Label {
+            span,
+            message: Some(message.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    primary: Label,
+    secondary: Vec<Label>,
+    notes: Vec<String>,
+    help: Vec<String>,
+    /// The calls that led to the problem, for exceptions raised while running
+    traceback: Option<Traceback>,
+}
+
+impl Diagnostic {
+    pub fn new<S: Into<String>>(severity: Severity, message: S, span: Span) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            primary: Label::new(span),
+            secondary: vec![],
+            notes: vec![],
+            help: vec![],
+            traceback: None,
+        }
+    }
+
+    pub fn error<S: Into<String>>(message: S, span: Span) -> Self {
+        Self::new(Severity::Error, message, span)
+    }
+
+    pub fn warning<S: Into<String>>(message: S, span: Span) -> Self {
+        Self::new(Severity::Warning, message, span)
+    }
+
+    /// Sets the message printed under the primary span
+    pub fn with_primary_label<S: Into<String>>(mut self, message: S) -> Self {
+        self.primary.message = Some(message.into());
+        self
+    }
+
+    /// Adds a secondary span
+    pub fn with_label<S: Into<String>>(mut self, span: Span, message: S) -> Self {
+        self.secondary.push(Label::with_message(span, message));
+        self
+    }
+
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help<S: Into<String>>(mut self, help: S) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn with_traceback(mut self, traceback: Traceback) -> Self {
+        self.traceback = Some(traceback);
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.primary.span
+    }
+
+    pub fn primary(&self) -> &Label {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &[Label] {
+        &self.secondary
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    pub fn help(&self) -> &[String] {
+        &self.help
+    }
+
+    pub fn traceback(&self) -> Option<&Traceback> {
+        self.traceback.as_ref()
+    }
+
+    /// Renders the diagnostic for a terminal, like
+    ///
+    /// ```text
+    /// error: expected `)`
+    ///  --> main.basil:1:8
+    ///   |
+    /// 1 | print(x
+    ///   |        ^ expected `)`
+    ///   |      - unclosed `(`
+    ///   |
+    ///   = help: ...
+    /// ```
+    ///
+    /// Labels in a source that isn't in `sources` are left out. With `color`, the output uses ANSI
+    /// escape codes.
+    pub fn render(&self, sources: &SourceMap, color: bool) -> String {
+        let style = Style { color };
+        let mut output = String::new();
+        let severity = self.severity.color();
+        let _ = writeln!(
+            output,
+            "{}: {}",
+            style.paint(&format!("{}{}", BOLD, severity), &self.severity.to_string()),
+            style.paint(BOLD, &self.message)
+        );
+
+        let labels: Vec<(&Label, bool)> = std::iter::once((&self.primary, true))
+            .chain(self.secondary.iter().map(|label| (label, false)))
+            .filter(|(label, _)| sources.get(label.span.file()).is_some())
+            .collect();
+        let width = labels
+            .iter()
+            .map(|(label, _)| {
+                let file = sources.get(label.span.file()).unwrap();
+                file.location(label.span.start()).line.to_string().len()
+            })
+            .max()
+            .unwrap_or(0);
+        let gutter = style.paint(BLUE, &format!("{} |", " ".repeat(width)));
+        let arrow = style.paint(BLUE, "-->");
+
+        match sources.get(self.primary.span.file()) {
+            Some(file) => {
+                let start = file.location(self.primary.span.start());
+                let _ = writeln!(
+                    output,
+                    "{}{} {}:{}:{}",
+                    " ".repeat(width),
+                    arrow,
+                    file.name(),
+                    start.line,
+                    start.column + 1
+                );
+            }
+            None => {
+                let _ = writeln!(output, "{}{} <native>", " ".repeat(width), arrow);
+            }
+        }
+
+        // labels are shown grouped by source, starting with the source of the primary span
+        let mut files = vec![];
+        for (label, _) in &labels {
+            if !files.contains(&label.span.file()) {
+                files.push(label.span.file());
+            }
+        }
+        for (index, id) in files.into_iter().enumerate() {
+            let file = sources.get(id).unwrap();
+            if index > 0 {
+                let start = labels
+                    .iter()
+                    .find(|(label, _)| label.span.file() == id)
+                    .map(|(label, _)| file.location(label.span.start()))
+                    .unwrap();
+                let _ = writeln!(
+                    output,
+                    "{}{} {}:{}:{}",
+                    " ".repeat(width),
+                    style.paint(BLUE, ":::"),
+                    file.name(),
+                    start.line,
+                    start.column + 1
+                );
+            }
+            let _ = writeln!(output, "{}", gutter);
+            let in_file: Vec<_> = labels
+                .iter()
+                .filter(|(label, _)| label.span.file() == id)
+                .copied()
+                .collect();
+            self.render_snippet(&mut output, &style, file, &in_file, width);
+        }
+
+        if !labels.is_empty() && (!self.notes.is_empty() || !self.help.is_empty()) {
+            let _ = writeln!(output, "{}", gutter);
+        }
+        let equals = style.paint(BLUE, &format!("{} =", " ".repeat(width)));
+        for note in &self.notes {
+            let _ = writeln!(output, "{} {}: {}", equals, style.paint(BOLD, "note"), note);
+        }
+        for help in &self.help {
+            let _ = writeln!(output, "{} {}: {}", equals, style.paint(BOLD, "help"), help);
+        }
+        output
+    }
+
+    fn render_snippet(
+        &self,
+        output: &mut String,
+        style: &Style,
+        file: &SourceFile,
+        labels: &[(&Label, bool)],
+        width: usize,
+    ) {
+        let mut lines: Vec<usize> = labels
+            .iter()
+            .map(|(label, _)| file.location(label.span.start()).line)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        let gutter = style.paint(BLUE, &format!("{} |", " ".repeat(width)));
+        let mut previous = None;
+        for line in lines {
+            if matches!(previous, Some(previous) if previous + 1 < line) {
+                let _ = writeln!(output, "{}", style.paint(BLUE, "..."));
+            }
+            previous = Some(line);
+
+            let text = file.line(line).unwrap_or("");
+            let number = style.paint(BLUE, &format!("{:>width$} |", line, width = width));
+            let _ = writeln!(
+                output,
+                "{}",
+                format!("{} {}", number, expand_tabs(text)).trim_end()
+            );
+
+            // each label gets its own line under the source line, primary first
+            let mut on_line: Vec<_> = labels
+                .iter()
+                .filter(|(label, _)| file.location(label.span.start()).line == line)
+                .collect();
+            on_line.sort_by_key(|(_, primary)| !primary);
+            for (label, primary) in on_line {
+                let start = file.location(label.span.start());
+                let end = file.location(label.span.end());
+                let first = display_width(text, start.column);
+                // spans that go past the line are underlined to its end
+                let last = if end.line == line {
+                    display_width(text, end.column)
+                } else {
+                    display_width(text, text.chars().count())
+                };
+                let marker = if *primary { "^" } else { "-" };
+                let underline = marker.repeat(last.saturating_sub(first).max(1));
+                let color = if *primary {
+                    self.severity.color()
+                } else {
+                    BLUE
+                };
+                let message = label
+                    .message
+                    .as_ref()
+                    .map_or(String::new(), |message| format!(" {}", message));
+                let _ = writeln!(
+                    output,
+                    "{} {}{}",
+                    gutter,
+                    " ".repeat(first),
+                    style.paint(color, &format!("{}{}", underline, message))
+                );
+            }
+        }
+    }
+
+    /// Writes the diagnostic as a JSON object, with the name, lines and 1-indexed columns of every
+    /// span that is in `sources`. A traceback is written as a `traceback` array of the calls, each
+    /// with its `function` and `span`, outermost first.
+    pub fn to_json(&self, sources: &SourceMap) -> String {
+        let mut output = String::new();
+        output.push('{');
+        let _ = write!(
+            output,
+            "\"severity\":{},\"message\":{},\"spans\":[",
+            json_string(&self.severity.to_string()),
+            json_string(&self.message)
+        );
+        let labels = std::iter::once((&self.primary, true))
+            .chain(self.secondary.iter().map(|label| (label, false)));
+        for (index, (label, primary)) in labels.enumerate() {
+            if index > 0 {
+                output.push(',');
+            }
+            output.push('{');
+            write_json_span(&label.span, sources, &mut output);
+            let _ = write!(
+                output,
+                ",\"primary\":{},\"label\":{}}}",
+                primary,
+                label
+                    .message
+                    .as_ref()
+                    .map_or("null".to_string(), |message| json_string(message))
+            );
+        }
+        output.push_str("],\"notes\":");
+        output.push_str(&json_strings(&self.notes));
+        output.push_str(",\"help\":");
+        output.push_str(&json_strings(&self.help));
+        if let Some(traceback) = &self.traceback {
+            output.push_str(",\"traceback\":[");
+            for (index, frame) in traceback.frames().iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                let _ = write!(
+                    output,
+                    "{{\"function\":{},\"span\":{{",
+                    json_string(frame.name())
+                );
+                write_json_span(frame.span(), sources, &mut output);
+                output.push_str("}}");
+            }
+            output.push(']');
+        }
+        output.push('}');
+        output
+    }
+}
+
+impl Display for Diagnostic {
+    /// Shows only the severity and message, since the source is needed for anything more
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const RESET: &str = "\x1b[0m";
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, codes: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", codes, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+const TAB_WIDTH: usize = 4;
+
+fn expand_tabs(line: &str) -> String {
+    line.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// How many columns the first `chars` characters of a line take up once its tabs are expanded
+fn display_width(line: &str, chars: usize) -> usize {
+    let prefix: Vec<char> = line.chars().take(chars).collect();
+    let width: usize = prefix
+        .iter()
+        .map(|c| if *c == '\t' { TAB_WIDTH } else { 1 })
+        .sum();
+    // columns past the end of the line, like the end of an unterminated statement
+    width + (chars - prefix.len())
+}
+
+/// Writes the fields that locate a span, without the braces around them
+fn write_json_span(span: &Span, sources: &SourceMap, output: &mut String) {
+    match sources.get(span.file()) {
+        Some(file) => {
+            let start = file.location(span.start());
+            let end = file.location(span.end());
+            let _ = write!(
+                output,
+                "\"file\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{},",
+                json_string(&file.name().to_string()),
+                start.line,
+                start.column + 1,
+                end.line,
+                end.column + 1
+            );
+        }
+        None => output.push_str("\"file\":null,"),
+    }
+    let _ = write!(
+        output,
+        "\"byte_start\":{},\"byte_end\":{}",
+        span.start(),
+        span.end()
+    );
+}
+
+fn json_string(string: &str) -> String {
+    let mut output = String::with_capacity(string.len() + 2);
+    output.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn json_strings(strings: &[String]) -> String {
+    let strings: Vec<_> = strings.iter().map(|string| json_string(string)).collect();
+    format!("[{}]", strings.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::FileId;
+    use crate::traceback::TracebackFrame;
+
+    /// The span of the first occurrence of `text` in a source
+    fn find(sources: &SourceMap, file: FileId, text: &str) -> Span {
+        let start = sources.get(file).unwrap().text().find(text).unwrap();
+        Span::new(file, start, start + text.len())
+    }
+
+    #[test]
+    fn labels_in_other_files() {
+        let mut sources = SourceMap::new();
+        let main = sources.add_file("main.basil", "import shapes\nshapes.area(1)\n");
+        let shapes = sources.add_file("shapes.basil", "def area(w, h):\n    return w * h\n");
+        let diagnostic = Diagnostic::error("missing argument `h`", find(&sources, main, "(1)"))
+            .with_primary_label("called with one argument")
+            .with_label(find(&sources, shapes, "w, h"), "takes two");
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: missing argument `h`\n \
+             --> main.basil:2:12\n  \
+               |\n\
+             2 | shapes.area(1)\n  \
+               |            ^^^ called with one argument\n \
+             ::: shapes.basil:1:10\n  \
+               |\n\
+             1 | def area(w, h):\n  \
+               |          ---- takes two\n"
+        );
+    }
+
+    #[test]
+    fn tabs() {
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("tabs.basil", "if x:\n\ty = )\n");
+        let diagnostic = Diagnostic::error("expected an expression", find(&sources, file, ")"));
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: expected an expression\n \
+             --> tabs.basil:2:6\n  \
+               |\n\
+             2 |     y = )\n  \
+               |         ^\n"
+        );
+    }
+
+    #[test]
+    fn multi_line_spans() {
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("lines.basil", "x = [1,\n     2\n");
+        let span = find(&sources, file, "[1,\n     2");
+        let diagnostic = Diagnostic::error("unclosed `[`", span).with_primary_label("here");
+        // the span is underlined to the end of its first line
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: unclosed `[`\n \
+             --> lines.basil:1:5\n  \
+               |\n\
+             1 | x = [1,\n  \
+               |     ^^^ here\n"
+        );
+    }
+
+    #[test]
+    fn end_of_line_and_file() {
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("end.basil", "print(x\ny = 2");
+        let end_of_line = Span::new(file, 7, 7);
+        let diagnostic = Diagnostic::error("expected `)`", end_of_line);
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: expected `)`\n \
+             --> end.basil:1:8\n  \
+               |\n\
+             1 | print(x\n  \
+               |        ^\n"
+        );
+
+        let end_of_file = Span::new(file, 13, 13);
+        let diagnostic = Diagnostic::error("expected a newline", end_of_file);
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: expected a newline\n \
+             --> end.basil:2:6\n  \
+               |\n\
+             2 | y = 2\n  \
+               |      ^\n"
+        );
+
+        // spans that don't fall on character boundaries are moved back onto them
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("accent.basil", "é = 1\n");
+        let diagnostic = Diagnostic::error("odd span", Span::new(file, 1, 2));
+        assert!(diagnostic
+            .render(&sources, false)
+            .contains(" --> accent.basil:1:1\n"));
+    }
+
+    #[test]
+    fn native_spans() {
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("main.basil", "f()\n");
+        let diagnostic = Diagnostic::error("failed", Span::native())
+            .with_label(find(&sources, file, "f()"), "called here")
+            .with_help("check the arguments");
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: failed\n \
+             --> <native>\n  \
+               |\n\
+             1 | f()\n  \
+               | --- called here\n  \
+               |\n  \
+               = help: check the arguments\n"
+        );
+        assert_eq!(
+            Diagnostic::warning("no source", Span::native()).render(&sources, false),
+            "warning: no source\n--> <native>\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("main.basil", "def f():\n    raise E\nf()\n");
+        let raised = find(&sources, file, "raise E");
+        let traceback = Traceback::new(vec![
+            TracebackFrame::new("<module>".to_string(), Span::new(file, 21, 24)),
+            TracebackFrame::new("f".to_string(), raised.clone()),
+            TracebackFrame::new("map".to_string(), Span::native()),
+        ]);
+        let diagnostic = Diagnostic::error("E", raised)
+            .with_label(Span::native(), "from \"native\" code")
+            .with_note("a note")
+            .with_traceback(traceback);
+        assert_eq!(
+            diagnostic.to_json(&sources),
+            "{\"severity\":\"error\",\"message\":\"E\",\"spans\":[\
+             {\"file\":\"main.basil\",\"line_start\":2,\"column_start\":5,\"line_end\":2,\
+             \"column_end\":12,\"byte_start\":13,\"byte_end\":20,\"primary\":true,\"label\":null},\
+             {\"file\":null,\"byte_start\":0,\"byte_end\":0,\"primary\":false,\
+             \"label\":\"from \\\"native\\\" code\"}],\
+             \"notes\":[\"a note\"],\"help\":[],\"traceback\":[\
+             {\"function\":\"<module>\",\"span\":{\"file\":\"main.basil\",\"line_start\":3,\
+             \"column_start\":1,\"line_end\":3,\"column_end\":4,\"byte_start\":21,\"byte_end\":24}},\
+             {\"function\":\"f\",\"span\":{\"file\":\"main.basil\",\"line_start\":2,\
+             \"column_start\":5,\"line_end\":2,\"column_end\":12,\"byte_start\":13,\"byte_end\":20}},\
+             {\"function\":\"map\",\"span\":{\"file\":null,\"byte_start\":0,\"byte_end\":0}}]}"
+        );
+        // diagnostics without a traceback leave the field out
+        let diagnostic = Diagnostic::warning("w", Span::native());
+        assert_eq!(
+            diagnostic.to_json(&sources),
+            "{\"severity\":\"warning\",\"message\":\"w\",\"spans\":[\
+             {\"file\":null,\"byte_start\":0,\"byte_end\":0,\"primary\":true,\"label\":null}],\
+             \"notes\":[],\"help\":[]}"
+        );
+    }
+}
@@ -1,19 +1,17 @@
-use crate::function::Function;
 use crate::object::{DeepClone, Object};
 use crate::primitive::Primitive;
 use crate::variable::{IntoVariable, Variable};
-use std::borrow::Borrow;
 use std::cell::{Ref, RefCell};
-use std::collections::hash_map::IterMut;
-use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::iter::FromIterator;
 
 #[derive(Clone, Default)]
 pub struct Dictionary {
     values: HashMap<u64, Vec<Variable>>,
     keys: HashMap<u64, Vec<RefCell<Object>>>,
     len: usize,
+    /// Whether this is a set, whose values are all None
+    set: bool,
 }
 
 impl Dictionary {
@@ -44,8 +42,8 @@ impl Dictionary {
         mut eq: Eq,
     ) {
         let hash_value = hash(&mut key);
-        let mut keys = self.keys.entry(hash_value).or_default();
-        let mut values = self.values.entry(hash_value).or_default();
+        let keys = self.keys.entry(hash_value).or_default();
+        let values = self.values.entry(hash_value).or_default();
         for (index, key2) in keys.iter_mut().enumerate() {
             let key2 = &mut *key2.borrow_mut();
             if eq(&mut key, key2) {
@@ -121,6 +119,16 @@ impl Dictionary {
         }
     }
 
+    /// Marks the dictionary as a set, which only its keys matter for
+    pub fn into_set(mut self) -> Self {
+        self.set = true;
+        self
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.set
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -129,7 +137,7 @@ impl Dictionary {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> DictionaryIterator {
+    pub fn iter(&self) -> DictionaryIterator<'_> {
         self.into_iter()
     }
 
@@ -246,8 +254,8 @@ impl Debug for Dictionary {
         write!(f, "{{")?;
         let mut first = true;
         for hash in self.keys.keys() {
-            let keys = &self.keys[&hash];
-            let values = &self.values[&hash];
+            let keys = &self.keys[hash];
+            let values = &self.values[hash];
             let zip = keys.iter().zip(values.iter());
             for (k, v) in zip {
                 let k = k.borrow();
@@ -265,11 +273,14 @@ impl Debug for Dictionary {
 
 impl DeepClone for Dictionary {
     fn deep_clone(&self) -> Self {
-        let mut ret = Dictionary::new();
+        let mut ret = Dictionary {
+            set: self.set,
+            ..Dictionary::new()
+        };
         for hash in self.keys.keys() {
             let keys = &self.keys[hash];
             let values = &self.values[hash];
-            let mut zipped = keys.iter().zip(values);
+            let zipped = keys.iter().zip(values);
 
             let ret_keys = ret.keys.entry(*hash).or_default();
             let ret_values = ret.values.entry(*hash).or_default();
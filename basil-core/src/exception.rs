@@ -1,17 +1,54 @@
 use crate::object::Object;
 use crate::primitive::Primitive;
+use crate::span::Span;
+use crate::traceback::Traceback;
 use crate::variable::Variable;
 
 #[derive(Debug)]
-pub struct Exception(Variable);
+pub struct Exception {
+    inner: Variable,
+    span: Option<Span>,
+    traceback: Option<Traceback>,
+}
 
 impl Exception {
     pub fn new(inner: Variable) -> Self {
-        Exception(inner)
+        Exception {
+            inner,
+            span: None,
+            traceback: None,
+        }
     }
 
     pub fn inner(&self) -> &Variable {
-        &self.0
+        &self.inner
+    }
+
+    /// Where the exception was raised, if it's known
+    pub fn span(&self) -> Option<&Span> {
+        self.span.as_ref()
+    }
+
+    /// Sets where the exception was raised, unless a more precise location was already set
+    pub fn at(mut self, span: &Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span.clone());
+        }
+        self
+    }
+
+    /// The calls that were running when the exception was raised, if they're known
+    pub fn traceback(&self) -> Option<&Traceback> {
+        self.traceback.as_ref()
+    }
+
+    /// Sets the calls that were running when the exception was raised, unless they were already
+    /// set closer to where it was raised
+    pub fn with_traceback(mut self, traceback: Traceback) -> Self {
+        if self.traceback.is_none() {
+            self.traceback = Some(traceback);
+        }
+        self
     }
 }
 
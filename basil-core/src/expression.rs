@@ -1,13 +1,39 @@
 use std::collections::HashMap;
 
-use crate::object::Object;
-use crate::variable::{IntoVariable, Variable};
+use crate::function::Function;
+use crate::span::{Span, WithSpan};
+use crate::variable::Variable;
 
 
 #[derive(Debug, Clone)]
 pub enum Atom {
-    Identifier(String),
-    Variable(Variable)
+    /// A name, along with the scope that name resolution found it in and where it's written
+    Identifier(String, NameScope, Span),
+    Variable(Variable),
+    /// A nested expression, which allows tails to be chained together
+    Expression(Box<Expression>),
+    UnaryOperation {
+        op: UnaryOperator,
+        value: Box<Expression>,
+    },
+    /// Converts the value of the expression into a string with the `__str__` protocol
+    Str(Box<Expression>),
+    /// Converts the value of the expression into a string with the `__repr__` protocol
+    Repr(Box<Expression>),
+    /// Builds a list from the elements, which are evaluated in order. Tuples are lists as well.
+    List(Vec<Expression>),
+    /// Builds a dictionary whose keys are the elements, all mapped to `None`
+    Set(Vec<Expression>),
+    Dictionary(Vec<(Expression, Expression)>),
+    /// Builds a collection by running through the clauses in a scope of its own, so that the loop
+    /// variables aren't visible outside of it
+    Comprehension {
+        kind: Box<ComprehensionKind>,
+        clauses: Vec<ComprehensionClause>,
+    },
+    /// Creates a function from a `def` or a lambda, capturing its
+    /// [free variables](Function::free_variables) from the scope it's created in
+    Function(Box<WithSpan<Function>>),
 }
 
 #[derive(Debug, Clone)]
@@ -29,10 +55,7 @@ impl Expression {
     }
 
     pub fn tail(&self) -> Option<&ExpressionTail> {
-        match &self.tail {
-            None => None,
-            Some(t) => Some(&*t),
-        }
+        self.tail.as_deref()
     }
 
     pub fn head_mut(&mut self) -> &mut Atom {
@@ -40,11 +63,58 @@ impl Expression {
     }
 
     pub fn tail_mut(&mut self) -> Option<&mut ExpressionTail> {
-        match &mut self.tail {
-            None => None,
-            Some(t) => Some(&mut *t),
+        self.tail.as_deref_mut()
+    }
+
+    /// Creates a new expression that applies the tail to the result of this expression
+    pub fn then(self, tail: ExpressionTail) -> Self {
+        match self.tail {
+            None => Expression::new(self.head, Some(tail)),
+            Some(_) => Expression::new(Atom::Expression(Box::new(self)), Some(tail)),
         }
     }
+
+    /// Creates the expression `lhs op rhs`
+    pub fn binary(lhs: Expression, op: BinaryOperator, rhs: Expression) -> Self {
+        lhs.then(ExpressionTail::BinaryOperation { op, rhs })
+    }
+
+    /// Creates the expression `op value`
+    pub fn unary(op: UnaryOperator, value: Expression) -> Self {
+        Atom::UnaryOperation {
+            op,
+            value: Box::new(value),
+        }
+        .into()
+    }
+}
+
+impl Atom {
+    /// An identifier that hasn't been resolved yet
+    pub fn identifier<S: Into<String>>(name: S, span: Span) -> Self {
+        Atom::Identifier(name.into(), NameScope::Unresolved, span)
+    }
+}
+
+/// Where the variable that an [identifier](Atom::Identifier) refers to is defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameScope {
+    Unresolved,
+    /// A variable of the function, or the comprehension, that uses it
+    Local,
+    /// A variable of an enclosing function, which the function that uses it captures
+    Captured,
+    /// `this`, or a member of the class that the method using it belongs to or of one of its
+    /// parents
+    Class,
+    /// A variable of the module, or one that the host defines, such as a builtin
+    Global,
+}
+
+impl From<Atom> for Expression {
+    fn from(atom: Atom) -> Self {
+        Expression::new(atom, None)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -54,6 +124,58 @@ pub enum ExpressionTail {
         positional: Vec<Expression>,
         named: HashMap<String, Expression>,
     },
+    /// Gets an element out of a list, string or dictionary
+    Index(Expression),
+    /// Applies a binary operator with the head as the left hand side
+    BinaryOperation {
+        op: BinaryOperator,
+        rhs: Expression,
+    },
+}
+
+/// What a comprehension produces each time it gets through all of its clauses
+#[derive(Debug, Clone)]
+pub enum ComprehensionKind {
+    List(Expression),
+    Set(Expression),
+    Dictionary(Expression, Expression),
+}
+
+#[derive(Debug, Clone)]
+pub enum ComprehensionClause {
+    /// Runs the rest of the clauses once for every element of `iterable`, with `variable` bound to
+    /// the element
+    For {
+        variable: String,
+        iterable: Expression,
+    },
+    /// Only continues on to the rest of the clauses if the condition is true
+    If(Expression),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+    /// Short circuits, so the right hand side is only evaluated if the left hand side is true
+    And,
+    /// Short circuits, so the right hand side is only evaluated if the left hand side is false
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    Neg,
+    Not,
 }
 
 /*
@@ -1,21 +1,28 @@
 use crate::code_block::CodeBlock;
+use crate::exception::ExpressionResult;
 use crate::object::Object;
 use crate::span::WithSpan;
 use crate::variable::Variable;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 static FUNCTION_COUNT: AtomicUsize = AtomicUsize::new(0);
 
+/// The body of a function that is written in Rust, which is given the positional and keyword
+/// arguments the function was called with
+pub type NativeFunction = fn(Vec<Variable>, Vec<(String, Variable)>) -> ExpressionResult;
+
 #[derive(Debug, Clone)]
 pub struct Function {
     id: usize,
     name: String,
     captures: HashMap<String, Variable>,
+    free_variables: Vec<String>,
+    cell_variables: Vec<String>,
     positional_arguments: Vec<String>,
     keyword_arguments: Vec<(String, Object)>,
     code_block: WithSpan<CodeBlock>,
+    native: Option<NativeFunction>,
 }
 
 impl Function {
@@ -31,12 +38,29 @@ impl Function {
             name,
             id,
             captures,
+            free_variables: vec![],
+            cell_variables: vec![],
             positional_arguments,
             keyword_arguments,
             code_block,
+            native: None,
         }
     }
 
+    /// Creates a function that runs Rust code instead of a code block. Its arguments are passed
+    /// along as they are, so it has to check them itself.
+    pub fn native(name: String, body: NativeFunction) -> Self {
+        let mut function = Function::new(
+            name,
+            HashMap::new(),
+            vec![],
+            vec![],
+            WithSpan::empty(CodeBlock::new(vec![])),
+        );
+        function.native = Some(body);
+        function
+    }
+
     pub fn empty_span(self) -> WithSpan<Self> {
         WithSpan::empty(self)
     }
@@ -50,6 +74,31 @@ impl Function {
     pub fn captures(&self) -> &HashMap<String, Variable> {
         &self.captures
     }
+
+    pub fn set_captures(&mut self, captures: HashMap<String, Variable>) {
+        self.captures = captures;
+    }
+
+    /// The variables of enclosing functions that this function uses, which are captured by
+    /// reference when the function is created
+    pub fn free_variables(&self) -> &Vec<String> {
+        &self.free_variables
+    }
+
+    pub fn set_free_variables(&mut self, names: Vec<String>) {
+        self.free_variables = names;
+    }
+
+    /// The local variables of this function that the functions defined in it capture, which have
+    /// to exist before any of those functions are created
+    pub fn cell_variables(&self) -> &Vec<String> {
+        &self.cell_variables
+    }
+
+    pub fn set_cell_variables(&mut self, names: Vec<String>) {
+        self.cell_variables = names;
+    }
+
     pub fn positional_arguments(&self) -> &Vec<String> {
         &self.positional_arguments
     }
@@ -61,6 +110,15 @@ impl Function {
         &self.code_block
     }
 
+    pub fn code_block_mut(&mut self) -> &mut WithSpan<CodeBlock> {
+        &mut self.code_block
+    }
+
+    /// The Rust body of the function, if it was made with [native](Function::native)
+    pub fn native_body(&self) -> Option<NativeFunction> {
+        self.native
+    }
+
 
     pub fn name(&self) -> &String {
         &self.name
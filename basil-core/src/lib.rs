@@ -1,14 +1,18 @@
 pub mod base;
 pub mod class;
 pub mod code_block;
+pub mod diagnostic;
 pub mod dictionary;
 pub mod exception;
 pub mod expression;
 pub mod function;
 pub mod object;
+pub mod pattern;
 pub mod primitive;
 pub mod ptr;
+pub mod source_map;
 pub mod span;
 pub mod statements;
+pub mod traceback;
 pub mod type_id;
 pub mod variable;
@@ -1,11 +1,9 @@
 use crate::dictionary::Dictionary;
-use crate::exception::Exception;
 use crate::primitive::Primitive;
 use crate::type_id::{Explicit, TypeId};
 use crate::variable::{IntoVariable, Variable};
 use num_bigint::BigInt;
 use std::fmt::{Debug, Formatter};
-use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
 
 #[derive(Clone)]
@@ -52,11 +50,7 @@ impl Object {
     }
 
     pub fn is_class_object(&self) -> bool {
-        if let TypeId::Explicit(Explicit(_)) = self.type_id {
-            true
-        } else {
-            false
-        }
+        matches!(self.type_id, TypeId::Explicit(Explicit(_)))
     }
 }
 
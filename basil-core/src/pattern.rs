@@ -0,0 +1,67 @@
+use crate::code_block::CodeBlock;
+use crate::expression::Expression;
+
+/// A `case` of a [Match](crate::statements::Statement::Match) statement
+#[derive(Debug, Clone)]
+pub struct MatchCase {
+    pattern: Pattern,
+    guard: Option<Expression>,
+    block: CodeBlock,
+}
+
+impl MatchCase {
+    pub fn new(pattern: Pattern, guard: Option<Expression>, block: CodeBlock) -> Self {
+        MatchCase {
+            pattern,
+            guard,
+            block,
+        }
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// A condition that's checked after the pattern matched, with its names already bound
+    pub fn guard(&self) -> Option<&Expression> {
+        self.guard.as_ref()
+    }
+
+    pub fn block(&self) -> &CodeBlock {
+        &self.block
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.pattern
+    }
+
+    pub fn guard_mut(&mut self) -> Option<&mut Expression> {
+        self.guard.as_mut()
+    }
+
+    pub fn block_mut(&mut self) -> &mut CodeBlock {
+        &mut self.block
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// `_`, which matches anything without binding it
+    Wildcard,
+    /// Matches anything, and binds it to the name
+    Capture(String),
+    /// Matches values that are equal to the value of the expression, such as a literal or
+    /// `Color.RED`
+    Value(Expression),
+    /// Matches lists of the same length whose elements match the patterns
+    List(Vec<Pattern>),
+    /// Matches dictionaries that have every key, where the value of each key matches its pattern.
+    /// Other keys are ignored.
+    Dictionary(Vec<(Expression, Pattern)>),
+    /// `Point(x=0)`, which matches instances of the class or of a class that inherits from it,
+    /// whose members match the patterns
+    Class {
+        class: Expression,
+        members: Vec<(String, Pattern)>,
+    },
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use num_bigint::BigInt;
 use num_rational::Rational;
 
@@ -27,6 +25,12 @@ pub enum Primitive {
     Dictionary(Dictionary),
     Function(WithSpan<Function>),
     Class(WithSpan<Class>),
+    /// The integers from `start` up to, but not including, `stop`, counting by `step`
+    Range {
+        start: BigInt,
+        stop: BigInt,
+        step: BigInt,
+    },
 }
 
 impl Primitive {
@@ -41,16 +45,42 @@ impl Primitive {
             Primitive::Dictionary(_) => Implicit::new(6),
             Primitive::Function(_) => Implicit::new(7),
             Primitive::Class(_) => Implicit::new(8),
+            Primitive::Range { .. } => Implicit::new(9),
         }
     }
 
+    /// The name of this primitive's type, as it would be shown to the user
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Primitive::None => "None",
+            Primitive::Integer(_) => "Integer",
+            Primitive::Float(_) => "Float",
+            Primitive::String(_) => "String",
+            Primitive::Boolean(_) => "Boolean",
+            Primitive::List(_) => "List",
+            Primitive::Dictionary(_) => "Dictionary",
+            Primitive::Function(_) => "Function",
+            Primitive::Class(_) => "Class",
+            Primitive::Range { .. } => "Range",
+        }
+    }
+
+    /// Whether this primitive can be used as a dictionary key with
+    /// [basic_hash](Primitive::basic_hash)
+    pub fn is_hashable(&self) -> bool {
+        matches!(
+            self,
+            Primitive::Integer(_) | Primitive::Float(_) | Primitive::String(_) | Primitive::Boolean(_)
+        )
+    }
+
     pub fn basic_eq(&mut self, other: &mut Self) -> bool {
         match (self, other) {
             (Primitive::Integer(left), Primitive::Integer(right)) => right == left,
             (Primitive::Float(left), Primitive::Float(right)) => right == left,
             (Primitive::String(left), Primitive::String(right)) => left == right,
             (Primitive::Boolean(left), Primitive::Boolean(right)) => left == right,
-            (left, right) => (left as *const Primitive) == (right as *const Primitive),
+            (left, right) => std::ptr::eq(left, right),
         }
     }
 
@@ -67,11 +97,7 @@ impl Primitive {
     }
 
     pub fn is_function(&self) -> bool {
-        if let Primitive::Function(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, Primitive::Function(_))
     }
 
     pub fn get_dictionary(&self) -> Option<&Dictionary> {
@@ -104,7 +130,7 @@ impl DeepClone for Primitive {
                 Primitive::List(l.iter().map(|inner| inner.deep_clone()).collect())
             }
             Primitive::Dictionary(dict) => Primitive::Dictionary(dict.deep_clone()),
-            Primitive::Function(f) => {
+            Primitive::Function(_) => {
                 unimplemented!()
             }
             Primitive::None => Primitive::None,
@@ -149,11 +175,14 @@ impl Debug for Primitive {
             Primitive::Dictionary(d) => {
                 write!(f, "{:?}", d)
             }
-            Primitive::Function(_) => {
-                unimplemented!()
+            Primitive::Function(function) => {
+                write!(f, "<function {}>", function.get_object().name())
             }
-            Primitive::Class(_) => {
-                unimplemented!()
+            Primitive::Class(class) => {
+                write!(f, "<class {}>", class.get_object().name())
+            }
+            Primitive::Range { start, stop, step } => {
+                write!(f, "range({}, {}, {})", start, stop, step)
             }
         }
     }
@@ -2,7 +2,6 @@ use crate::object::Object;
 use crate::primitive::Primitive;
 use std::cell::{Ref, RefCell, RefMut};
 use std::fmt::{Debug, Formatter};
-use std::ops::Deref;
 use std::rc::Rc;
 
 #[derive(Clone)]
@@ -13,21 +12,26 @@ impl<T> Ptr<T> {
         Ptr(Rc::new(RefCell::new(val)))
     }
 
-    pub fn get(&self) -> Ref<T> {
+    pub fn get(&self) -> Ref<'_, T> {
         self.0.borrow()
     }
 
-    pub fn get_mut(&self) -> RefMut<T> {
+    pub fn get_mut(&self) -> RefMut<'_, T> {
         self.0.borrow_mut()
     }
 
-    pub fn try_get(&self) -> Option<Ref<T>> {
+    pub fn try_get(&self) -> Option<Ref<'_, T>> {
         self.0.try_borrow().ok()
     }
 
-    pub fn try_get_mut(&self) -> Option<RefMut<T>> {
+    pub fn try_get_mut(&self) -> Option<RefMut<'_, T>> {
         self.0.try_borrow_mut().ok()
     }
+
+    /// Whether both pointers point to the same value
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl<T> From<T> for Ptr<T> {
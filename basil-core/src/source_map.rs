@@ -0,0 +1,321 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::span::{LineColumn, Span};
+
+/// Identifies a source registered in a [SourceMap]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(u32);
+
+impl FileId {
+    /// The file of spans that point at Rust code instead of basil source, like the spans made by
+    /// `span!`. These spans can't be resolved to any text.
+    pub const NATIVE: FileId = FileId(u32::MAX);
+
+    pub fn is_native(self) -> bool {
+        self == FileId::NATIVE
+    }
+
+    /// The position of the source in its [SourceMap]
+    pub fn index(self) -> u32 {
+        self.0
+    }
+
+    /// Recreates an id from its [index](FileId::index), such as one read back from a dump of a tree
+    pub fn from_index(index: u32) -> Self {
+        FileId(index)
+    }
+}
+
+/// Where a source came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceName {
+    File(PathBuf),
+    /// Source that was given as a string, with a label saying what it is
+    String(String),
+    /// A cell of an interactive session, numbered from 1
+    Repl(usize),
+}
+
+impl Display for SourceName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceName::File(path) => write!(f, "{}", path.display()),
+            SourceName::String(label) => write!(f, "<{}>", label),
+            SourceName::Repl(cell) => write!(f, "<repl {}>", cell),
+        }
+    }
+}
+
+/// The text of a source, along with where each of its lines starts so that byte offsets can be
+/// turned into lines and columns.
+///
+/// Lines are 1-indexed and columns are 0-indexed character offsets into the line.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    id: FileId,
+    name: SourceName,
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new<S: Into<String>>(id: FileId, name: SourceName, text: S) -> Self {
+        let text = text.into();
+        let line_starts = line_starts(&text);
+        SourceFile {
+            id,
+            name,
+            text,
+            line_starts,
+        }
+    }
+
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    pub fn name(&self) -> &SourceName {
+        &self.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The number of lines in the source. An empty source has one empty line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Gets a line of the source, without its line ending
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |next| next - 1);
+        Some(self.text[start..end].trim_end_matches('\r'))
+    }
+
+    /// The byte offset where a line starts
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    /// Finds the line and column of a byte offset. Offsets past the end of the source are treated
+    /// as the end of the source, and offsets inside of a character as the start of it.
+    pub fn location(&self, offset: usize) -> LineColumn {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let start = self.line_starts[line - 1];
+        let column = self.text[start..offset].chars().count();
+        LineColumn::new(line, column)
+    }
+
+    /// Finds the byte offset of a line and column. The column can be one past the last character
+    /// of the line.
+    pub fn offset(&self, location: LineColumn) -> Option<usize> {
+        let start = self.line_start(location.line)?;
+        let line = self.line(location.line)?;
+        line.char_indices()
+            .map(|(index, _)| index)
+            .chain(std::iter::once(line.len()))
+            .nth(location.column)
+            .map(|index| start + index)
+    }
+
+    /// Gets the text that a span covers, if the span is in this source
+    pub fn slice(&self, span: &Span) -> Option<&str> {
+        if span.file() != self.id {
+            return None;
+        }
+        self.text.get(span.range())
+    }
+
+    /// Replaces part of the text, keeping the id and name
+    pub fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        self.text.replace_range(range, text);
+        self.line_starts = line_starts(&self.text);
+    }
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+/// Holds every source the frontend and interpreter have seen, so that [Span]s can be turned back
+/// into text, lines and columns without reading anything again.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    repl_cells: usize,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a source, giving it a new id
+    pub fn add<S: Into<String>>(&mut self, name: SourceName, text: S) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(id, name, text));
+        id
+    }
+
+    /// Registers the text of a file that has already been read
+    pub fn add_file<P: Into<PathBuf>, S: Into<String>>(&mut self, path: P, text: S) -> FileId {
+        self.add(SourceName::File(path.into()), text)
+    }
+
+    /// Reads a file and registers it
+    pub fn load_file<P: Into<PathBuf>>(&mut self, path: P) -> std::io::Result<FileId> {
+        let path = path.into();
+        let text = std::fs::read_to_string(&path)?;
+        Ok(self.add_file(path, text))
+    }
+
+    pub fn add_string<L: Into<String>, S: Into<String>>(&mut self, label: L, text: S) -> FileId {
+        self.add(SourceName::String(label.into()), text)
+    }
+
+    /// Registers the next cell of an interactive session
+    pub fn add_repl_cell<S: Into<String>>(&mut self, text: S) -> FileId {
+        self.repl_cells += 1;
+        self.add(SourceName::Repl(self.repl_cells), text)
+    }
+
+    pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.0 as usize)
+    }
+
+    pub fn get_mut(&mut self, id: FileId) -> Option<&mut SourceFile> {
+        self.files.get_mut(id.0 as usize)
+    }
+
+    /// Gets the text that a span covers
+    pub fn text(&self, span: &Span) -> Option<&str> {
+        self.get(span.file())?.slice(span)
+    }
+
+    /// Finds the lines and columns that a span starts and ends at
+    pub fn locate(&self, span: &Span) -> Option<(LineColumn, LineColumn)> {
+        let file = self.get(span.file())?;
+        Some((file.location(span.start()), file.location(span.end())))
+    }
+
+    /// Describes where a span starts, as `name:line:column` with the column counted from 1 like
+    /// in [Diagnostic](crate::diagnostic::Diagnostic)s
+    pub fn describe(&self, span: &Span) -> String {
+        match self.get(span.file()) {
+            Some(file) => {
+                let start = file.location(span.start());
+                format!("{}:{}:{}", file.name(), start.line, start.column + 1)
+            }
+            None => "<native>".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_byte_characters() {
+        let mut sources = SourceMap::new();
+        let id = sources.add_string("test", "café = 'naïve'\nx = 1\n");
+        let file = sources.get(id).unwrap();
+        let quote = file.text().find('\'').unwrap();
+        // `é` takes two bytes, but is one column
+        assert_eq!(quote, 8);
+        assert_eq!(file.location(quote), LineColumn::new(1, 7));
+        assert_eq!(
+            file.location(file.text().find('x').unwrap()),
+            LineColumn::new(2, 0)
+        );
+
+        // offsets in the middle of `é` are in the column of the `é`
+        let accent = file.text().find('é').unwrap();
+        assert_eq!(file.location(accent + 1), LineColumn::new(1, 3));
+        assert_eq!(file.location(accent + 2), LineColumn::new(1, 4));
+
+        let word = Span::new(id, quote + 1, quote + 7);
+        assert_eq!(file.slice(&word), Some("naïve"));
+        assert_eq!(sources.text(&word), Some("naïve"));
+        assert_eq!(sources.describe(&word), "<test>:1:9");
+        let (start, end) = sources.locate(&word).unwrap();
+        assert_eq!((start.column, end.column), (8, 13));
+    }
+
+    #[test]
+    fn offsets_and_locations_round_trip() {
+        let mut sources = SourceMap::new();
+        let id = sources.add_file("main.basil", "ä = 1\n\nüber = 'ß'\n");
+        let file = sources.get(id).unwrap();
+        for (offset, _) in file.text().char_indices() {
+            assert_eq!(file.offset(file.location(offset)), Some(offset));
+        }
+        let end = file.text().len();
+        assert_eq!(file.location(end), LineColumn::new(4, 0));
+        assert_eq!(file.offset(LineColumn::new(4, 0)), Some(end));
+        // one past the end of a line is allowed, but not any further
+        assert_eq!(file.offset(LineColumn::new(1, 5)), Some(6));
+        assert_eq!(file.offset(LineColumn::new(1, 6)), None);
+        assert_eq!(file.offset(LineColumn::new(5, 0)), None);
+        assert_eq!(file.offset(LineColumn::new(0, 0)), None);
+    }
+
+    #[test]
+    fn last_line() {
+        let mut sources = SourceMap::new();
+        let with_newline = sources.add_string("a", "a\r\nb\n");
+        let without_newline = sources.add_string("b", "a\nb");
+        let file = sources.get(with_newline).unwrap();
+        assert_eq!(file.line_count(), 3);
+        assert_eq!(file.line(1), Some("a"));
+        assert_eq!(file.line(2), Some("b"));
+        assert_eq!(file.line(3), Some(""));
+        assert_eq!(file.line(4), None);
+        let file = sources.get(without_newline).unwrap();
+        assert_eq!(file.line_count(), 2);
+        assert_eq!(file.line(2), Some("b"));
+        assert_eq!(file.line(3), None);
+        assert_eq!(file.location(3), LineColumn::new(2, 1));
+        assert_eq!(file.location(100), LineColumn::new(2, 1));
+    }
+
+    #[test]
+    fn repl_cells() {
+        let mut sources = SourceMap::new();
+        let first = sources.add_repl_cell("x = 1\n");
+        let second = sources.add_repl_cell("x = 1\n");
+        assert_ne!(first, second);
+        assert_eq!(sources.get(first).unwrap().name(), &SourceName::Repl(1));
+        assert_eq!(sources.get(second).unwrap().name().to_string(), "<repl 2>");
+        // a span only resolves in the cell it came from
+        let span = Span::new(second, 0, 1);
+        assert_eq!(sources.get(first).unwrap().slice(&span), None);
+        assert_eq!(sources.describe(&span), "<repl 2>:1:1");
+    }
+
+    #[test]
+    fn native_spans() {
+        let mut sources = SourceMap::new();
+        sources.add_string("test", "x = 1\n");
+        let span = Span::native();
+        assert!(span.file().is_native());
+        assert_eq!(sources.get(span.file()).map(SourceFile::id), None);
+        assert_eq!(sources.text(&span), None);
+        assert_eq!(sources.locate(&span), None);
+        assert_eq!(span.get_string(&sources), None);
+        assert_eq!(sources.describe(&span), "<native>");
+    }
+}
@@ -1,22 +1,22 @@
-use std::borrow::Borrow;
-use std::cell::{Cell, Ref, RefCell};
 use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
-use std::fs::File;
-use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom};
-use std::ops::{Deref, Range};
-use std::path::PathBuf;
+use std::ops::Range;
+
+use crate::source_map::{FileId, SourceMap};
 
 /// A span represents a place in a file so we don't have to individually save every part of a file
-/// repeatedly
-#[derive(Debug, Clone)]
+/// repeatedly.
+///
+/// The start and end are byte offsets into a source registered in a [SourceMap], with the end being
+/// the first byte after the span. The source map turns them back into text, lines and columns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Span {
-    file: PathBuf,
-    start: LineColumn,
-    end: LineColumn,
-    cache: RefCell<Option<String>>,
+    file: FileId,
+    start: usize,
+    end: usize,
 }
 
+/// A line and column in a source. Lines are 1-indexed, and columns are 0-indexed character offsets
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LineColumn {
     pub line: usize,
@@ -31,11 +31,9 @@ impl LineColumn {
 
 impl Ord for LineColumn {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.line.cmp(&other.line) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Equal => self.column.cmp(&other.column),
-            Ordering::Greater => Ordering::Greater,
-        }
+        self.line
+            .cmp(&other.line)
+            .then(self.column.cmp(&other.column))
     }
 }
 
@@ -46,87 +44,80 @@ impl PartialOrd for LineColumn {
 }
 
 impl Span {
-    /// Creates a new space
-    pub fn new(file: PathBuf, start: LineColumn, end: LineColumn) -> Self {
-        Span {
-            file,
-            start,
-            end,
-            cache: Default::default(),
+    /// Creates a new span
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        if end < start {
+            panic!("Can't create a span where the end is before the start");
         }
+        Span { file, start, end }
+    }
+
+    /// Creates a span for something defined in Rust rather than in basil source
+    pub fn native() -> Self {
+        Span::new(FileId::NATIVE, 0, 0)
     }
 
     /// Creates a new span inside an old span.
     ///
     /// Returns None if the range in the sub space isn't entirely in the range of the outer span, or
     /// if the ranges are equal.
-    pub fn sub_span(&self, start: LineColumn, end: LineColumn) -> Option<Self> {
+    pub fn sub_span(&self, start: usize, end: usize) -> Option<Self> {
         if end < start {
             panic!("Can't create a span where the end is before the start");
         }
         if (self.start == start && self.end == end) || !(start >= self.start && end <= self.end) {
             return None;
         }
+        Some(Span::new(self.file, start, end))
+    }
 
-        Some(Self {
-            file: self.file.clone(),
-            start,
-            end,
-            cache: Default::default(),
-        })
+    /// Creates a span that covers both this span and the other span
+    pub fn join(&self, other: &Span) -> Self {
+        Span::new(
+            self.file,
+            self.start.min(other.start),
+            self.end.max(other.end),
+        )
     }
 
-    /// Gets the associated string from this span
-    pub fn get_string(&self) -> std::io::Result<String> {
-        if self.cache.borrow().is_none() {
-            let mut file = File::open(&self.file)?;
-            let mut buf_reader = BufReader::new(file);
-            for _ in 1..self.start.line {
-                let mut _buf = String::new();
-                buf_reader.read_line(&mut _buf);
-            }
-
-            buf_reader.seek(SeekFrom::Current(self.start.column as i64))?;
-            let mut ret = String::new();
-            let mut current = self.start;
-            while current <= self.end {
-                let mut line = String::new();
-                buf_reader.read_line(&mut line)?;
-                for char in line.chars() {
-                    ret.push(char);
-                    if char == '\n' {
-                        current.line += 1;
-                        current.column = 1;
-                    } else {
-                        current.column += 1;
-                    }
-                }
-            }
-
-            *self.cache.borrow_mut() = Some(ret);
-        }
+    /// Moves the span forwards or backwards by a number of bytes
+    pub fn shift(&mut self, bytes: isize) {
+        self.start = (self.start as isize + bytes) as usize;
+        self.end = (self.end as isize + bytes) as usize;
+    }
 
-        let borrow = self.cache.borrow();
-        Ok(borrow.as_ref().unwrap().clone())
+    /// Gets the associated string from this span
+    pub fn get_string<'s>(&self, sources: &'s SourceMap) -> Option<&'s str> {
+        sources.text(self)
     }
 
     pub fn empty() -> Span {
-        Self {
-            file: PathBuf::from("none"),
-            start: LineColumn::new(1, 0),
-            end: LineColumn::new(1, 0),
-            cache: Default::default(),
-        }
+        Span::native()
     }
-    pub fn file(&self) -> &PathBuf {
-        &self.file
+
+    pub fn file(&self) -> FileId {
+        self.file
     }
-    pub fn start(&self) -> LineColumn {
+
+    pub fn start(&self) -> usize {
         self.start
     }
-    pub fn end(&self) -> LineColumn {
+
+    pub fn end(&self) -> usize {
         self.end
     }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
 }
 
 #[derive(Clone)]
@@ -145,12 +136,16 @@ impl<T> WithSpan<T> {
         &self.0
     }
 
+    pub fn get_object_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
     pub fn get_span(&self) -> &Span {
         &self.1
     }
 }
 
-impl<T : Clone> From<&WithSpan<T>> for WithSpan<T> {
+impl<T: Clone> From<&WithSpan<T>> for WithSpan<T> {
     fn from(w: &WithSpan<T>) -> Self {
         let borrow = &w.0;
         WithSpan::new(borrow.clone(), w.1.clone())
@@ -1,6 +1,7 @@
 use crate::code_block::CodeBlock;
 use crate::expression::Expression;
-use crate::variable::Variable;
+use crate::object::Object;
+use crate::pattern::MatchCase;
 
 #[derive(Debug, Clone)]
 pub enum Statement {
@@ -12,11 +13,96 @@ pub enum Statement {
         elifs: Vec<(Expression, CodeBlock)>,
         r#else: Option<CodeBlock>,
     },
+    /// Runs the block for as long as `condition` is true. The `else` block runs once the
+    /// condition is false, unless the loop was left with a `break`.
     While {
         condition: Expression,
         block: CodeBlock,
+        r#else: Option<CodeBlock>,
+    },
+    /// Runs the block once for every element of `iterable`, with `variable` bound to the element
+    /// in the enclosing scope. The `else` block runs once the elements run out, unless the loop
+    /// was left with a `break`.
+    For {
+        variable: String,
+        iterable: Expression,
+        block: CodeBlock,
+        r#else: Option<CodeBlock>,
+    },
+    /// Runs the block of the first case whose pattern matches the value of `subject`, and whose
+    /// guard is true
+    Match {
+        subject: Expression,
+        cases: Vec<MatchCase>,
     },
     Expression(Expression),
     Return(Expression),
+    /// Leaves the innermost loop
+    Break,
+    /// Skips to the next iteration of the innermost loop
+    Continue,
     Raise(Expression),
+    /// Runs the block, and if it raises an exception, the block of the first handler whose class
+    /// it's an instance of. The `else` block runs if nothing was raised, and the `finally` block
+    /// runs last however the statement is left.
+    Try {
+        block: CodeBlock,
+        handlers: Vec<ExceptHandler>,
+        r#else: Option<CodeBlock>,
+        finally: Option<CodeBlock>,
+    },
+    /// Defines a class and binds it to `name` in the current scope. The definitions named in
+    /// `decorators` are passed through their decorators, innermost first, when the class is
+    /// defined.
+    Class {
+        name: String,
+        parents: Vec<Expression>,
+        definitions: Vec<(String, Object)>,
+        decorators: Vec<(String, Vec<Expression>)>,
+    },
+    /// Evaluates the decorators, then creates the function or class that `definition` defines
+    /// and passes it through them, innermost first. The result is bound to `name`, which is the
+    /// name the definition would have bound on its own.
+    Decorated {
+        name: String,
+        decorators: Vec<Expression>,
+        definition: Box<Statement>,
+    },
+}
+
+/// An `except` clause of a [Try](Statement::Try) statement
+#[derive(Debug, Clone)]
+pub struct ExceptHandler {
+    class: Option<Expression>,
+    name: Option<String>,
+    block: CodeBlock,
+}
+
+impl ExceptHandler {
+    pub fn new(class: Option<Expression>, name: Option<String>, block: CodeBlock) -> Self {
+        ExceptHandler { class, name, block }
+    }
+
+    /// The class, or tuple of classes, that the exception has to be an instance of. A handler
+    /// without one catches every exception.
+    pub fn class(&self) -> Option<&Expression> {
+        self.class.as_ref()
+    }
+
+    /// The name that the exception is bound to while the block runs
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn block(&self) -> &CodeBlock {
+        &self.block
+    }
+
+    pub fn class_mut(&mut self) -> Option<&mut Expression> {
+        self.class.as_mut()
+    }
+
+    pub fn block_mut(&mut self) -> &mut CodeBlock {
+        &mut self.block
+    }
 }
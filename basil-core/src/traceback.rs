@@ -0,0 +1,120 @@
+//! The calls that were running when an exception was raised.
+
+use std::fmt::Write;
+
+use crate::source_map::SourceMap;
+use crate::span::Span;
+
+/// A function that was running when an exception was raised, and where in it the exception came
+/// from, or the call it was waiting on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracebackFrame {
+    name: String,
+    span: Span,
+}
+
+impl TracebackFrame {
+    pub fn new(name: String, span: Span) -> Self {
+        TracebackFrame { name, span }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+/// The frames that were running when an exception was raised, outermost first, so the last frame
+/// is the one that raised it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Traceback {
+    frames: Vec<TracebackFrame>,
+}
+
+impl Traceback {
+    pub fn new(frames: Vec<TracebackFrame>) -> Self {
+        Traceback { frames }
+    }
+
+    pub fn frames(&self) -> &[TracebackFrame] {
+        &self.frames
+    }
+
+    /// Renders the traceback for a terminal, like
+    ///
+    /// ```text
+    /// traceback (most recent call last):
+    ///   main.basil:7:1, in <module>
+    ///     load('config')
+    ///   main.basil:3:5, in load
+    ///     raise NotFound(name)
+    /// ```
+    ///
+    /// Frames in a source that isn't in `sources` are shown without a line.
+    pub fn render(&self, sources: &SourceMap) -> String {
+        let mut output = String::from("traceback (most recent call last):\n");
+        for frame in &self.frames {
+            let _ = writeln!(
+                output,
+                "  {}, in {}",
+                sources.describe(&frame.span),
+                frame.name
+            );
+            let line = sources.get(frame.span.file()).and_then(|file| {
+                let start = file.location(frame.span.start());
+                file.line(start.line)
+            });
+            if let Some(line) = line {
+                let _ = writeln!(output, "    {}", line.trim());
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_kept_in_order() {
+        let frames = vec![
+            TracebackFrame::new("<module>".to_string(), Span::native()),
+            TracebackFrame::new("main".to_string(), Span::native()),
+        ];
+        let traceback = Traceback::new(frames.clone());
+        assert_eq!(traceback.frames(), &frames[..]);
+        assert_eq!(traceback.frames()[1].name(), "main");
+        assert!(Traceback::default().frames().is_empty());
+    }
+
+    #[test]
+    fn render() {
+        let mut sources = SourceMap::new();
+        let source = "def load(name):\n    raise NotFound(name)\nload('config')\n";
+        let file = sources.add_file("main.basil", source);
+        let at = |text: &str| {
+            let start = source.find(text).unwrap();
+            Span::new(file, start, start + text.len())
+        };
+        let traceback = Traceback::new(vec![
+            TracebackFrame::new("<module>".to_string(), at("load('config')")),
+            TracebackFrame::new("load".to_string(), at("raise NotFound(name)")),
+            TracebackFrame::new("map".to_string(), Span::native()),
+        ]);
+        assert_eq!(
+            traceback.render(&sources),
+            "\
+traceback (most recent call last):
+  main.basil:3:1, in <module>
+    load('config')
+  main.basil:2:5, in load
+    raise NotFound(name)
+  <native>, in map
+"
+        );
+    }
+}
@@ -1,6 +1,3 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
 use crate::exception::Exception;
 use crate::object::{DeepClone, Object};
 use crate::primitive::Primitive;
@@ -242,7 +239,7 @@ impl TryFrom<Variable> for i8 {
         let object = value.get_object();
         let object = object.get();
         if let Primitive::Integer(i) = object.as_primitive() {
-            BigInt::to_i8(&i).ok_or(Exception::new(value))
+            BigInt::to_i8(i).ok_or(Exception::new(value))
         } else {
             Err(Exception::new(value))
         }
@@ -256,7 +253,7 @@ impl TryFrom<Variable> for i16 {
         let object = value.get_object();
         let object = object.get();
         if let Primitive::Integer(i) = object.as_primitive() {
-            BigInt::to_i16(&i).ok_or(Exception::new(value))
+            BigInt::to_i16(i).ok_or(Exception::new(value))
         } else {
             Err(Exception::new(value))
         }
@@ -270,7 +267,7 @@ impl TryFrom<Variable> for i32 {
         let object = value.get_object();
         let object = object.get();
         if let Primitive::Integer(i) = object.as_primitive() {
-            BigInt::to_i32(&i).ok_or(Exception::new(value))
+            BigInt::to_i32(i).ok_or(Exception::new(value))
         } else {
             Err(Exception::new(value))
         }
@@ -284,7 +281,7 @@ impl TryFrom<Variable> for i64 {
         let object = value.get_object();
         let object = object.get();
         if let Primitive::Integer(i) = object.as_primitive() {
-            BigInt::to_i64(&i).ok_or(Exception::new(value))
+            BigInt::to_i64(i).ok_or(Exception::new(value))
         } else {
             Err(Exception::new(value))
         }
@@ -0,0 +1,1152 @@
+//! A gradual type checker that runs over the parsed tree, before it's lowered.
+//!
+//! Types come from the annotations on assignments, parameters and return values, and from the
+//! literals, operators and calls in expressions. Anything the checker can't work out, such as an
+//! unannotated parameter or a member of an object, has the type [Any](Type::Any), which is
+//! compatible with every other type. Code without annotations is only rejected when it would fail
+//! no matter what, such as calling an Integer or adding a String to a List.
+//!
+//! Assignments without an annotation give a variable the type of the value. After an `if`, a loop
+//! or a `match`, a variable that one of its blocks assigned a different type to becomes
+//! [Any](Type::Any). Inside of a function, the variables of the enclosing scopes are only trusted
+//! if they're annotated, or if they're functions or classes.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use basil_ast::node::{Node, NodeType};
+use basil_ast::operators::Operator;
+use basil_core::diagnostic::Diagnostic;
+use basil_core::span::Span;
+
+use crate::token::operator_str;
+
+/// The type of a value, as far as the checker knows it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// A value whose type isn't known, which can be used as anything
+    Any,
+    None,
+    Integer,
+    Float,
+    String,
+    Boolean,
+    List,
+    Dictionary,
+    Range,
+    /// A function, along with its signature if it's known
+    Function(Option<Box<Signature>>),
+    /// The class with the given name, rather than an object of it
+    Class(String),
+    /// An object of the class with the given name
+    Instance(String),
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Type::Any => "Any",
+            Type::None => "None",
+            Type::Integer => "Integer",
+            Type::Float => "Float",
+            Type::String => "String",
+            Type::Boolean => "Boolean",
+            Type::List => "List",
+            Type::Dictionary => "Dictionary",
+            Type::Range => "Range",
+            Type::Function(_) => "Function",
+            Type::Class(_) => "Class",
+            Type::Instance(class) => class,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The parameters and return type of a function
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    parameters: Vec<Parameter>,
+    returns: Type,
+}
+
+impl Signature {
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    pub fn returns(&self) -> &Type {
+        &self.returns
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    name: String,
+    parameter_type: Type,
+    /// Where the type of the parameter was declared, if it was annotated
+    annotation: Option<Span>,
+    has_default: bool,
+}
+
+impl Parameter {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn parameter_type(&self) -> &Type {
+        &self.parameter_type
+    }
+
+    pub fn has_default(&self) -> bool {
+        self.has_default
+    }
+}
+
+/// Checks the types in a [Block](NodeType::Block) node, such as the one produced by
+/// [parse](crate::parser::parse), reporting every error that it finds
+pub fn check(module: &Node) -> Result<(), Vec<TypeError>> {
+    let mut checker = Checker::default();
+    checker.scopes.push(Scope::default());
+    checker.block(module);
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    /// The module, function and comprehension scopes around the code being checked, innermost last
+    scopes: Vec<Scope>,
+    /// The parent of every class that has been defined, if it has one
+    classes: HashMap<String, Option<String>>,
+    errors: Vec<TypeError>,
+}
+
+#[derive(Default, Clone)]
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    /// The return type of the function that this is the scope of, and the annotation it comes
+    /// from. Module and comprehension scopes don't have one.
+    function: Option<(Type, Option<Span>)>,
+}
+
+#[derive(Clone, PartialEq)]
+struct Binding {
+    binding_type: Type,
+    /// The annotation that declared the type of the variable, which every assignment to it has
+    /// to match
+    annotation: Option<Span>,
+    /// Whether the variable was bound by a `def` or a class
+    definition: bool,
+}
+
+impl Binding {
+    fn inferred(binding_type: Type) -> Self {
+        Binding {
+            binding_type,
+            annotation: None,
+            definition: false,
+        }
+    }
+}
+
+impl Checker {
+    fn error(&mut self, kind: TypeErrorKind, span: &Span) {
+        self.errors.push(TypeError::new(kind, span.clone()));
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        let mut enclosing = false;
+        for scope in self.scopes.iter().rev() {
+            if let Some(binding) = scope.bindings.get(name) {
+                let trusted = binding.annotation.is_some() || binding.definition;
+                return if enclosing && !trusted {
+                    None
+                } else {
+                    Some(binding)
+                };
+            }
+            enclosing |= scope.function.is_some();
+        }
+        None
+    }
+
+    fn bind(&mut self, name: &str, binding: Binding) {
+        self.scope().bindings.insert(name.to_string(), binding);
+    }
+
+    /// Checks a block that might not run, or might run more than once. Afterwards every variable
+    /// whose type the block changed has the type [Any](Type::Any).
+    fn branch(&mut self, block: &Node) {
+        let before = self.scope().bindings.clone();
+        self.block(block);
+        for (name, binding) in self.scope().bindings.iter_mut() {
+            match before.get(name) {
+                Some(old) if old.binding_type != binding.binding_type => {
+                    binding.binding_type = Type::Any
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn block(&mut self, node: &Node) {
+        match node.node_type() {
+            NodeType::Block(statements) => {
+                for statement in statements {
+                    self.statement(statement);
+                }
+            }
+            _ => self.statement(node),
+        }
+    }
+
+    fn statement(&mut self, node: &Node) {
+        match node.node_type() {
+            NodeType::Assignment { lhs, var_type, rhs } => self.assignment(lhs, var_type, rhs),
+            NodeType::Function { name, .. } => {
+                let function = self.function(node, None);
+                if let NodeType::Identifier(name) = name.node_type() {
+                    self.bind(
+                        name,
+                        Binding {
+                            binding_type: function,
+                            annotation: None,
+                            definition: true,
+                        },
+                    );
+                }
+            }
+            NodeType::Class { name, parent, defs } => {
+                let name = match name.node_type() {
+                    NodeType::Identifier(name) => name.clone(),
+                    _ => return,
+                };
+                let parent = parent
+                    .as_ref()
+                    .and_then(|parent| match self.expression(parent) {
+                        Type::Class(parent) => Some(parent),
+                        Type::Any => None,
+                        other => {
+                            self.error(TypeErrorKind::NotAClass(other), parent.span());
+                            None
+                        }
+                    });
+                self.classes.insert(name.clone(), parent);
+                self.bind(
+                    &name,
+                    Binding {
+                        binding_type: Type::Class(name.clone()),
+                        annotation: None,
+                        definition: true,
+                    },
+                );
+                for def in defs {
+                    match def.node_type() {
+                        NodeType::Function { .. } => {
+                            self.function(def, Some(Type::Instance(name.clone())));
+                        }
+                        _ => self.statement(def),
+                    }
+                }
+            }
+            NodeType::Decorated {
+                decorators,
+                definition,
+            } => {
+                for decorator in decorators {
+                    let decorator_type = self.expression(decorator);
+                    self.callable(&decorator_type, decorator.span());
+                }
+                self.statement(definition);
+                // the decorators can replace the definition with anything
+                if let NodeType::Function { name, .. } | NodeType::Class { name, .. } =
+                    definition.node_type()
+                {
+                    if let NodeType::Identifier(name) = name.node_type() {
+                        self.bind(name, Binding::inferred(Type::Any));
+                    }
+                }
+            }
+            NodeType::If {
+                condition,
+                block,
+                r#else,
+            } => {
+                self.expression(condition);
+                self.branch(block);
+                if let Some(r#else) = r#else {
+                    self.branch(r#else);
+                }
+            }
+            NodeType::While {
+                condition,
+                block,
+                r#else,
+            } => {
+                self.expression(condition);
+                self.branch(block);
+                if let Some(r#else) = r#else {
+                    self.branch(r#else);
+                }
+            }
+            NodeType::For {
+                identifier,
+                iterator,
+                block,
+                r#else,
+            } => {
+                let iterator_type = self.expression(iterator);
+                self.iterable(&iterator_type, iterator.span());
+                if let NodeType::Identifier(name) = identifier.node_type() {
+                    self.bind(name, Binding::inferred(Type::Any));
+                }
+                self.branch(block);
+                if let Some(r#else) = r#else {
+                    self.branch(r#else);
+                }
+            }
+            NodeType::Match { subject, cases } => {
+                self.expression(subject);
+                for case in cases {
+                    if let NodeType::MatchCase {
+                        pattern,
+                        guard,
+                        block,
+                    } = case.node_type()
+                    {
+                        self.pattern(pattern);
+                        if let Some(guard) = guard {
+                            self.expression(guard);
+                        }
+                        self.branch(block);
+                    }
+                }
+            }
+            NodeType::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                // the block can stop at any statement, so it's treated like a branch too
+                self.branch(block);
+                for handler in handlers {
+                    if let NodeType::ExceptHandler { class, name, block } = handler.node_type() {
+                        let caught = class.as_ref().map(|class| self.expression(class));
+                        if let Some(NodeType::Identifier(name)) = name.as_ref().map(Node::node_type)
+                        {
+                            let caught = match caught {
+                                Some(Type::Class(class)) => Type::Instance(class),
+                                _ => Type::Any,
+                            };
+                            self.bind(name, Binding::inferred(caught));
+                        }
+                        self.branch(block);
+                    }
+                }
+                if let Some(r#else) = r#else {
+                    self.branch(r#else);
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            NodeType::Return(value) => {
+                let found = self.expression(value);
+                let function = self
+                    .scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.function.clone());
+                if let Some((expected, annotation)) = function {
+                    self.expect(&expected, &found, value.span(), annotation);
+                }
+            }
+            NodeType::Yield(value) | NodeType::Raise(value) | NodeType::Import(value) => {
+                self.expression(value);
+            }
+            NodeType::Block(_) => self.block(node),
+            NodeType::Pass | NodeType::Break | NodeType::Continue | NodeType::Error => {}
+            _ => {
+                self.expression(node);
+            }
+        }
+    }
+
+    fn assignment(&mut self, lhs: &Node, var_type: &Option<Node>, rhs: &Node) {
+        let found = self.expression(rhs);
+        let declared = var_type
+            .as_ref()
+            .map(|annotation| (self.annotation(annotation), annotation.span().clone()));
+        if let NodeType::List(_) | NodeType::Tuple(_) = lhs.node_type() {
+            self.iterable(&found, rhs.span());
+            self.unpack(lhs);
+            return;
+        }
+        let name = match lhs.node_type() {
+            NodeType::Identifier(name) => name,
+            _ => {
+                self.expression(lhs);
+                if let Some((expected, annotation)) = declared {
+                    self.expect(&expected, &found, rhs.span(), Some(annotation));
+                }
+                return;
+            }
+        };
+        match declared {
+            Some((expected, annotation)) => {
+                self.expect(&expected, &found, rhs.span(), Some(annotation.clone()));
+                let binding = Binding {
+                    binding_type: expected,
+                    annotation: Some(annotation),
+                    definition: false,
+                };
+                self.bind(name, binding);
+            }
+            None => match self.scope().bindings.get(name).cloned() {
+                Some(Binding {
+                    binding_type,
+                    annotation: annotation @ Some(_),
+                    ..
+                }) => self.expect(&binding_type, &found, rhs.span(), annotation),
+                _ => self.bind(name, Binding::inferred(found)),
+            },
+        }
+    }
+
+    /// Binds the names that a value is unpacked into. The types of the elements aren't known, so
+    /// names that were annotated keep their type.
+    fn unpack(&mut self, target: &Node) {
+        match target.node_type() {
+            NodeType::Identifier(name) => {
+                let annotated = self
+                    .scope()
+                    .bindings
+                    .get(name)
+                    .is_some_and(|binding| binding.annotation.is_some());
+                if !annotated {
+                    self.bind(name, Binding::inferred(Type::Any));
+                }
+            }
+            NodeType::List(elements) | NodeType::Tuple(elements) => {
+                for element in elements {
+                    self.unpack(element);
+                }
+            }
+            _ => {
+                self.expression(target);
+            }
+        }
+    }
+
+    /// Checks a `def` and its body, returning the type of the function. Methods see the object
+    /// they're called on as `this`, which has the type `this`.
+    fn function(&mut self, node: &Node, this: Option<Type>) -> Type {
+        let (parameters, returns, block) = match node.node_type() {
+            NodeType::Function {
+                parameters,
+                returns,
+                block,
+                ..
+            } => (parameters, returns, block),
+            _ => return Type::Any,
+        };
+        let parameters = self.parameters(parameters);
+        let (returns, annotation) = match returns {
+            Some(returns) => (self.annotation(returns), Some(returns.span().clone())),
+            None => (Type::Any, None),
+        };
+        let signature = Signature {
+            parameters,
+            returns: returns.clone(),
+        };
+
+        self.scopes.push(Scope {
+            function: Some((returns, annotation)),
+            ..Scope::default()
+        });
+        if let Some(this) = this {
+            self.bind("this", Binding::inferred(this));
+        }
+        for parameter in &signature.parameters {
+            let binding = Binding {
+                binding_type: parameter.parameter_type.clone(),
+                annotation: parameter.annotation.clone(),
+                definition: false,
+            };
+            self.bind(&parameter.name, binding);
+        }
+        self.block(block);
+        self.scopes.pop();
+        Type::Function(Some(Box::new(signature)))
+    }
+
+    fn parameters(&mut self, parameters: &[Node]) -> Vec<Parameter> {
+        let mut checked = vec![];
+        for parameter in parameters {
+            if let NodeType::Parameter {
+                name,
+                annotation,
+                default,
+            } = parameter.node_type()
+            {
+                let name = match name.node_type() {
+                    NodeType::Identifier(name) => name.clone(),
+                    _ => continue,
+                };
+                let parameter_type = match annotation {
+                    Some(annotation) => self.annotation(annotation),
+                    None => Type::Any,
+                };
+                let annotation = annotation
+                    .as_ref()
+                    .map(|annotation| annotation.span().clone());
+                if let Some(default) = default {
+                    let found = self.expression(default);
+                    self.expect(&parameter_type, &found, default.span(), annotation.clone());
+                }
+                checked.push(Parameter {
+                    name,
+                    parameter_type,
+                    annotation,
+                    has_default: default.is_some(),
+                });
+            }
+        }
+        checked
+    }
+
+    /// Works out the type that an annotation stands for
+    fn annotation(&mut self, node: &Node) -> Type {
+        let name = match node.node_type() {
+            NodeType::None => return Type::None,
+            NodeType::Identifier(name) => name,
+            _ => {
+                self.error(TypeErrorKind::InvalidAnnotation, node.span());
+                return Type::Any;
+            }
+        };
+        match name.as_str() {
+            "Any" => Type::Any,
+            "Integer" => Type::Integer,
+            "Float" => Type::Float,
+            "String" => Type::String,
+            "Boolean" => Type::Boolean,
+            "List" => Type::List,
+            "Dictionary" => Type::Dictionary,
+            "Range" => Type::Range,
+            "Function" => Type::Function(None),
+            _ => match self.lookup(name).map(|binding| &binding.binding_type) {
+                Some(Type::Class(class)) => Type::Instance(class.clone()),
+                _ => {
+                    self.error(TypeErrorKind::UnknownType(name.clone()), node.span());
+                    Type::Any
+                }
+            },
+        }
+    }
+
+    fn pattern(&mut self, node: &Node) {
+        match node.node_type() {
+            NodeType::Identifier(name) if name != "_" => {
+                self.bind(name, Binding::inferred(Type::Any))
+            }
+            NodeType::List(elements) | NodeType::Tuple(elements) => {
+                for element in elements {
+                    self.pattern(element);
+                }
+            }
+            NodeType::Dictionary(entries) => {
+                for entry in entries {
+                    if let NodeType::DictionaryEntry { value, .. } = entry.node_type() {
+                        self.pattern(value);
+                    }
+                }
+            }
+            NodeType::FunctionCall { parameters, .. } => {
+                for parameter in parameters {
+                    if let NodeType::KeywordArgument { value, .. } = parameter.node_type() {
+                        self.pattern(value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expression(&mut self, node: &Node) -> Type {
+        match node.node_type() {
+            NodeType::Integer(_) => Type::Integer,
+            NodeType::Float(_) => Type::Float,
+            NodeType::String(_) => Type::String,
+            NodeType::Boolean(_) => Type::Boolean,
+            NodeType::None => Type::None,
+            NodeType::FormatString(parts) => {
+                for part in parts {
+                    self.expression(part);
+                }
+                Type::String
+            }
+            NodeType::Interpolation { value, .. } => {
+                self.expression(value);
+                Type::String
+            }
+            NodeType::Identifier(name) => self
+                .lookup(name)
+                .map_or(Type::Any, |binding| binding.binding_type.clone()),
+            NodeType::QualifiedIdentifier { parent, .. } => {
+                self.expression(parent);
+                Type::Any
+            }
+            NodeType::Index { value, index } => {
+                let value = self.expression(value);
+                let index = self.expression(index);
+                match (value, index) {
+                    (Type::String, Type::Integer) => Type::String,
+                    _ => Type::Any,
+                }
+            }
+            NodeType::FunctionCall { name, parameters } => self.call(name, parameters),
+            NodeType::BinaryExpression { lhs, rhs, op } => {
+                let lhs = self.expression(lhs);
+                let rhs = self.expression(rhs);
+                let operator = match op.node_type() {
+                    NodeType::Operator(operator) => *operator,
+                    _ => return Type::Any,
+                };
+                binary_operation(operator, &lhs, &rhs).unwrap_or_else(|| {
+                    let kind = TypeErrorKind::UnsupportedOperands { operator, lhs, rhs };
+                    self.error(kind, node.span());
+                    Type::Any
+                })
+            }
+            NodeType::UnaryExpression { val, op } => {
+                let value = self.expression(val);
+                match (op.node_type(), value) {
+                    (NodeType::Operator(Operator::Not), _) => Type::Boolean,
+                    (_, value @ Type::Integer)
+                    | (_, value @ Type::Float)
+                    | (_, value @ Type::Any) => value,
+                    (NodeType::Operator(operator), value) => {
+                        let kind = TypeErrorKind::UnsupportedOperand {
+                            operator: *operator,
+                            value,
+                        };
+                        self.error(kind, node.span());
+                        Type::Any
+                    }
+                    _ => Type::Any,
+                }
+            }
+            NodeType::List(elements) | NodeType::Tuple(elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+                Type::List
+            }
+            NodeType::Set(elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+                Type::Any
+            }
+            NodeType::Dictionary(entries) => {
+                for entry in entries {
+                    self.expression(entry);
+                }
+                Type::Dictionary
+            }
+            NodeType::DictionaryEntry { key, value } => {
+                self.expression(key);
+                self.expression(value);
+                Type::Any
+            }
+            NodeType::ListComprehension { element, clauses } => {
+                self.comprehension(element, clauses);
+                Type::List
+            }
+            NodeType::SetComprehension { element, clauses } => {
+                self.comprehension(element, clauses);
+                Type::Any
+            }
+            NodeType::DictionaryComprehension { element, clauses } => {
+                self.comprehension(element, clauses);
+                Type::Dictionary
+            }
+            NodeType::Lambda { parameters, body } => {
+                let parameters = self.parameters(parameters);
+                self.scopes.push(Scope {
+                    function: Some((Type::Any, None)),
+                    ..Scope::default()
+                });
+                for parameter in &parameters {
+                    self.bind(&parameter.name, Binding::inferred(Type::Any));
+                }
+                let returns = self.expression(body);
+                self.scopes.pop();
+                Type::Function(Some(Box::new(Signature {
+                    parameters,
+                    returns,
+                })))
+            }
+            _ => Type::Any,
+        }
+    }
+
+    fn comprehension(&mut self, element: &Node, clauses: &[Node]) {
+        self.scopes.push(Scope::default());
+        for clause in clauses {
+            match clause.node_type() {
+                NodeType::ComprehensionFor {
+                    identifier,
+                    iterator,
+                } => {
+                    let iterator_type = self.expression(iterator);
+                    self.iterable(&iterator_type, iterator.span());
+                    if let NodeType::Identifier(name) = identifier.node_type() {
+                        self.bind(name, Binding::inferred(Type::Any));
+                    }
+                }
+                NodeType::ComprehensionIf(condition) => {
+                    self.expression(condition);
+                }
+                _ => {}
+            }
+        }
+        self.expression(element);
+        self.scopes.pop();
+    }
+
+    /// Checks a call, returning the type of its result
+    fn call(&mut self, name: &Node, arguments: &[Node]) -> Type {
+        let function = self.expression(name);
+        let mut positional = vec![];
+        let mut keywords = vec![];
+        for argument in arguments {
+            match argument.node_type() {
+                NodeType::KeywordArgument { name, value } => {
+                    let found = self.expression(value);
+                    if let NodeType::Identifier(name) = name.node_type() {
+                        keywords.push((name.clone(), found, value.span()));
+                    }
+                }
+                _ => positional.push((self.expression(argument), argument.span())),
+            }
+        }
+        if !self.callable(&function, name.span()) {
+            return Type::Any;
+        }
+        let signature = match function {
+            Type::Function(Some(signature)) => signature,
+            Type::Class(class) => return Type::Instance(class),
+            _ => return Type::Any,
+        };
+        // positional arguments go to the parameters without defaults, in order
+        let required = signature.parameters.iter().filter(|p| !p.has_default);
+        for (parameter, (found, span)) in required.zip(positional) {
+            let annotation = parameter.annotation.clone();
+            self.expect(&parameter.parameter_type, &found, span, annotation);
+        }
+        for (name, found, span) in keywords {
+            if let Some(parameter) = signature.parameters.iter().find(|p| p.name == name) {
+                let annotation = parameter.annotation.clone();
+                self.expect(&parameter.parameter_type, &found, span, annotation);
+            }
+        }
+        signature.returns
+    }
+
+    /// Reports a value that can't be called, returning whether it can be
+    fn callable(&mut self, found: &Type, span: &Span) -> bool {
+        match found {
+            Type::Any | Type::Function(_) | Type::Class(_) => true,
+            _ => {
+                self.error(TypeErrorKind::NotCallable(found.clone()), span);
+                false
+            }
+        }
+    }
+
+    fn iterable(&mut self, found: &Type, span: &Span) {
+        match found {
+            Type::None | Type::Integer | Type::Float | Type::Boolean | Type::Function(_) => {
+                self.error(TypeErrorKind::NotIterable(found.clone()), span)
+            }
+            _ => {}
+        }
+    }
+
+    /// Reports a value whose type doesn't match the type it's expected to have
+    fn expect(&mut self, expected: &Type, found: &Type, span: &Span, annotation: Option<Span>) {
+        if !self.accepts(expected, found) {
+            let kind = TypeErrorKind::Mismatch {
+                expected: expected.clone(),
+                found: found.clone(),
+            };
+            let mut error = TypeError::new(kind, span.clone());
+            error.annotation = annotation;
+            self.errors.push(error);
+        }
+    }
+
+    /// Whether a value of type `found` can be used where `expected` is declared
+    fn accepts(&self, expected: &Type, found: &Type) -> bool {
+        match (expected, found) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Float, Type::Integer) => true,
+            (Type::Function(_), Type::Function(_)) => true,
+            (Type::Instance(expected), Type::Instance(found)) => {
+                let mut class = Some(found);
+                while let Some(name) = class {
+                    if name == expected {
+                        return true;
+                    }
+                    class = self.classes.get(name).and_then(Option::as_ref);
+                }
+                false
+            }
+            (expected, found) => expected == found,
+        }
+    }
+}
+
+/// The type of the result of a binary operation, or None if the operation isn't supported. This
+/// follows what the interpreter's operators accept.
+fn binary_operation(operator: Operator, lhs: &Type, rhs: &Type) -> Option<Type> {
+    let number = |t: &Type| matches!(t, Type::Integer | Type::Float);
+    let result = match operator {
+        Operator::Eq | Operator::Neq => Type::Boolean,
+        Operator::And | Operator::Or => match (lhs, rhs) {
+            (Type::Boolean, Type::Boolean) => Type::Boolean,
+            _ => Type::Any,
+        },
+        Operator::Lt | Operator::Gt | Operator::Lte | Operator::Gte => match (lhs, rhs) {
+            (Type::Any, _) | (_, Type::Any) => Type::Boolean,
+            (l, r) if number(l) && number(r) => Type::Boolean,
+            (Type::String, Type::String) | (Type::List, Type::List) => Type::Boolean,
+            _ => return None,
+        },
+        _ => match (lhs, rhs) {
+            (Type::Any, _) | (_, Type::Any) => Type::Any,
+            (Type::Integer, Type::Integer) if operator == Operator::Div => Type::Float,
+            (Type::Integer, Type::Integer) => Type::Integer,
+            (l, r) if number(l) && number(r) => Type::Float,
+            (Type::String, Type::String) if operator == Operator::Add => Type::String,
+            (Type::String, Type::Integer) | (Type::Integer, Type::String)
+                if operator == Operator::Mul =>
+            {
+                Type::String
+            }
+            (Type::List, Type::List) if operator == Operator::Add => Type::List,
+            (Type::List, Type::Integer) | (Type::Integer, Type::List)
+                if operator == Operator::Mul =>
+            {
+                Type::List
+            }
+            _ => return None,
+        },
+    };
+    Some(result)
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeError {
+    kind: TypeErrorKind,
+    span: Span,
+    annotation: Option<Span>,
+}
+
+impl TypeError {
+    pub fn new(kind: TypeErrorKind, span: Span) -> Self {
+        TypeError {
+            kind,
+            span,
+            annotation: None,
+        }
+    }
+
+    pub fn kind(&self) -> &TypeErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// The annotation that declared the expected type of a mismatched value
+    pub fn annotation(&self) -> Option<&Span> {
+        self.annotation.as_ref()
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.kind.to_string(), self.span.clone());
+        let diagnostic = match &self.kind {
+            TypeErrorKind::Mismatch { found, .. } => {
+                diagnostic.with_primary_label(format!("this is {}", found))
+            }
+            TypeErrorKind::UnknownType(_) => diagnostic.with_help(
+                "types are Any, None, Integer, Float, String, Boolean, List, Dictionary, Range, \
+                 Function and the names of classes",
+            ),
+            _ => diagnostic,
+        };
+        match &self.annotation {
+            Some(annotation) => {
+                diagnostic.with_label(annotation.clone(), "expected because of this")
+            }
+            None => diagnostic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeErrorKind {
+    /// A value of a different type than the one it was declared with
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    /// A call of a value that isn't a function or a class
+    NotCallable(Type),
+    NotIterable(Type),
+    /// A parent of a class that isn't a class
+    NotAClass(Type),
+    UnsupportedOperands {
+        operator: Operator,
+        lhs: Type,
+        rhs: Type,
+    },
+    UnsupportedOperand {
+        operator: Operator,
+        value: Type,
+    },
+    /// A name in an annotation that isn't a type
+    UnknownType(String),
+    /// An annotation that isn't a name, such as `1 + 2`
+    InvalidAnnotation,
+}
+
+impl Display for TypeErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeErrorKind::Mismatch { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            TypeErrorKind::NotCallable(found) => write!(f, "{} is not callable", found),
+            TypeErrorKind::NotIterable(found) => write!(f, "{} is not iterable", found),
+            TypeErrorKind::NotAClass(found) => write!(f, "{} is not a class", found),
+            TypeErrorKind::UnsupportedOperands { operator, lhs, rhs } => write!(
+                f,
+                "unsupported operand types for {}: {} and {}",
+                operator_str(*operator),
+                lhs,
+                rhs
+            ),
+            TypeErrorKind::UnsupportedOperand { operator, value } => write!(
+                f,
+                "unsupported operand type for {}: {}",
+                operator_str(*operator),
+                value
+            ),
+            TypeErrorKind::UnknownType(name) => write!(f, "unknown type `{}`", name),
+            TypeErrorKind::InvalidAnnotation => write!(f, "expected a type"),
+        }
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::test_util::source;
+
+    fn errors(text: &str) -> Vec<TypeErrorKind> {
+        match check(&parse(&source(text)).unwrap()) {
+            Ok(()) => vec![],
+            Err(errors) => errors.iter().map(|error| error.kind().clone()).collect(),
+        }
+    }
+
+    fn mismatch(expected: Type, found: Type) -> TypeErrorKind {
+        TypeErrorKind::Mismatch { expected, found }
+    }
+
+    #[test]
+    fn annotations() {
+        let source = "\
+count: Integer = 'zero'
+def half(x: Integer, by: Float = 2) -> String:
+    return x / by
+half('four')
+half(4, by='two')
+count = 1.5
+";
+        assert_eq!(
+            errors(source),
+            vec![
+                mismatch(Type::Integer, Type::String),
+                mismatch(Type::String, Type::Float),
+                mismatch(Type::Integer, Type::String),
+                mismatch(Type::Float, Type::String),
+                mismatch(Type::Integer, Type::Float),
+            ]
+        );
+    }
+
+    #[test]
+    fn inferred_errors() {
+        let source = "\
+x = 1
+x()
+y = 'a' + [1]
+z = -'a'
+for c in 2.5:
+    pass
+class A(x):
+    pass
+";
+        assert_eq!(
+            errors(source),
+            vec![
+                TypeErrorKind::NotCallable(Type::Integer),
+                TypeErrorKind::UnsupportedOperands {
+                    operator: Operator::Add,
+                    lhs: Type::String,
+                    rhs: Type::List
+                },
+                TypeErrorKind::UnsupportedOperand {
+                    operator: Operator::Sub,
+                    value: Type::String
+                },
+                TypeErrorKind::NotIterable(Type::Float),
+                TypeErrorKind::NotAClass(Type::Integer),
+            ]
+        );
+    }
+
+    #[test]
+    fn unpacking() {
+        let source = "\
+n: Integer = 1
+n, s = 2, 'two'
+s = s + 1
+a, b = 1
+";
+        // the types of the elements aren't known, so only what's certain to fail is reported
+        assert_eq!(
+            errors(source),
+            vec![TypeErrorKind::NotIterable(Type::Integer)]
+        );
+    }
+
+    #[test]
+    fn exception_handlers() {
+        let source = "\
+class Oops:
+    pass
+def handle(error: Oops):
+    pass
+n = 1
+try:
+    n = 'one'
+except Oops as e:
+    handle(e)
+except (Oops, Oops) as e:
+    handle(e)
+finally:
+    s: String = 1
+m: Integer = n
+";
+        // `n` might have been reassigned before the exception, so its type isn't known
+        assert_eq!(errors(source), vec![mismatch(Type::String, Type::Integer)]);
+        assert_eq!(
+            errors(
+                "class Oops:\n    pass\ntry:\n    pass\nexcept Oops as e:\n    x: Integer = e\n"
+            ),
+            vec![mismatch(Type::Integer, Type::Instance("Oops".to_string()))]
+        );
+    }
+
+    #[test]
+    fn gradual() {
+        // unannotated code is never rejected unless it can't work
+        let source = "\
+def f(a, b=1):
+    return a + b
+x = 1
+if f(x):
+    x = 'one'
+y = x + 1
+n: Integer = f(2)
+g = lambda v: v
+s: String = g(1)
+z = 1
+z = 'z'
+w = z + 'w'
+";
+        assert_eq!(errors(source), vec![]);
+    }
+
+    #[test]
+    fn classes_and_functions() {
+        let source = "\
+class Shape:
+    pass
+class Square(Shape):
+    def area() -> Integer:
+        return this
+def describe(shape: Shape) -> String:
+    return 'shape'
+def apply(f: Function, total: Float) -> Float:
+    return total
+describe(Square())
+describe(1)
+apply(describe, 1)
+apply(1, 2.5)
+label: String = describe(Shape())
+size: Integer = describe(Shape())
+check: Integer = 1
+def inner():
+    check = 'no'
+    count: Unknown = 1
+";
+        assert_eq!(
+            errors(source),
+            vec![
+                mismatch(Type::Integer, Type::Instance("Square".to_string())),
+                mismatch(Type::Instance("Shape".to_string()), Type::Integer),
+                mismatch(Type::Function(None), Type::Integer),
+                mismatch(Type::Integer, Type::String),
+                TypeErrorKind::UnknownType("Unknown".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        let file = source("def f(x: Integer):\n    pass\nf('a')\n");
+        let errors = check(&parse(&file).unwrap()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(file.slice(errors[0].span()), Some("'a'"));
+        assert_eq!(file.slice(errors[0].annotation().unwrap()), Some("Integer"));
+
+        let diagnostic = errors[0].diagnostic();
+        assert_eq!(diagnostic.message(), "expected Integer, found String");
+        assert_eq!(diagnostic.secondary().len(), 1);
+    }
+}
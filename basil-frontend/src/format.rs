@@ -0,0 +1,862 @@
+//! Prints a parsed file back out in a canonical style.
+//!
+//! Statements are indented with four spaces, operators and commas are spaced the same way
+//! everywhere, and parentheses are only kept where they're needed. A statement that doesn't fit in
+//! [MAX_WIDTH] columns has its last bracketed expression split over several lines, one element per
+//! line. Literals are copied from the source as they were written, so the base of a number and the
+//! quotes and escapes of a string are kept.
+//!
+//! Comments and blank lines come from the [Trivia](basil_ast::trivia::Trivia) of each statement.
+//! Runs of blank lines are shortened to one, and blank lines at the start of a block are removed.
+//! Formatting is idempotent: formatting the output again doesn't change it.
+
+use basil_ast::node::{Node, NodeType};
+use basil_ast::operators::Operator;
+use basil_ast::trivia::TriviaPiece;
+use basil_core::source_map::SourceFile;
+use basil_core::span::Span;
+
+use crate::parser::{parse_recovering, ParseError};
+use crate::token::operator_str;
+
+/// The widest a line can be before the formatter splits it
+pub const MAX_WIDTH: usize = 100;
+
+const INDENT: usize = 4;
+
+/// Formats a source file. Files with syntax errors aren't formatted, and every error is returned
+/// instead.
+pub fn format(source: &SourceFile) -> Result<String, Vec<ParseError>> {
+    let (tree, errors) = parse_recovering(source);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let mut printer = Printer::new(source);
+    printer.module(&tree);
+    Ok(printer.output)
+}
+
+/// Checks whether a source file is already formatted, which is what a check run reports on
+pub fn is_formatted(source: &SourceFile) -> Result<bool, Vec<ParseError>> {
+    Ok(format(source)? == source.text())
+}
+
+struct Printer<'s> {
+    source: &'s SourceFile,
+    output: String,
+    indent: usize,
+    /// Whether nothing has been printed in the current block yet
+    block_start: bool,
+}
+
+impl<'s> Printer<'s> {
+    fn new(source: &'s SourceFile) -> Self {
+        Printer {
+            source,
+            output: String::new(),
+            indent: 0,
+            block_start: true,
+        }
+    }
+
+    /// The text of a span, exactly as it was written
+    fn source_text(&self, span: &Span) -> String {
+        self.source.slice(span).unwrap_or_default().to_string()
+    }
+
+    fn module(&mut self, module: &Node) {
+        if let NodeType::Block(statements) = module.node_type() {
+            self.statements(statements, &module.trivia().dangling);
+        }
+    }
+
+    fn statements(&mut self, statements: &[Node], dangling: &[TriviaPiece]) {
+        for statement in statements {
+            self.trivia(&statement.trivia().leading);
+            self.statement(statement);
+        }
+        self.trivia(dangling);
+    }
+
+    /// Prints a block of statements one level deeper than the current one
+    fn body(&mut self, statements: &[Node], dangling: &[TriviaPiece]) {
+        self.indent += INDENT;
+        self.block_start = true;
+        self.statements(statements, dangling);
+        self.indent -= INDENT;
+    }
+
+    fn block(&mut self, block: &Node) {
+        match block.node_type() {
+            NodeType::Block(statements) => self.body(statements, &block.trivia().dangling),
+            _ => self.body(std::slice::from_ref(block), &[]),
+        }
+    }
+
+    fn trivia(&mut self, pieces: &[TriviaPiece]) {
+        let mut blank = false;
+        for piece in pieces {
+            match piece {
+                TriviaPiece::BlankLine => blank = true,
+                TriviaPiece::Comment(comment) => {
+                    self.blank_line(blank);
+                    blank = false;
+                    self.line(comment, None);
+                }
+            }
+        }
+        self.blank_line(blank);
+    }
+
+    fn blank_line(&mut self, blank: bool) {
+        if blank && !self.block_start {
+            self.output.push('\n');
+        }
+    }
+
+    fn line(&mut self, text: &str, comment: Option<&str>) {
+        for _ in 0..self.indent {
+            self.output.push(' ');
+        }
+        self.output.push_str(text);
+        if let Some(comment) = comment {
+            self.output.push_str("  ");
+            self.output.push_str(comment);
+        }
+        self.output.push('\n');
+        self.block_start = false;
+    }
+
+    fn statement(&mut self, statement: &Node) {
+        let comment = statement.trivia().trailing.as_deref();
+        match statement.node_type() {
+            NodeType::If { .. } => self.if_statement(statement, "if", comment),
+            NodeType::While {
+                condition,
+                block,
+                r#else,
+            } => {
+                let header = format!("while {}:", self.expression(condition));
+                self.line(&header, comment);
+                self.block(block);
+                self.loop_else(r#else);
+            }
+            NodeType::For {
+                identifier,
+                iterator,
+                block,
+                r#else,
+            } => {
+                let header = format!(
+                    "for {} in {}:",
+                    self.expression(identifier),
+                    self.expression(iterator)
+                );
+                self.line(&header, comment);
+                self.block(block);
+                self.loop_else(r#else);
+            }
+            NodeType::Match { subject, cases } => {
+                let header = format!("match {}:", self.expression(subject));
+                self.line(&header, comment);
+                self.body(cases, &statement.trivia().dangling);
+            }
+            NodeType::MatchCase {
+                pattern,
+                guard,
+                block,
+            } => {
+                let header = match guard {
+                    Some(guard) => format!(
+                        "case {} if {}:",
+                        self.expression(pattern),
+                        self.expression(guard)
+                    ),
+                    None => format!("case {}:", self.expression(pattern)),
+                };
+                self.line(&header, comment);
+                self.block(block);
+            }
+            NodeType::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                self.line("try:", comment);
+                self.block(block);
+                for handler in handlers {
+                    self.statement(handler);
+                }
+                for (keyword, block) in [("else:", r#else), ("finally:", finally)] {
+                    if let Some(block) = block {
+                        self.line(keyword, None);
+                        self.block(block);
+                    }
+                }
+            }
+            NodeType::ExceptHandler { class, name, block } => {
+                let header = match (class, name) {
+                    (Some(class), Some(name)) => format!(
+                        "except {} as {}:",
+                        self.expression(class),
+                        self.expression(name)
+                    ),
+                    (Some(class), None) => format!("except {}:", self.expression(class)),
+                    _ => "except:".to_string(),
+                };
+                self.line(&header, comment);
+                self.block(block);
+            }
+            NodeType::Function {
+                name,
+                parameters,
+                returns,
+                block,
+            } => {
+                let mut header =
+                    format!("def {}({})", self.expression(name), self.list(parameters));
+                if let Some(returns) = returns {
+                    header = format!("{} -> {}", header, self.expression(returns));
+                }
+                header.push(':');
+                self.line(&header, comment);
+                self.block(block);
+            }
+            NodeType::Class { name, parent, defs } => {
+                let header = match parent {
+                    Some(parent) => format!(
+                        "class {}({}):",
+                        self.expression(name),
+                        self.expression(parent)
+                    ),
+                    None => format!("class {}:", self.expression(name)),
+                };
+                self.line(&header, comment);
+                self.body(defs, &statement.trivia().dangling);
+            }
+            NodeType::Decorated {
+                decorators,
+                definition,
+            } => {
+                let mut comment = comment;
+                for decorator in decorators {
+                    let line = format!("@{}", self.expression(decorator));
+                    self.line(&line, comment.take());
+                }
+                self.trivia(&definition.trivia().leading);
+                self.statement(definition);
+            }
+            _ => {
+                let mut text = self.simple_statement(statement);
+                if width(&text) + self.indent > MAX_WIDTH {
+                    if let Some(wrapped) = self.wrap(statement, self.indent) {
+                        text = wrapped;
+                    }
+                }
+                self.line(&text, comment);
+            }
+        }
+    }
+
+    fn loop_else(&mut self, r#else: &Option<Node>) {
+        if let Some(r#else) = r#else {
+            self.line("else:", None);
+            self.block(r#else);
+        }
+    }
+
+    fn if_statement(&mut self, statement: &Node, keyword: &str, comment: Option<&str>) {
+        if let NodeType::If {
+            condition,
+            block,
+            r#else,
+        } = statement.node_type()
+        {
+            let header = format!("{} {}:", keyword, self.expression(condition));
+            self.line(&header, comment);
+            self.block(block);
+            match r#else {
+                Some(r#else) if matches!(r#else.node_type(), NodeType::If { .. }) => {
+                    self.if_statement(r#else, "elif", None)
+                }
+                Some(r#else) => {
+                    self.line("else:", None);
+                    self.block(r#else);
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn simple_statement(&self, statement: &Node) -> String {
+        match statement.node_type() {
+            NodeType::Pass => "pass".to_string(),
+            NodeType::Break => "break".to_string(),
+            NodeType::Continue => "continue".to_string(),
+            NodeType::Return(value) if self.source_text(value.span()) == "return" => {
+                "return".to_string()
+            }
+            NodeType::Return(value) => format!("return {}", self.expression(value)),
+            NodeType::Yield(value) => format!("yield {}", self.expression(value)),
+            NodeType::Raise(value) => format!("raise {}", self.expression(value)),
+            NodeType::Import(path) => format!("import {}", self.expression(path)),
+            NodeType::Assignment { lhs, var_type, rhs } => match var_type {
+                Some(var_type) => format!(
+                    "{}: {} = {}",
+                    self.expression(lhs),
+                    self.expression(var_type),
+                    self.expression(rhs)
+                ),
+                None => format!(
+                    "{} = {}",
+                    self.expression_list(lhs),
+                    self.expression_list(rhs)
+                ),
+            },
+            _ => self.expression_list(statement),
+        }
+    }
+
+    /// Prints an expression, leaving a tuple without parentheses if it was written without them
+    fn expression_list(&self, node: &Node) -> String {
+        match node.node_type() {
+            NodeType::Tuple(elements)
+                if !elements.is_empty() && !self.source_text(node.span()).starts_with('(') =>
+            {
+                match elements.as_slice() {
+                    [element] => format!("{},", self.expression(element)),
+                    _ => self.list(elements),
+                }
+            }
+            _ => self.expression(node),
+        }
+    }
+
+    /// Prints a statement that's too long with its last bracketed expression split over several
+    /// lines, or returns None if it doesn't have one
+    fn wrap(&self, node: &Node, indent: usize) -> Option<String> {
+        let prefixed = |prefix: &str, value: &Node| {
+            self.wrap(value, indent)
+                .map(|value| format!("{}{}", prefix, value))
+        };
+        match node.node_type() {
+            NodeType::Return(value) => prefixed("return ", value),
+            NodeType::Yield(value) => prefixed("yield ", value),
+            NodeType::Raise(value) => prefixed("raise ", value),
+            NodeType::Assignment {
+                lhs,
+                var_type: None,
+                rhs,
+            } => prefixed(&format!("{} = ", self.expression_list(lhs)), rhs),
+            NodeType::KeywordArgument { name, value } => {
+                prefixed(&format!("{}=", self.expression(name)), value)
+            }
+            NodeType::DictionaryEntry { key, value } => {
+                prefixed(&format!("{}: ", self.expression(key)), value)
+            }
+            NodeType::BinaryExpression { lhs, rhs, op } if !needs_parens(rhs, op, true) => {
+                let prefix = format!("{} {} ", self.operand(lhs, op, false), self.expression(op));
+                prefixed(&prefix, rhs)
+            }
+            NodeType::FunctionCall { name, parameters } if !parameters.is_empty() => Some(format!(
+                "{}({})",
+                self.postfix_base(name),
+                self.split(parameters, indent)
+            )),
+            NodeType::List(elements) if !elements.is_empty() => {
+                Some(format!("[{}]", self.split(elements, indent)))
+            }
+            NodeType::Tuple(elements) if !elements.is_empty() => {
+                Some(format!("({})", self.split(elements, indent)))
+            }
+            NodeType::Set(elements) => Some(format!("{{{}}}", self.split(elements, indent))),
+            NodeType::Dictionary(elements) if !elements.is_empty() => {
+                Some(format!("{{{}}}", self.split(elements, indent)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Puts each element on its own line, indented one level past `indent`, with a comma after
+    /// every element
+    fn split(&self, elements: &[Node], indent: usize) -> String {
+        let inner = indent + INDENT;
+        let mut text = String::from("\n");
+        for element in elements {
+            let mut element_text = self.expression(element);
+            if inner + width(&element_text) + 1 > MAX_WIDTH {
+                if let Some(wrapped) = self.wrap(element, inner) {
+                    element_text = wrapped;
+                }
+            }
+            text.push_str(&" ".repeat(inner));
+            text.push_str(&element_text);
+            text.push_str(",\n");
+        }
+        text.push_str(&" ".repeat(indent));
+        text
+    }
+
+    fn list(&self, nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| self.expression(node))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn expression(&self, node: &Node) -> String {
+        match node.node_type() {
+            NodeType::Identifier(name) => name.clone(),
+            NodeType::Integer(_)
+            | NodeType::Float(_)
+            | NodeType::String(_)
+            | NodeType::FormatString(_) => self.source_text(node.span()),
+            NodeType::Boolean(true) => "True".to_string(),
+            NodeType::Boolean(false) => "False".to_string(),
+            NodeType::None => "None".to_string(),
+            NodeType::Operator(op) => operator_str(*op).to_string(),
+            NodeType::QualifiedIdentifier { parent, child } => {
+                format!("{}.{}", self.postfix_base(parent), self.expression(child))
+            }
+            NodeType::Index { value, index } => {
+                format!("{}[{}]", self.postfix_base(value), self.expression(index))
+            }
+            NodeType::FunctionCall { name, parameters } => {
+                format!("{}({})", self.postfix_base(name), self.list(parameters))
+            }
+            NodeType::KeywordArgument { name, value }
+            | NodeType::Parameter {
+                name,
+                annotation: None,
+                default: Some(value),
+            } => format!("{}={}", self.expression(name), self.expression(value)),
+            NodeType::Parameter {
+                name,
+                annotation: Some(annotation),
+                default: Some(value),
+            } => format!(
+                "{}: {} = {}",
+                self.expression(name),
+                self.expression(annotation),
+                self.expression(value)
+            ),
+            NodeType::Parameter {
+                name,
+                annotation: Some(annotation),
+                default: None,
+            } => format!("{}: {}", self.expression(name), self.expression(annotation)),
+            NodeType::Parameter {
+                name,
+                annotation: None,
+                default: None,
+            } => self.expression(name),
+            NodeType::Lambda { parameters, body } if parameters.is_empty() => {
+                format!("lambda: {}", self.expression(body))
+            }
+            NodeType::Lambda { parameters, body } => {
+                format!(
+                    "lambda {}: {}",
+                    self.list(parameters),
+                    self.expression(body)
+                )
+            }
+            NodeType::BinaryExpression { lhs, rhs, op } => format!(
+                "{} {} {}",
+                self.operand(lhs, op, false),
+                self.expression(op),
+                self.operand(rhs, op, true)
+            ),
+            NodeType::UnaryExpression { val, op } => {
+                let operand = if precedence(val) < precedence(node) {
+                    format!("({})", self.expression(val))
+                } else {
+                    self.expression(val)
+                };
+                match op.node_type() {
+                    NodeType::Operator(Operator::Not) => format!("not {}", operand),
+                    _ => format!("-{}", operand),
+                }
+            }
+            NodeType::List(elements) => format!("[{}]", self.list(elements)),
+            NodeType::Tuple(elements) if elements.len() == 1 => {
+                format!("({},)", self.expression(&elements[0]))
+            }
+            NodeType::Tuple(elements) => format!("({})", self.list(elements)),
+            NodeType::Set(elements) | NodeType::Dictionary(elements) => {
+                format!("{{{}}}", self.list(elements))
+            }
+            NodeType::DictionaryEntry { key, value } => {
+                format!("{}: {}", self.expression(key), self.expression(value))
+            }
+            NodeType::ListComprehension { element, clauses } => {
+                format!("[{}]", self.comprehension(element, clauses))
+            }
+            NodeType::SetComprehension { element, clauses }
+            | NodeType::DictionaryComprehension { element, clauses } => {
+                format!("{{{}}}", self.comprehension(element, clauses))
+            }
+            NodeType::ComprehensionFor {
+                identifier,
+                iterator,
+            } => format!(
+                "for {} in {}",
+                self.expression(identifier),
+                self.expression(iterator)
+            ),
+            NodeType::ComprehensionIf(condition) => format!("if {}", self.expression(condition)),
+            _ => self.simple_statement(node),
+        }
+    }
+
+    fn comprehension(&self, element: &Node, clauses: &[Node]) -> String {
+        let mut text = self.expression(element);
+        for clause in clauses {
+            text.push(' ');
+            text.push_str(&self.expression(clause));
+        }
+        text
+    }
+
+    /// Prints the value that a call, member access or index is applied to
+    fn postfix_base(&self, node: &Node) -> String {
+        if precedence(node) < POSTFIX {
+            format!("({})", self.expression(node))
+        } else {
+            self.expression(node)
+        }
+    }
+
+    /// Prints an operand of a binary operator, in parentheses if it would otherwise be grouped
+    /// differently
+    fn operand(&self, operand: &Node, op: &Node, right: bool) -> String {
+        if needs_parens(operand, op, right) {
+            format!("({})", self.expression(operand))
+        } else {
+            self.expression(operand)
+        }
+    }
+}
+
+fn needs_parens(operand: &Node, op: &Node, right: bool) -> bool {
+    let op = match op.node_type() {
+        NodeType::Operator(op) => binary_precedence(*op),
+        _ => return false,
+    };
+    // binary operators are left associative, so an operand on the right with the same precedence
+    // has to be grouped
+    precedence(operand) < op || (right && precedence(operand) == op)
+}
+
+const LAMBDA: u8 = 0;
+const NOT: u8 = 3;
+const NEGATION: u8 = 7;
+const POSTFIX: u8 = 8;
+
+fn binary_precedence(op: Operator) -> u8 {
+    match op {
+        Operator::Or => 1,
+        Operator::And => 2,
+        Operator::Not => NOT,
+        Operator::Eq
+        | Operator::Neq
+        | Operator::Lt
+        | Operator::Gt
+        | Operator::Lte
+        | Operator::Gte => 4,
+        Operator::Add | Operator::Sub => 5,
+        Operator::Mul | Operator::Div | Operator::Rem => 6,
+    }
+}
+
+fn precedence(node: &Node) -> u8 {
+    match node.node_type() {
+        NodeType::BinaryExpression { op, .. } => match op.node_type() {
+            NodeType::Operator(op) => binary_precedence(*op),
+            _ => POSTFIX,
+        },
+        NodeType::UnaryExpression { op, .. } => match op.node_type() {
+            NodeType::Operator(Operator::Not) => NOT,
+            _ => NEGATION,
+        },
+        NodeType::Lambda { .. } => LAMBDA,
+        _ => POSTFIX,
+    }
+}
+
+/// The width of the widest line of some text
+fn width(text: &str) -> usize {
+    text.split('\n')
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::source;
+
+    fn formatted(text: &str) -> String {
+        let output = format(&source(text)).unwrap();
+        assert_eq!(
+            format(&source(&output)).unwrap(),
+            output,
+            "formatting isn't idempotent"
+        );
+        output
+    }
+
+    #[test]
+    fn spacing_and_indentation() {
+        let source = "\
+x=1+2*  3
+def f( a,b = 2 ) :
+  if a: return a
+  elif b :
+        return  b
+  else :
+        return
+class C ( object ) :
+   y=[1,2 ,3]
+   z = {'a':1}
+";
+        let expected = "\
+x = 1 + 2 * 3
+def f(a, b=2):
+    if a:
+        return a
+    elif b:
+        return b
+    else:
+        return
+class C(object):
+    y = [1, 2, 3]
+    z = {'a': 1}
+";
+        assert_eq!(formatted(source), expected);
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let source = "\
+# header
+
+
+import a.b  # trailing
+
+def f(x):  # on the header
+
+    # inside
+    y = [
+        1,  # in a list
+        2,
+    ]
+    return y
+    # end of f
+
+
+# before g
+def g():
+    pass
+  # dedented, so it belongs to what comes next
+
+# end of file
+";
+        let expected = "\
+# header
+
+import a.b  # trailing
+
+def f(x):  # on the header
+    # inside
+    # in a list
+    y = [1, 2]
+    return y
+    # end of f
+
+# before g
+def g():
+    pass
+# dedented, so it belongs to what comes next
+
+# end of file
+";
+        assert_eq!(formatted(source), expected);
+    }
+
+    #[test]
+    fn match_statements() {
+        let source = "\
+match  command:  # dispatch
+    case {'go':[x,y]}  if x>0:
+        move(x,y)
+
+    # points
+    case Point(x=0): pass
+";
+        let expected = "\
+match command:  # dispatch
+    case {'go': [x, y]} if x > 0:
+        move(x, y)
+
+    # points
+    case Point(x=0):
+        pass
+";
+        assert_eq!(formatted(source), expected);
+    }
+
+    #[test]
+    fn annotations() {
+        let source = "def f(a:Integer,b :String='x')->None:\n    c:Float=a\n";
+        let expected = "def f(a: Integer, b: String = 'x') -> None:\n    c: Float = a\n";
+        assert_eq!(formatted(source), expected);
+        assert_eq!(formatted(expected), expected);
+    }
+
+    #[test]
+    fn decorators() {
+        let source = "\
+@app.on( 'click' )  # clicks
+@log
+# the handler
+def handler(event):
+    pass
+";
+        let expected = "\
+@app.on('click')  # clicks
+@log
+# the handler
+def handler(event):
+    pass
+";
+        assert_eq!(formatted(source), expected);
+        assert_eq!(formatted(expected), expected);
+    }
+
+    #[test]
+    fn loop_else() {
+        let source = "\
+for x in xs :  # every x
+    if x: continue
+    break
+else :
+    pass
+while a:b
+else:c
+";
+        let expected = "\
+for x in xs:  # every x
+    if x:
+        continue
+    break
+else:
+    pass
+while a:
+    b
+else:
+    c
+";
+        assert_eq!(formatted(source), expected);
+        assert_eq!(formatted(expected), expected);
+    }
+
+    #[test]
+    fn try_statements() {
+        let source = "\
+try :  # might fail
+    risky( )
+except KeyError as e :print(e)
+except(A,B):
+    pass
+except :
+    raise Failed
+else: done()
+finally :
+    close()
+";
+        let expected = "\
+try:  # might fail
+    risky()
+except KeyError as e:
+    print(e)
+except (A, B):
+    pass
+except:
+    raise Failed
+else:
+    done()
+finally:
+    close()
+";
+        assert_eq!(formatted(source), expected);
+        assert_eq!(formatted(expected), expected);
+    }
+
+    #[test]
+    fn tuples_without_parentheses() {
+        assert_eq!(formatted("a,b = b,a\n"), "a, b = b, a\n");
+        assert_eq!(formatted("(a, b) = (b, a)\n"), "(a, b) = (b, a)\n");
+        assert_eq!(formatted("x, = 1 ,\n"), "x, = 1,\n");
+        assert_eq!(formatted("a , b\n"), "a, b\n");
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(formatted("x = (a + b) * c\n"), "x = (a + b) * c\n");
+        assert_eq!(formatted("x = a + (b * c)\n"), "x = a + b * c\n");
+        assert_eq!(formatted("x = a - (b - c)\n"), "x = a - (b - c)\n");
+        assert_eq!(formatted("x = (a - b) - c\n"), "x = a - b - c\n");
+        assert_eq!(formatted("x = not (a and b)\n"), "x = not (a and b)\n");
+        assert_eq!(formatted("x = -(a + b).c\n"), "x = -(a + b).c\n");
+        assert_eq!(formatted("x = (-a).b\n"), "x = (-a).b\n");
+        assert_eq!(formatted("x = (1,)\n"), "x = (1,)\n");
+        assert_eq!(formatted("f = (lambda:a+1)\n"), "f = lambda: a + 1\n");
+        assert_eq!(
+            formatted("f = (lambda a,b=1:a)(1)\n"),
+            "f = (lambda a, b=1: a)(1)\n"
+        );
+        assert_eq!(
+            formatted("f = a or (lambda: b)\n"),
+            "f = a or (lambda: b)\n"
+        );
+    }
+
+    #[test]
+    fn literals_are_kept() {
+        let source =
+            "x = 0xFF + 1_000 + 1.5e3\ny = r'a\\b' + \"\"\"multi\nline\"\"\"\nz = f'{a!r}'\n";
+        assert_eq!(formatted(source), source);
+    }
+
+    #[test]
+    fn long_lines() {
+        let source = format!(
+            "if True:\n    result = call(first_argument_{0}, second_argument_{0}, key=[{1}])\n",
+            "x".repeat(20),
+            "element, ".repeat(8)
+        );
+        let expected = format!(
+            "\
+if True:
+    result = call(
+        first_argument_{0},
+        second_argument_{0},
+        key=[{1}],
+    )
+",
+            "x".repeat(20),
+            ["element"; 8].join(", ")
+        );
+        assert_eq!(formatted(&source), expected);
+    }
+
+    #[test]
+    fn check() {
+        assert!(is_formatted(&source("x = 1\n")).unwrap());
+        assert!(!is_formatted(&source("x  =  1\n")).unwrap());
+        assert!(is_formatted(&source("x = (\n")).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt::{Display, Formatter};
+
+use basil_ast::node::{Node, NodeType};
+use basil_ast::visit::{walk_mut, VisitorMut};
+use basil_core::source_map::SourceFile;
+use basil_core::span::Span;
+
+use crate::lexer::Lexer;
+use crate::parser::{parse_recovering, parse_tokens, ParseError};
+use crate::trivia::{attach_dangling, attach_statements, clear_dangling, Pieces};
+
+/// A change to a source file, replacing the text covered by `span` with `text`
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    span: Span,
+    text: String,
+}
+
+impl TextEdit {
+    pub fn new<S: Into<String>>(span: Span, text: S) -> Self {
+        TextEdit {
+            span,
+            text: text.into(),
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// A parsed source file that can be edited without parsing all of it again.
+///
+/// Only the top level statements that an edit touches are parsed again, along with the statements
+/// on either side of them, because a change in indentation can move lines from one statement into
+/// another. Every other statement is reused as is, with its spans shifted by the number of bytes
+/// the edit added or removed.
+///
+/// When the file has syntax errors the whole file is parsed again instead, so the errors are
+/// always the same as the ones from [parse_recovering].
+pub struct Document {
+    source: SourceFile,
+    tree: Node,
+    errors: Vec<ParseError>,
+}
+
+/// The statements that have to be parsed again after an edit, and the bytes of the edited source
+/// they cover
+struct Region {
+    first: usize,
+    last: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Document {
+    pub fn new(source: SourceFile) -> Self {
+        let (tree, errors) = parse_recovering(&source);
+        Document {
+            source,
+            tree,
+            errors,
+        }
+    }
+
+    pub fn source(&self) -> &SourceFile {
+        &self.source
+    }
+
+    pub fn tree(&self) -> &Node {
+        &self.tree
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Applies an edit to the source, and updates the tree to match it
+    pub fn apply(&mut self, edit: &TextEdit) -> Result<&Node, InvalidEdit> {
+        let span = edit.span();
+        let text = self.source.text();
+        if span.file() != self.source.id()
+            || span.end() > text.len()
+            || !text.is_char_boundary(span.start())
+            || !text.is_char_boundary(span.end())
+        {
+            return Err(InvalidEdit::new(span.clone()));
+        }
+
+        let bytes_added = edit.text().len() as isize - span.len() as isize;
+        let region = match self.errors.is_empty() {
+            true => self.region_around(span, bytes_added),
+            false => None,
+        };
+        self.source.replace_range(span.range(), edit.text());
+        let reparsed = match region {
+            Some(region) => self.reparse(region, bytes_added),
+            None => false,
+        };
+        if !reparsed {
+            let (tree, errors) = parse_recovering(&self.source);
+            self.tree = tree;
+            self.errors = errors;
+        }
+        Ok(&self.tree)
+    }
+
+    /// Finds the top level statements around an edit, before the edit is made to the source
+    fn region_around(&self, edit: &Span, bytes_added: isize) -> Option<Region> {
+        let statements = match self.tree.node_type() {
+            NodeType::Block(statements) => statements,
+            _ => return None,
+        };
+        let source = &self.source;
+        let line_start = |offset: usize| source.line_start(source.location(offset).line);
+        let next_line_start = |offset: usize| source.line_start(source.location(offset).line + 1);
+        let edit_start = line_start(edit.start())?;
+        let edit_end = next_line_start(edit.end()).unwrap_or(usize::MAX);
+
+        // statements[first..last] are parsed again, which includes one statement on either side
+        let before = statements
+            .iter()
+            .take_while(|statement| statement.span().end() < edit_start)
+            .count();
+        let first = before.saturating_sub(1);
+        let after = statements
+            .iter()
+            .position(|statement| statement.span().start() >= edit_end)
+            .unwrap_or(statements.len());
+        let last = (after + 1).min(statements.len());
+
+        let start = match first {
+            0 => 0,
+            _ => next_line_start(statements[first - 1].span().end())?,
+        };
+        let end = match statements.get(last) {
+            Some(next) => (line_start(next.span().start())? as isize + bytes_added) as usize,
+            None => (source.text().len() as isize + bytes_added) as usize,
+        };
+        Some(Region {
+            first,
+            last,
+            start,
+            end,
+        })
+    }
+
+    /// Parses the statements in a region of the edited source again, keeping the rest of the
+    /// tree.
+    ///
+    /// Returns false if the tree couldn't be updated this way, in which case the whole file needs
+    /// to be parsed again.
+    fn reparse(&mut self, region: Region, bytes_added: isize) -> bool {
+        let source = &self.source;
+        let mut lexer = Lexer::new(source.id(), &source.text()[region.start..region.end])
+            .starting_at(region.start);
+        let (parsed, errors) = parse_tokens(&mut lexer);
+        if !errors.is_empty() {
+            return false;
+        }
+        let mut reparsed = match parsed.into_node_type() {
+            NodeType::Block(statements) => statements,
+            _ => return false,
+        };
+        let mut statements = match self.tree.node_type_mut() {
+            NodeType::Block(statements) => std::mem::take(statements),
+            _ => return false,
+        };
+
+        let mut suffix = statements.split_off(region.last);
+        statements.truncate(region.first);
+        if bytes_added != 0 {
+            for statement in &mut suffix {
+                Shift(bytes_added).visit_node_mut(statement);
+            }
+        }
+
+        // the region's comments can belong to the blocks at the end of the statement before it,
+        // and whatever is left over belongs to the statement after it
+        let mut pieces = Pieces::new(source, lexer.take_trivia());
+        let suffix_start = suffix.first().map(|next| next.span().start());
+        if let Some(previous) = statements.last_mut() {
+            let next = reparsed
+                .first()
+                .map(|next| next.span().start())
+                .or(suffix_start);
+            clear_dangling(previous);
+            attach_dangling(previous, &mut pieces, next);
+        }
+        attach_statements(&mut reparsed, &mut pieces, suffix_start);
+        let dangling = match suffix.first_mut() {
+            Some(next) => {
+                next.trivia_mut().leading = pieces.take_before(next.span().start());
+                std::mem::take(&mut self.tree.trivia_mut().dangling)
+            }
+            None => pieces.take_dangling(0, None),
+        };
+        statements.extend(reparsed);
+        statements.extend(suffix);
+
+        let end = source.text().len();
+        let start = statements
+            .first()
+            .map(|statement| statement.span().start())
+            .unwrap_or(end);
+        let span = Span::new(source.id(), start, end);
+        self.tree = Node::new(span, NodeType::Block(statements));
+        self.tree.trivia_mut().dangling = dangling;
+        true
+    }
+}
+
+/// Moves the spans of a tree by a number of bytes, for the statements after an edit
+struct Shift(isize);
+
+impl VisitorMut for Shift {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        node.span_mut().shift(self.0);
+        walk_mut(self, node);
+    }
+}
+
+/// An edit whose span isn't inside of the document
+#[derive(Debug, Clone)]
+pub struct InvalidEdit {
+    span: Span,
+}
+
+impl InvalidEdit {
+    pub fn new(span: Span) -> Self {
+        InvalidEdit { span }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+impl Display for InvalidEdit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "edit of bytes {} to {} is outside of the document",
+            self.span.start(),
+            self.span.end()
+        )
+    }
+}
+
+impl std::error::Error for InvalidEdit {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::source;
+    use basil_core::span::LineColumn;
+
+    const SOURCE: &str = "\
+x = 1
+
+def f(a):
+    return a + 1
+
+class C:
+    y = 2
+
+z = f(x)
+w = z
+";
+
+    fn document(text: &str) -> Document {
+        Document::new(source(text))
+    }
+
+    /// Creates an edit between two lines and columns of the document's current source
+    fn edit(
+        document: &Document,
+        start: (usize, usize),
+        end: (usize, usize),
+        text: &str,
+    ) -> TextEdit {
+        let source = document.source();
+        let offset = |(line, column)| {
+            source
+                .offset(LineColumn::new(line, column))
+                .unwrap_or(source.text().len() + 1)
+        };
+        let span = Span::new(source.id(), offset(start), offset(end));
+        TextEdit::new(span, text)
+    }
+
+    /// Checks that the document's tree is exactly what parsing its source from scratch gives
+    fn assert_matches_full_parse(document: &Document) {
+        let (tree, errors) = parse_recovering(document.source());
+        assert_eq!(format!("{:?}", document.tree()), format!("{:?}", tree));
+        assert_eq!(document.errors(), &errors[..]);
+    }
+
+    fn statement_address(document: &Document, index: usize) -> *const NodeType {
+        match document.tree().node_type() {
+            NodeType::Block(statements) => statements[index].node_type(),
+            other => panic!("expected a block, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reuses_statements() {
+        let mut document = document(SOURCE);
+        let last = statement_address(&document, 4);
+        document
+            .apply(&edit(
+                &document,
+                (4, 15),
+                (4, 16),
+                "b\n    b = a * 2\n    return b",
+            ))
+            .unwrap();
+        assert!(document
+            .source()
+            .text()
+            .contains("return a + b\n    b = a * 2"));
+        assert_matches_full_parse(&document);
+        assert_eq!(statement_address(&document, 4), last);
+
+        let first = statement_address(&document, 0);
+        document
+            .apply(&edit(&document, (9, 8), (9, 9), "3\n    v = 4"))
+            .unwrap();
+        assert_matches_full_parse(&document);
+        assert_eq!(statement_address(&document, 0), first);
+        assert_eq!(statement_address(&document, 4), last);
+    }
+
+    #[test]
+    fn moves_lines_between_statements() {
+        let mut document = document(SOURCE);
+        document
+            .apply(&edit(&document, (7, 9), (7, 9), "\n    w = 3"))
+            .unwrap();
+        assert_matches_full_parse(&document);
+        document
+            .apply(&edit(&document, (5, 0), (6, 0), ""))
+            .unwrap();
+        assert_matches_full_parse(&document);
+        document
+            .apply(&edit(&document, (1, 0), (3, 0), ""))
+            .unwrap();
+        assert_matches_full_parse(&document);
+    }
+
+    #[test]
+    fn errors() {
+        let mut document = document(SOURCE);
+        document
+            .apply(&edit(&document, (6, 7), (6, 8), ""))
+            .unwrap();
+        assert_eq!(document.errors().len(), 1);
+        assert_matches_full_parse(&document);
+        document
+            .apply(&edit(&document, (6, 7), (6, 7), ":"))
+            .unwrap();
+        assert!(document.errors().is_empty());
+        assert_matches_full_parse(&document);
+        assert!(document
+            .apply(&edit(&document, (20, 0), (20, 1), ""))
+            .is_err());
+    }
+
+    #[test]
+    fn keeps_trivia() {
+        let source = "\
+# header
+x = 1  # one
+
+def f(a):
+    return a
+    # end of f
+
+# before y
+y = 2
+";
+        let mut document = document(source);
+        document
+            .apply(&edit(&document, (6, 4), (6, 4), "    "))
+            .unwrap();
+        assert_matches_full_parse(&document);
+        document
+            .apply(&edit(&document, (6, 0), (6, 8), ""))
+            .unwrap();
+        assert_matches_full_parse(&document);
+        document
+            .apply(&edit(&document, (9, 5), (9, 5), "  # two\n# end"))
+            .unwrap();
+        assert_matches_full_parse(&document);
+        document
+            .apply(&edit(&document, (2, 5), (2, 6), ""))
+            .unwrap();
+        assert_matches_full_parse(&document);
+    }
+}
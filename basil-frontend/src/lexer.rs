@@ -0,0 +1,1094 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use basil_ast::operators::Operator;
+use basil_ast::trivia::TriviaPiece;
+use basil_core::diagnostic::Diagnostic;
+use basil_core::source_map::{FileId, SourceFile};
+use basil_core::span::Span;
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational};
+use num_traits::{ToPrimitive, Zero};
+
+use crate::token::{FormatStringPart, Keyword, Token, TokenKind};
+
+/// Turns basil source code into a stream of [Token]s.
+///
+/// Spans are byte offsets into the source, with the end of a span being the first byte after the
+/// token. Newlines inside of brackets are ignored, and changes in
+/// indentation at the start of a line produce [Indent](TokenKind::Indent) and
+/// [Dedent](TokenKind::Dedent) tokens. Brackets that are still open when the next line is dedented
+/// past the line they were opened on, or starts with a keyword like `def`, are given up on, so that
+/// one unclosed bracket doesn't swallow the rest of the file.
+///
+/// The lexer keeps going after an error, skipping whatever caused the error, so every error in a
+/// file can be found in one pass.
+///
+/// Comments and blank lines don't produce tokens. They're collected on the side, and can be taken
+/// with [take_trivia](Lexer::take_trivia) once the tokens have been read.
+pub struct Lexer<'s> {
+    file: FileId,
+    source: &'s str,
+    position: usize,
+    offset: usize,
+    indent_stack: Vec<usize>,
+    nesting: usize,
+    at_line_start: bool,
+    line_has_tokens: bool,
+    pending: VecDeque<Token>,
+    finished: bool,
+    trivia: Vec<(usize, TriviaPiece)>,
+}
+
+impl<'s> Lexer<'s> {
+    pub fn new(file: FileId, source: &'s str) -> Self {
+        Lexer {
+            file,
+            source,
+            position: 0,
+            offset: 0,
+            indent_stack: vec![0],
+            nesting: 0,
+            at_line_start: true,
+            line_has_tokens: false,
+            pending: VecDeque::new(),
+            finished: false,
+            trivia: vec![],
+        }
+    }
+
+    /// Treats the source as starting at byte `offset` of the file instead of at its start, for
+    /// lexing a piece of a larger file
+    pub fn starting_at(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Takes the comments and blank lines lexed so far, with where they start, in source order
+    pub fn take_trivia(&mut self) -> Vec<(usize, TriviaPiece)> {
+        std::mem::take(&mut self.trivia)
+    }
+
+    /// Lexes the entire source, stopping at the first error
+    pub fn tokenize(self) -> Result<Vec<Token>, LexError> {
+        self.collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.source[self.position..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn bump_if(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The offset in the file of the next character
+    fn here(&self) -> usize {
+        self.offset + self.position
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(self.file, start, self.here())
+    }
+
+    fn token(&self, kind: TokenKind, start: usize) -> Token {
+        Token::new(kind, self.span_from(start))
+    }
+
+    fn error(&self, kind: LexErrorKind, start: usize) -> LexError {
+        LexError::new(kind, self.span_from(start))
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.bump();
+                }
+                '#' => {
+                    let start = self.here();
+                    let from = self.position;
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                    let comment = self.source[from..self.position].trim_end().to_string();
+                    self.trivia.push((start, TriviaPiece::Comment(comment)));
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Measures the indentation at the start of a line, queueing any indents or dedents
+    fn indentation(&mut self) -> Result<(), LexError> {
+        let start = self.here();
+        let mut width = 0;
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.bump();
+            width += 1;
+        }
+        if matches!(self.peek(), None | Some('\n') | Some('\r') | Some('#')) {
+            // blank lines don't affect indentation
+            if matches!(self.peek(), Some('\n') | Some('\r')) {
+                self.trivia.push((start, TriviaPiece::BlankLine));
+            }
+            return Ok(());
+        }
+
+        let current = *self.indent_stack.last().unwrap();
+        if width > current {
+            self.indent_stack.push(width);
+            self.pending.push_back(self.token(TokenKind::Indent, start));
+        } else if width < current {
+            while *self.indent_stack.last().unwrap() > width {
+                self.indent_stack.pop();
+                let dedent = Token::new(
+                    TokenKind::Dedent,
+                    Span::new(self.file, self.here(), self.here()),
+                );
+                self.pending.push_back(dedent);
+            }
+            if *self.indent_stack.last().unwrap() != width {
+                return Err(self.error(LexErrorKind::InconsistentDedent, start));
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        let here = Span::new(self.file, self.here(), self.here());
+        if self.line_has_tokens {
+            self.line_has_tokens = false;
+            self.pending
+                .push_back(Token::new(TokenKind::Newline, here.clone()));
+        }
+        while self.indent_stack.len() > 1 {
+            self.indent_stack.pop();
+            self.pending
+                .push_back(Token::new(TokenKind::Dedent, here.clone()));
+        }
+        self.pending.push_back(Token::new(TokenKind::Eof, here));
+        self.finished = true;
+    }
+
+    fn next_token(&mut self) -> Option<Result<Token, LexError>> {
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                return Some(Ok(token));
+            }
+            if self.finished {
+                return None;
+            }
+            if self.at_line_start && self.nesting == 0 {
+                self.at_line_start = false;
+                if let Err(e) = self.indentation() {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+
+            self.skip_whitespace_and_comments();
+            let start = self.here();
+            match self.peek() {
+                None => self.finish(),
+                Some('\n') => {
+                    self.bump();
+                    if self.nesting > 0 && self.next_line_ends_brackets() {
+                        self.nesting = 0;
+                    }
+                    if self.nesting == 0 {
+                        self.at_line_start = true;
+                        if self.line_has_tokens {
+                            self.line_has_tokens = false;
+                            return Some(Ok(self.token(TokenKind::Newline, start)));
+                        }
+                    }
+                }
+                Some(c) => {
+                    let token = self.lex_token(c);
+                    self.line_has_tokens = true;
+                    return Some(token);
+                }
+            }
+        }
+    }
+
+    /// Whether the next line that has code on it can't be a continuation of the open brackets,
+    /// because it's dedented past the line they were opened on or starts a statement
+    fn next_line_ends_brackets(&self) -> bool {
+        let indentation = *self.indent_stack.last().unwrap();
+        for line in self.source[self.position..].lines() {
+            let code = line.trim_start_matches([' ', '\t']);
+            if code.trim_end().is_empty() || code.starts_with('#') {
+                continue;
+            }
+            if line.len() - code.len() < indentation {
+                return true;
+            }
+            let word = code
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or_default();
+            return Keyword::from_identifier(word)
+                .is_some_and(|keyword| keyword.starts_statement());
+        }
+        false
+    }
+
+    fn lex_token(&mut self, c: char) -> Result<Token, LexError> {
+        if c.is_alphabetic() || c == '_' {
+            if self.at_string_prefix() {
+                return self.string();
+            }
+            return Ok(self.identifier());
+        }
+        if c.is_ascii_digit() {
+            return self.number();
+        }
+        if c == '"' || c == '\'' {
+            return self.string();
+        }
+
+        let start = self.here();
+        self.bump();
+        let kind = match c {
+            '(' => {
+                self.nesting += 1;
+                TokenKind::LeftParen
+            }
+            ')' => {
+                self.nesting = self.nesting.saturating_sub(1);
+                TokenKind::RightParen
+            }
+            '[' => {
+                self.nesting += 1;
+                TokenKind::LeftBracket
+            }
+            ']' => {
+                self.nesting = self.nesting.saturating_sub(1);
+                TokenKind::RightBracket
+            }
+            '{' => {
+                self.nesting += 1;
+                TokenKind::LeftBrace
+            }
+            '}' => {
+                self.nesting = self.nesting.saturating_sub(1);
+                TokenKind::RightBrace
+            }
+            ',' => TokenKind::Comma,
+            ':' => TokenKind::Colon,
+            '.' => TokenKind::Dot,
+            '@' => TokenKind::At,
+            '+' => TokenKind::Operator(Operator::Add),
+            '*' => TokenKind::Operator(Operator::Mul),
+            '/' => TokenKind::Operator(Operator::Div),
+            '%' => TokenKind::Operator(Operator::Rem),
+            '-' => {
+                if self.bump_if('>') {
+                    TokenKind::Arrow
+                } else {
+                    TokenKind::Operator(Operator::Sub)
+                }
+            }
+            '=' => {
+                if self.bump_if('=') {
+                    TokenKind::Operator(Operator::Eq)
+                } else {
+                    TokenKind::Assign
+                }
+            }
+            '!' if self.bump_if('=') => TokenKind::Operator(Operator::Neq),
+            '<' => {
+                if self.bump_if('=') {
+                    TokenKind::Operator(Operator::Lte)
+                } else {
+                    TokenKind::Operator(Operator::Lt)
+                }
+            }
+            '>' => {
+                if self.bump_if('=') {
+                    TokenKind::Operator(Operator::Gte)
+                } else {
+                    TokenKind::Operator(Operator::Gt)
+                }
+            }
+            other => return Err(self.error(LexErrorKind::UnexpectedCharacter(other), start)),
+        };
+        Ok(self.token(kind, start))
+    }
+
+    fn identifier(&mut self) -> Token {
+        let start = self.here();
+        let begin = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let identifier = &self.source[begin..self.position];
+        let kind = match identifier {
+            "and" => TokenKind::Operator(Operator::And),
+            "or" => TokenKind::Operator(Operator::Or),
+            "not" => TokenKind::Operator(Operator::Not),
+            _ => match Keyword::from_identifier(identifier) {
+                Some(keyword) => TokenKind::Keyword(keyword),
+                None => TokenKind::Identifier(identifier.to_string()),
+            },
+        };
+        self.token(kind, start)
+    }
+
+    /// Reads the digits of a number in the given radix, leaving out the `_`s that can separate
+    /// them. Any letters that run into the number are consumed as invalid digits, except for an
+    /// exponent in a decimal number. The first error is stored in `error`.
+    fn digits(&mut self, radix: u32, error: &mut Option<LexError>) -> String {
+        let mut digits = String::new();
+        let mut after_digit = false;
+        while let Some(c) = self.peek() {
+            let here = self.here();
+            if c.is_digit(radix) {
+                digits.push(c);
+                after_digit = true;
+            } else if c == '_' {
+                let before_digit = self.peek_next().is_some_and(|c| c.is_digit(radix));
+                if !(after_digit && before_digit) {
+                    self.bump();
+                    error.get_or_insert(self.error(LexErrorKind::MisplacedUnderscore, here));
+                    continue;
+                }
+                after_digit = false;
+            } else if radix == 10 && (c == 'e' || c == 'E') {
+                break;
+            } else if c.is_alphanumeric() {
+                self.bump();
+                error.get_or_insert(self.error(LexErrorKind::InvalidDigit(c), here));
+                continue;
+            } else {
+                break;
+            }
+            self.bump();
+        }
+        digits
+    }
+
+    /// Lexes an integer, which can be written in hex, octal or binary with a `0x`, `0o` or `0b`
+    /// prefix, or a decimal float with an optional exponent. Floats are converted exactly, so
+    /// `0.1` is one tenth.
+    fn number(&mut self) -> Result<Token, LexError> {
+        let start = self.here();
+        let mut error = None;
+        let radix = match (self.peek(), self.peek_next()) {
+            (Some('0'), Some('x')) | (Some('0'), Some('X')) => 16,
+            (Some('0'), Some('o')) | (Some('0'), Some('O')) => 8,
+            (Some('0'), Some('b')) | (Some('0'), Some('B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.bump();
+            self.bump();
+            let digits = self.digits(radix, &mut error);
+            if digits.is_empty() {
+                error.get_or_insert(self.error(LexErrorKind::MissingDigits, start));
+            }
+            return match error {
+                Some(error) => Err(error),
+                None => {
+                    let value = BigInt::parse_bytes(digits.as_bytes(), radix).unwrap();
+                    Ok(self.token(TokenKind::Integer(value), start))
+                }
+            };
+        }
+
+        let whole = self.digits(10, &mut error);
+        let is_float =
+            self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit());
+        let mut fraction = String::new();
+        if is_float {
+            self.bump();
+            fraction = self.digits(10, &mut error);
+        }
+        let mut exponent = None;
+        if let Some('e') | Some('E') = self.peek() {
+            let marker = self.here();
+            self.bump();
+            let negative = self.bump_if('-');
+            if !negative {
+                self.bump_if('+');
+            }
+            let digits = self.digits(10, &mut error);
+            if digits.is_empty() {
+                error.get_or_insert(self.error(LexErrorKind::MissingDigits, marker));
+            }
+            exponent = Some((negative, digits));
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        if !is_float && exponent.is_none() {
+            let value = whole.parse::<BigInt>().unwrap();
+            return Ok(self.token(TokenKind::Integer(value), start));
+        }
+
+        let numerator = format!("{}{}", whole, fraction).parse::<BigInt>().unwrap();
+        let denominator = BigInt::from(10).pow(fraction.len() as u32);
+        let mut value = BigRational::new(numerator, denominator);
+        if let Some((negative, digits)) = exponent {
+            // anything past this can't fit in a Rational unless it's zero
+            const MAX_EXPONENT: u32 = 1000;
+            let exponent = digits.parse::<u32>().ok().filter(|&e| e <= MAX_EXPONENT);
+            match exponent {
+                _ if value.is_zero() => {}
+                Some(exponent) => {
+                    let scale = BigRational::from_integer(BigInt::from(10).pow(exponent));
+                    value = if negative {
+                        value / scale
+                    } else {
+                        value * scale
+                    };
+                }
+                None => return Err(self.error(LexErrorKind::FloatOutOfRange, start)),
+            }
+        }
+        match to_rational(value) {
+            Some(value) => Ok(self.token(TokenKind::Float(value), start)),
+            None => Err(self.error(LexErrorKind::FloatOutOfRange, start)),
+        }
+    }
+
+    /// Checks whether the current position is at a string prefix such as `r` or `f` that is
+    /// directly followed by a quote
+    fn at_string_prefix(&self) -> bool {
+        let rest = &self.source[self.position..];
+        let length = rest
+            .find(|c: char| !matches!(c, 'r' | 'R' | 'f' | 'F'))
+            .unwrap_or(rest.len());
+        let valid = matches!(
+            rest[..length].to_ascii_lowercase().as_str(),
+            "r" | "f" | "rf" | "fr"
+        );
+        valid && matches!(rest[length..].chars().next(), Some('"') | Some('\''))
+    }
+
+    /// Lexes a string literal, including any `r` or `f` prefix. Triple quoted strings can span
+    /// multiple lines, raw strings keep their backslashes, and interpolated strings become a
+    /// [FormatString](TokenKind::FormatString).
+    fn string(&mut self) -> Result<Token, LexError> {
+        let start = self.here();
+        let mut raw = false;
+        let mut format = false;
+        while let Some(c) = self.peek().filter(|c| c.is_alphabetic()) {
+            raw |= c.eq_ignore_ascii_case(&'r');
+            format |= c.eq_ignore_ascii_case(&'f');
+            self.bump();
+        }
+        let quote = self.bump().unwrap();
+        let triple = self.peek() == Some(quote) && self.peek_next() == Some(quote);
+        if triple {
+            self.bump();
+            self.bump();
+        }
+
+        let mut parts = vec![];
+        let mut value = String::new();
+        let mut literal_start = self.here();
+        let mut error = None;
+        loop {
+            match self.peek() {
+                None => return Err(self.error(LexErrorKind::UnterminatedString, start)),
+                Some('\n') if !triple => {
+                    return Err(self.error(LexErrorKind::UnterminatedString, start));
+                }
+                Some(c) if c == quote && (!triple || self.at_triple_quote(quote)) => {
+                    let length = if triple { 3 } else { 1 };
+                    for _ in 0..length {
+                        self.bump();
+                    }
+                    break;
+                }
+                Some('\\') if raw => {
+                    self.bump();
+                    value.push('\\');
+                    if let Some(c) = self.bump() {
+                        value.push(c);
+                    }
+                }
+                Some('\\') => {
+                    if let Err(e) = self.escape(&mut value) {
+                        error.get_or_insert(e);
+                    }
+                }
+                Some('{') if format && self.peek_next() == Some('{') => {
+                    self.bump();
+                    self.bump();
+                    value.push('{');
+                }
+                Some('}') if format && self.peek_next() == Some('}') => {
+                    self.bump();
+                    self.bump();
+                    value.push('}');
+                }
+                Some('{') if format => {
+                    if !value.is_empty() {
+                        let span = self.span_from(literal_start);
+                        parts.push(FormatStringPart::Literal(std::mem::take(&mut value), span));
+                    }
+                    match self.interpolation(quote, triple) {
+                        Ok(part) => parts.push(part),
+                        Err(e) => {
+                            error.get_or_insert(e);
+                        }
+                    }
+                    literal_start = self.here();
+                }
+                Some('}') if format => {
+                    let brace = self.here();
+                    self.bump();
+                    error.get_or_insert(self.error(LexErrorKind::UnmatchedBrace, brace));
+                }
+                Some(c) => {
+                    self.bump();
+                    value.push(c);
+                }
+            }
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        if !format {
+            return Ok(self.token(TokenKind::String(value), start));
+        }
+        if !value.is_empty() {
+            let end = self.here();
+            let length = if triple { 3 } else { 1 };
+            let span = Span::new(self.file, literal_start, end - length);
+            parts.push(FormatStringPart::Literal(value, span));
+        }
+        Ok(self.token(TokenKind::FormatString(parts), start))
+    }
+
+    fn at_triple_quote(&self, quote: char) -> bool {
+        self.source[self.position..]
+            .chars()
+            .take(3)
+            .all(|c| c == quote)
+    }
+
+    /// Reads the escape sequence at the current position, adding the character it stands for to
+    /// `value`
+    fn escape(&mut self, value: &mut String) -> Result<(), LexError> {
+        let start = self.here();
+        self.bump();
+        let escaped = match self.peek() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            Some('\n') => {
+                // a backslash at the end of a line continues the string on the next line
+                self.bump();
+                return Ok(());
+            }
+            Some('u') => {
+                self.bump();
+                value.push(self.unicode_escape(start)?);
+                return Ok(());
+            }
+            None => return Ok(()),
+            Some(other) => {
+                self.bump();
+                return Err(self.error(LexErrorKind::InvalidEscape(other), start));
+            }
+        };
+        self.bump();
+        value.push(escaped);
+        Ok(())
+    }
+
+    /// Reads the `{XXXX}` part of a `\u{XXXX}` escape, which can have up to 6 hex digits
+    fn unicode_escape(&mut self, start: usize) -> Result<char, LexError> {
+        if !self.bump_if('{') {
+            return Err(self.error(LexErrorKind::InvalidUnicodeEscape, start));
+        }
+        let begin = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.bump();
+        }
+        let digits = &self.source[begin..self.position];
+        if !self.bump_if('}') || digits.is_empty() || digits.len() > 6 {
+            return Err(self.error(LexErrorKind::InvalidUnicodeEscape, start));
+        }
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.error(LexErrorKind::InvalidUnicodeEscape, start))
+    }
+
+    /// Lexes an embedded `{expression}` in an interpolated string, starting at the `{`. The
+    /// expression can end with `!r` to use `repr` instead of `str`.
+    ///
+    /// The tokens of the expression keep their position in the file, so they point inside of the
+    /// string literal.
+    fn interpolation(&mut self, quote: char, triple: bool) -> Result<FormatStringPart, LexError> {
+        let open = self.here();
+        self.bump();
+        let begin = self.position;
+        let location = self.here();
+        let mut depth = 0usize;
+        let mut repr = false;
+        let end;
+        loop {
+            match self.peek() {
+                None => return Err(self.error(LexErrorKind::UnterminatedString, open)),
+                Some('\n') if !triple => {
+                    return Err(self.error(LexErrorKind::UnterminatedString, open))
+                }
+                Some(c) if c == quote => {
+                    return Err(self.error(LexErrorKind::UnterminatedInterpolation, open))
+                }
+                Some('}') if depth == 0 => {
+                    end = self.position;
+                    self.bump();
+                    break;
+                }
+                Some('!') if depth == 0 && matches!(self.peek_next(), Some('r') | Some('s')) => {
+                    end = self.position;
+                    self.bump();
+                    repr = self.bump() == Some('r');
+                    if !self.bump_if('}') {
+                        return Err(self.error(LexErrorKind::UnterminatedInterpolation, open));
+                    }
+                    break;
+                }
+                Some(c) => {
+                    match c {
+                        '(' | '[' | '{' => depth += 1,
+                        ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    self.bump();
+                }
+            }
+        }
+
+        let mut lexer = Lexer::new(self.file, &self.source[begin..end]).starting_at(location);
+        lexer.nesting = 1;
+        lexer.at_line_start = false;
+        let mut tokens = vec![];
+        for token in lexer {
+            let token = token?;
+            if !matches!(
+                token.kind(),
+                TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent
+            ) {
+                tokens.push(token);
+            }
+        }
+        Ok(FormatStringPart::Expression {
+            tokens,
+            repr,
+            span: self.span_from(open),
+        })
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
+
+/// Converts an arbitrary precision rational into the [Rational] used by
+/// [Primitive::Float](basil_core::primitive::Primitive::Float), if it fits
+fn to_rational(value: BigRational) -> Option<Rational> {
+    let numerator = value.numer().to_isize()?;
+    let denominator = value.denom().to_isize()?;
+    if denominator.is_zero() {
+        return None;
+    }
+    Some(Rational::new(numerator, denominator))
+}
+
+/// Lexes a source file, stopping at the first error
+pub fn tokenize(source: &SourceFile) -> Result<Vec<Token>, LexError> {
+    Lexer::new(source.id(), source.text()).tokenize()
+}
+
+#[derive(Debug, Clone)]
+pub struct LexError {
+    kind: LexErrorKind,
+    span: Span,
+}
+
+impl LexError {
+    pub fn new(kind: LexErrorKind, span: Span) -> Self {
+        LexError { kind, span }
+    }
+
+    pub fn kind(&self) -> &LexErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        self.kind.diagnostic(self.span.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    InvalidEscape(char),
+    /// A `\\u` escape that isn't a valid unicode scalar value written as `\\u{XXXX}`
+    InvalidUnicodeEscape,
+    /// An embedded expression in an interpolated string without a closing `}`
+    UnterminatedInterpolation,
+    /// A single `}` in an interpolated string, which should be written as `}}`
+    UnmatchedBrace,
+    /// A line was dedented to a level that doesn't match any enclosing block
+    InconsistentDedent,
+    /// A float literal whose numerator or denominator doesn't fit in the `isize`s of a [Rational]
+    FloatOutOfRange,
+    /// A digit that isn't valid in the base of the number, such as the `2` in `0b12`
+    InvalidDigit(char),
+    /// A `_` in a number that isn't between two digits
+    MisplacedUnderscore,
+    /// A base prefix or exponent without any digits after it
+    MissingDigits,
+}
+
+impl LexErrorKind {
+    pub(crate) fn diagnostic(&self, span: Span) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), span);
+        match self {
+            LexErrorKind::InvalidEscape(_) => diagnostic.with_help(
+                "valid escapes are `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, `\\'`, `\\\"` and `\\u{XXXX}`",
+            ),
+            LexErrorKind::UnmatchedBrace => {
+                diagnostic.with_help("write `}}` for a `}` in an interpolated string")
+            }
+            LexErrorKind::FloatOutOfRange => {
+                diagnostic.with_note("floats are stored as exact fractions of two `isize`s")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl Display for LexErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::InvalidEscape(c) => write!(f, "invalid escape sequence `\\{}`", c),
+            LexErrorKind::InvalidUnicodeEscape => write!(f, "invalid unicode escape sequence"),
+            LexErrorKind::UnterminatedInterpolation => {
+                write!(f, "expected `}}` after the interpolated expression")
+            }
+            LexErrorKind::UnmatchedBrace => write!(f, "single `}}` in an interpolated string"),
+            LexErrorKind::InconsistentDedent => {
+                write!(f, "unindent does not match any outer indentation level")
+            }
+            LexErrorKind::FloatOutOfRange => write!(
+                f,
+                "float literal can't be represented exactly, its numerator or denominator is too large"
+            ),
+            LexErrorKind::InvalidDigit(c) => write!(f, "invalid digit {:?} in number", c),
+            LexErrorKind::MisplacedUnderscore => {
+                write!(f, "`_` can only be used between the digits of a number")
+            }
+            LexErrorKind::MissingDigits => write!(f, "expected digits in number"),
+        }
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::source;
+    use basil_core::span::LineColumn;
+
+    fn lex_errors(text: &str) -> Vec<LexError> {
+        let file = source(text);
+        Lexer::new(file.id(), file.text())
+            .filter_map(Result::err)
+            .collect()
+    }
+
+    fn kinds(text: &str) -> Vec<TokenKind> {
+        tokenize(&source(text))
+            .unwrap()
+            .into_iter()
+            .map(Token::into_kind)
+            .collect()
+    }
+
+    fn id(name: &str) -> TokenKind {
+        TokenKind::Identifier(name.to_string())
+    }
+
+    #[test]
+    fn simple_statement() {
+        assert_eq!(
+            kinds("x = y + 3\n"),
+            vec![
+                id("x"),
+                TokenKind::Assign,
+                id("y"),
+                TokenKind::Operator(Operator::Add),
+                TokenKind::Integer(BigInt::from(3)),
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn indentation() {
+        let source = "while x:\n    if y:\n        pass\n\n    # comment\n    z\nw";
+        assert_eq!(
+            kinds(source),
+            vec![
+                TokenKind::Keyword(Keyword::While),
+                id("x"),
+                TokenKind::Colon,
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Keyword(Keyword::If),
+                id("y"),
+                TokenKind::Colon,
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Keyword(Keyword::Pass),
+                TokenKind::Newline,
+                TokenKind::Dedent,
+                id("z"),
+                TokenKind::Newline,
+                TokenKind::Dedent,
+                id("w"),
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn newlines_ignored_in_brackets() {
+        assert_eq!(
+            kinds("f(a,\n  b)\n"),
+            vec![
+                id("f"),
+                TokenKind::LeftParen,
+                id("a"),
+                TokenKind::Comma,
+                id("b"),
+                TokenKind::RightParen,
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+        // brackets that can't be closed end at a line that starts a statement
+        assert_eq!(
+            kinds("f(a,\n\n  # comment\npass\n"),
+            vec![
+                id("f"),
+                TokenKind::LeftParen,
+                id("a"),
+                TokenKind::Comma,
+                TokenKind::Newline,
+                TokenKind::Keyword(Keyword::Pass),
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_and_literals() {
+        assert_eq!(
+            kinds("a <= 1.25 and not 'hi\\n' != b -> c"),
+            vec![
+                id("a"),
+                TokenKind::Operator(Operator::Lte),
+                TokenKind::Float(Rational::new(5, 4)),
+                TokenKind::Operator(Operator::And),
+                TokenKind::Operator(Operator::Not),
+                TokenKind::String("hi\n".to_string()),
+                TokenKind::Operator(Operator::Neq),
+                id("b"),
+                TokenKind::Arrow,
+                id("c"),
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let file = source("if x:\n    \"é\" + yy\n");
+        let tokens = tokenize(&file).unwrap();
+        let find = |kind: TokenKind| {
+            let token = tokens.iter().find(|t| t.kind() == &kind).unwrap();
+            (
+                file.location(token.span().start()),
+                file.location(token.span().end()),
+            )
+        };
+        assert_eq!(
+            find(id("x")),
+            (LineColumn::new(1, 3), LineColumn::new(1, 4))
+        );
+        assert_eq!(
+            find(TokenKind::String("é".to_string())),
+            (LineColumn::new(2, 4), LineColumn::new(2, 7))
+        );
+        assert_eq!(
+            find(id("yy")),
+            (LineColumn::new(2, 10), LineColumn::new(2, 12))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let errors: Vec<_> = lex_errors("a $ b\nif x:\n    y\n  z\n\"open")
+            .into_iter()
+            .map(|e| e.kind().clone())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                LexErrorKind::UnexpectedCharacter('$'),
+                LexErrorKind::InconsistentDedent,
+                LexErrorKind::UnterminatedString
+            ]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let string = |value: &str| TokenKind::String(value.to_string());
+        assert_eq!(
+            kinds(
+                r#"'a\tb' "\u{1F600}\"" r'C:\dir\n' """one
+two""" "#
+            ),
+            vec![
+                string("a\tb"),
+                string("\u{1F600}\""),
+                string("C:\\dir\\n"),
+                string("one\ntwo"),
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+        let errors: Vec<_> = lex_errors(r#""\u{110000}" f"{x" f"}""#)
+            .into_iter()
+            .map(|e| e.kind().clone())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                LexErrorKind::InvalidUnicodeEscape,
+                LexErrorKind::UnterminatedInterpolation,
+                LexErrorKind::UnmatchedBrace
+            ]
+        );
+    }
+
+    #[test]
+    fn format_strings() {
+        let tokens = tokenize(&source("f'a{{ {user.name!r}}}'")).unwrap();
+        let parts = match tokens[0].kind() {
+            TokenKind::FormatString(parts) => parts,
+            other => panic!("expected a format string, found {:?}", other),
+        };
+        assert_eq!(parts.len(), 3);
+        assert!(matches!(&parts[0], FormatStringPart::Literal(s, _) if s == "a{ "));
+        assert!(matches!(&parts[2], FormatStringPart::Literal(s, _) if s == "}"));
+        match &parts[1] {
+            FormatStringPart::Expression { tokens, repr, span } => {
+                assert!(repr);
+                assert_eq!(span.start(), 6);
+                let kinds: Vec<_> = tokens.iter().map(|t| t.kind().clone()).collect();
+                assert_eq!(
+                    kinds,
+                    vec![id("user"), TokenKind::Dot, id("name"), TokenKind::Eof]
+                );
+                assert_eq!(tokens[2].span().start(), 12);
+            }
+            other => panic!("expected an expression, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let int = |i: i64| TokenKind::Integer(BigInt::from(i));
+        assert_eq!(
+            kinds("0xFF 0o17 0b1010 1_000_000 0.1 2.5e3 1e-2 0E99999999999 123456789012345678901234567890"),
+            vec![
+                int(255),
+                int(15),
+                int(10),
+                int(1_000_000),
+                TokenKind::Float(Rational::new(1, 10)),
+                TokenKind::Float(Rational::from_integer(2500)),
+                TokenKind::Float(Rational::new(1, 100)),
+                TokenKind::Float(Rational::from_integer(0)),
+                TokenKind::Integer("123456789012345678901234567890".parse().unwrap()),
+                TokenKind::Newline,
+                TokenKind::Eof
+            ]
+        );
+        let errors: Vec<_> = lex_errors("0b12 1__0 1_ 0x 1e 0.00000000000000000001 1e100")
+            .into_iter()
+            .map(|e| (e.kind().clone(), e.span().start()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (LexErrorKind::InvalidDigit('2'), 3),
+                (LexErrorKind::MisplacedUnderscore, 6),
+                (LexErrorKind::MisplacedUnderscore, 11),
+                (LexErrorKind::MissingDigits, 13),
+                (LexErrorKind::MissingDigits, 17),
+                (LexErrorKind::FloatOutOfRange, 19),
+                (LexErrorKind::FloatOutOfRange, 42),
+            ]
+        );
+    }
+}
@@ -1 +1,11 @@
-
+pub mod check;
+pub mod format;
+pub mod incremental;
+pub mod lexer;
+pub mod lower;
+pub mod parser;
+pub mod resolve;
+pub mod token;
+#[cfg(test)]
+mod test_util;
+mod trivia;
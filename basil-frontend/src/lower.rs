@@ -0,0 +1,908 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use basil_ast::node::{Node, NodeType};
+use basil_ast::operators::Operator;
+use basil_core::code_block::CodeBlock;
+use basil_core::diagnostic::Diagnostic;
+use basil_core::expression::{
+    Atom, BinaryOperator, ComprehensionClause, ComprehensionKind, Expression, ExpressionTail,
+    UnaryOperator,
+};
+use basil_core::function::Function;
+use basil_core::object::Object;
+use basil_core::pattern::{MatchCase, Pattern};
+use basil_core::primitive::Primitive;
+use basil_core::span::{Span, WithSpan};
+use basil_core::statements::{ExceptHandler, Statement};
+use basil_core::variable::IntoVariable;
+
+use crate::resolve;
+
+/// Translates a parsed [Node] tree into the [CodeBlock] that the interpreter runs.
+///
+/// Every statement keeps the span of the node it came from. Lowering doesn't stop at the first
+/// problem, so all constructs that can't be lowered are reported at once.
+#[derive(Default)]
+pub struct Lowerer {
+    errors: Vec<LoweringError>,
+}
+
+impl Lowerer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Lowers a [Block](NodeType::Block) node, such as the one produced by
+    /// [parse](crate::parser::parse), and [resolves](crate::resolve) the captures of its functions
+    pub fn lower(mut self, node: &Node) -> Result<WithSpan<CodeBlock>, Vec<LoweringError>> {
+        let mut block = self.block(node);
+        if self.errors.is_empty() {
+            resolve::resolve(block.get_object_mut());
+            Ok(block)
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn error(&mut self, kind: LoweringErrorKind, span: &Span) {
+        self.errors.push(LoweringError::new(kind, span.clone()));
+    }
+
+    fn unsupported(&mut self, construct: &'static str, span: &Span) {
+        self.error(LoweringErrorKind::Unsupported(construct), span)
+    }
+
+    fn block(&mut self, node: &Node) -> WithSpan<CodeBlock> {
+        let mut statements = vec![];
+        match node.node_type() {
+            NodeType::Block(nodes) => {
+                for node in nodes {
+                    if let Some(statement) = self.statement(node) {
+                        statements.push(WithSpan::new(statement, node.span().clone()));
+                    }
+                }
+            }
+            _ => self.error(LoweringErrorKind::ExpectedBlock, node.span()),
+        }
+        WithSpan::new(CodeBlock::new(statements), node.span().clone())
+    }
+
+    fn statement(&mut self, node: &Node) -> Option<Statement> {
+        let statement = match node.node_type() {
+            NodeType::Assignment { lhs, rhs, .. } => {
+                Statement::Assignment(self.expression(lhs), self.expression(rhs))
+            }
+            NodeType::Function {
+                name,
+                parameters,
+                block,
+                ..
+            } => {
+                let span = name.span().clone();
+                let name = self.identifier(name);
+                let function =
+                    self.function(node, name.clone(), parameters, |this| this.block(block));
+                Statement::Assignment(
+                    Atom::identifier(name, span).into(),
+                    Atom::Function(Box::new(function)).into(),
+                )
+            }
+            NodeType::If {
+                condition,
+                block,
+                r#else,
+            } => {
+                let condition = self.expression(condition);
+                let block = self.block(block).get_object().clone();
+                let mut elifs = vec![];
+                let mut r#else = r#else.as_ref();
+                // `elif`s are parsed as ifs directly inside of the else
+                while let Some(NodeType::If {
+                    condition,
+                    block,
+                    r#else: next,
+                }) = r#else.map(Node::node_type)
+                {
+                    elifs.push((
+                        self.expression(condition),
+                        self.block(block).get_object().clone(),
+                    ));
+                    r#else = next.as_ref();
+                }
+                let r#else = r#else.map(|block| self.block(block).get_object().clone());
+                Statement::If {
+                    condition,
+                    block,
+                    elifs,
+                    r#else,
+                }
+            }
+            NodeType::While {
+                condition,
+                block,
+                r#else,
+            } => Statement::While {
+                condition: self.expression(condition),
+                block: self.block(block).get_object().clone(),
+                r#else: r#else
+                    .as_ref()
+                    .map(|block| self.block(block).get_object().clone()),
+            },
+            NodeType::Return(value) => Statement::Return(self.expression(value)),
+            NodeType::Raise(value) => Statement::Raise(self.expression(value)),
+            NodeType::Class { name, parent, defs } => {
+                let name = self.identifier(name);
+                let parents = parent.iter().map(|p| self.expression(p)).collect();
+                let mut decorators = vec![];
+                let definitions = self.class_definitions(defs, &mut decorators);
+                Statement::Class {
+                    name,
+                    parents,
+                    definitions,
+                    decorators,
+                }
+            }
+            NodeType::Decorated {
+                decorators,
+                definition,
+            } => {
+                let decorators = decorators.iter().map(|d| self.expression(d)).collect();
+                let name = match definition.node_type() {
+                    NodeType::Function { name, .. } | NodeType::Class { name, .. } => {
+                        self.identifier(name)
+                    }
+                    _ => {
+                        self.error(LoweringErrorKind::ExpectedDefinition, definition.span());
+                        return None;
+                    }
+                };
+                Statement::Decorated {
+                    name,
+                    decorators,
+                    definition: Box::new(self.statement(definition)?),
+                }
+            }
+            NodeType::For {
+                identifier,
+                iterator,
+                block,
+                r#else,
+            } => Statement::For {
+                variable: self.identifier(identifier),
+                iterable: self.expression(iterator),
+                block: self.block(block).get_object().clone(),
+                r#else: r#else
+                    .as_ref()
+                    .map(|block| self.block(block).get_object().clone()),
+            },
+            NodeType::Match { subject, cases } => Statement::Match {
+                subject: self.expression(subject),
+                cases: cases
+                    .iter()
+                    .filter_map(|case| self.match_case(case))
+                    .collect(),
+            },
+            NodeType::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => Statement::Try {
+                block: self.block(block).get_object().clone(),
+                handlers: handlers
+                    .iter()
+                    .filter_map(|handler| self.except_handler(handler))
+                    .collect(),
+                r#else: r#else
+                    .as_ref()
+                    .map(|block| self.block(block).get_object().clone()),
+                finally: finally
+                    .as_ref()
+                    .map(|block| self.block(block).get_object().clone()),
+            },
+            NodeType::Pass => return None,
+            NodeType::Break => Statement::Break,
+            NodeType::Continue => Statement::Continue,
+            NodeType::Yield(_) => {
+                self.unsupported("yield", node.span());
+                return None;
+            }
+            NodeType::Import(_) => {
+                self.unsupported("imports", node.span());
+                return None;
+            }
+            NodeType::Error => {
+                self.error(LoweringErrorKind::SyntaxError, node.span());
+                return None;
+            }
+            _ => Statement::Expression(self.expression(node)),
+        };
+        Some(statement)
+    }
+
+    fn identifier(&mut self, node: &Node) -> String {
+        match node.node_type() {
+            NodeType::Identifier(id) => id.clone(),
+            _ => {
+                self.error(LoweringErrorKind::ExpectedIdentifier, node.span());
+                String::new()
+            }
+        }
+    }
+
+    /// Lowers a `def` or a lambda. The body is lowered by `body` after the parameters, so that
+    /// errors are reported in the order they appear in.
+    fn function<B: FnOnce(&mut Self) -> WithSpan<CodeBlock>>(
+        &mut self,
+        node: &Node,
+        name: String,
+        parameters: &[Node],
+        body: B,
+    ) -> WithSpan<Function> {
+        let mut positional = vec![];
+        let mut keyword = vec![];
+        for parameter in parameters {
+            if let NodeType::Parameter { name, default, .. } = parameter.node_type() {
+                let name = self.identifier(name);
+                match default {
+                    None => positional.push(name),
+                    Some(default) => match constant(default) {
+                        Some(value) => keyword.push((name, Object::new(value))),
+                        None => self.error(LoweringErrorKind::NonConstantDefault, default.span()),
+                    },
+                }
+            }
+        }
+        let block = body(self);
+        let function = Function::new(name, HashMap::new(), positional, keyword, block);
+        WithSpan::new(function, node.span().clone())
+    }
+
+    /// Lowers the body of a class, collecting the decorators of its decorated methods
+    fn class_definitions(
+        &mut self,
+        defs: &[Node],
+        decorators: &mut Vec<(String, Vec<Expression>)>,
+    ) -> Vec<(String, Object)> {
+        let mut definitions = vec![];
+        for def in defs {
+            // a decorated method is lowered like any other, and decorated when the class is
+            let def = match def.node_type() {
+                NodeType::Decorated {
+                    decorators: expressions,
+                    definition,
+                } => match definition.node_type() {
+                    NodeType::Function { name, .. } => {
+                        let expressions = expressions.iter().map(|d| self.expression(d)).collect();
+                        decorators.push((self.identifier(name), expressions));
+                        definition
+                    }
+                    _ => def,
+                },
+                _ => def,
+            };
+            match def.node_type() {
+                NodeType::Function {
+                    name,
+                    parameters,
+                    block,
+                    ..
+                } => {
+                    let name = self.identifier(name);
+                    let function =
+                        self.function(def, name.clone(), parameters, |this| this.block(block));
+                    definitions.push((name, Object::new(Primitive::Function(function))));
+                }
+                NodeType::Assignment { lhs, rhs, .. } => match constant(rhs) {
+                    Some(value) => definitions.push((self.identifier(lhs), Object::new(value))),
+                    None => self.error(LoweringErrorKind::InvalidClassBody, def.span()),
+                },
+                NodeType::Pass => {}
+                NodeType::Error => self.error(LoweringErrorKind::SyntaxError, def.span()),
+                _ => self.error(LoweringErrorKind::InvalidClassBody, def.span()),
+            }
+        }
+        definitions
+    }
+
+    fn expression(&mut self, node: &Node) -> Expression {
+        if let Some(value) = constant(node) {
+            return Atom::Variable(value.into_variable()).into();
+        }
+        match node.node_type() {
+            NodeType::Identifier(id) => Atom::identifier(id.clone(), node.span().clone()).into(),
+            NodeType::QualifiedIdentifier { parent, child } => {
+                let member = self.identifier(child);
+                self.expression(parent)
+                    .then(ExpressionTail::GetMember(member))
+            }
+            NodeType::FunctionCall { name, parameters } => {
+                let function = self.expression(name);
+                let mut positional = vec![];
+                let mut named = HashMap::new();
+                for parameter in parameters {
+                    match parameter.node_type() {
+                        NodeType::KeywordArgument { name, value } => {
+                            let name = self.identifier(name);
+                            named.insert(name, self.expression(value));
+                        }
+                        _ => positional.push(self.expression(parameter)),
+                    }
+                }
+                function.then(ExpressionTail::CallMethod { positional, named })
+            }
+            NodeType::Index { value, index } => {
+                let index = self.expression(index);
+                self.expression(value).then(ExpressionTail::Index(index))
+            }
+            NodeType::FormatString(parts) => {
+                let mut parts = parts.iter().map(|part| self.format_string_part(part));
+                let first = parts
+                    .next()
+                    .unwrap_or_else(|| Atom::Variable(Primitive::from("").into_variable()).into());
+                parts.fold(first, |string, part| {
+                    Expression::binary(string, BinaryOperator::Add, part)
+                })
+            }
+            NodeType::List(elements) | NodeType::Tuple(elements) => {
+                Atom::List(self.expressions(elements)).into()
+            }
+            NodeType::Set(elements) => Atom::Set(self.expressions(elements)).into(),
+            NodeType::Dictionary(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|entry| self.dictionary_entry(entry))
+                    .collect();
+                Atom::Dictionary(entries).into()
+            }
+            NodeType::ListComprehension { element, clauses } => {
+                let kind = ComprehensionKind::List(self.expression(element));
+                self.comprehension(kind, clauses)
+            }
+            NodeType::SetComprehension { element, clauses } => {
+                let kind = ComprehensionKind::Set(self.expression(element));
+                self.comprehension(kind, clauses)
+            }
+            NodeType::DictionaryComprehension { element, clauses } => {
+                let (key, value) = self.dictionary_entry(element);
+                self.comprehension(ComprehensionKind::Dictionary(key, value), clauses)
+            }
+            NodeType::Lambda { parameters, body } => {
+                let name = "<lambda>".to_string();
+                let function = self.function(node, name, parameters, |this| {
+                    let span = body.span().clone();
+                    let body = Statement::Return(this.expression(body));
+                    let block = CodeBlock::new(vec![WithSpan::new(body, span.clone())]);
+                    WithSpan::new(block, span)
+                });
+                Atom::Function(Box::new(function)).into()
+            }
+            NodeType::BinaryExpression { lhs, rhs, op } => {
+                let lhs = self.expression(lhs);
+                let rhs = self.expression(rhs);
+                match binary_operator(op) {
+                    Some(op) => Expression::binary(lhs, op, rhs),
+                    None => {
+                        self.error(LoweringErrorKind::InvalidOperator, op.span());
+                        lhs
+                    }
+                }
+            }
+            NodeType::UnaryExpression { val, op } => {
+                let value = self.expression(val);
+                match unary_operator(op) {
+                    Some(op) => Expression::unary(op, value),
+                    None => {
+                        self.error(LoweringErrorKind::InvalidOperator, op.span());
+                        value
+                    }
+                }
+            }
+            NodeType::Error => {
+                self.error(LoweringErrorKind::SyntaxError, node.span());
+                Atom::Variable(Primitive::None.into_variable()).into()
+            }
+            _ => {
+                self.error(LoweringErrorKind::ExpectedExpression, node.span());
+                Atom::Variable(Primitive::None.into_variable()).into()
+            }
+        }
+    }
+
+    fn match_case(&mut self, node: &Node) -> Option<MatchCase> {
+        match node.node_type() {
+            NodeType::MatchCase {
+                pattern,
+                guard,
+                block,
+            } => {
+                let pattern = self.pattern(pattern);
+                let guard = guard.as_ref().map(|guard| self.expression(guard));
+                let block = self.block(block).get_object().clone();
+                Some(MatchCase::new(pattern, guard, block))
+            }
+            _ => {
+                self.error(LoweringErrorKind::SyntaxError, node.span());
+                None
+            }
+        }
+    }
+
+    fn except_handler(&mut self, node: &Node) -> Option<ExceptHandler> {
+        match node.node_type() {
+            NodeType::ExceptHandler { class, name, block } => {
+                let class = class.as_ref().map(|class| self.expression(class));
+                let name = name.as_ref().map(|name| self.identifier(name));
+                let block = self.block(block).get_object().clone();
+                Some(ExceptHandler::new(class, name, block))
+            }
+            _ => {
+                self.error(LoweringErrorKind::SyntaxError, node.span());
+                None
+            }
+        }
+    }
+
+    /// Lowers a pattern, which the parser read as an expression
+    fn pattern(&mut self, node: &Node) -> Pattern {
+        if constant(node).is_some() {
+            return Pattern::Value(self.expression(node));
+        }
+        match node.node_type() {
+            NodeType::Identifier(id) if id == "_" => Pattern::Wildcard,
+            NodeType::Identifier(id) => Pattern::Capture(id.clone()),
+            NodeType::QualifiedIdentifier { .. } => Pattern::Value(self.expression(node)),
+            NodeType::List(elements) | NodeType::Tuple(elements) => {
+                Pattern::List(elements.iter().map(|e| self.pattern(e)).collect())
+            }
+            NodeType::Dictionary(entries) => {
+                let mut lowered = vec![];
+                for entry in entries {
+                    if let NodeType::DictionaryEntry { key, value } = entry.node_type() {
+                        if constant(key).is_none()
+                            && !matches!(key.node_type(), NodeType::QualifiedIdentifier { .. })
+                        {
+                            self.error(LoweringErrorKind::InvalidPattern, key.span());
+                        }
+                        lowered.push((self.expression(key), self.pattern(value)));
+                    } else {
+                        self.error(LoweringErrorKind::ExpectedDictionaryEntry, entry.span());
+                    }
+                }
+                Pattern::Dictionary(lowered)
+            }
+            NodeType::FunctionCall { name, parameters }
+                if matches!(
+                    name.node_type(),
+                    NodeType::Identifier(_) | NodeType::QualifiedIdentifier { .. }
+                ) =>
+            {
+                let mut members = vec![];
+                for parameter in parameters {
+                    match parameter.node_type() {
+                        NodeType::KeywordArgument { name, value } => {
+                            members.push((self.identifier(name), self.pattern(value)))
+                        }
+                        _ => self.error(LoweringErrorKind::InvalidPattern, parameter.span()),
+                    }
+                }
+                Pattern::Class {
+                    class: self.expression(name),
+                    members,
+                }
+            }
+            NodeType::Error => {
+                self.error(LoweringErrorKind::SyntaxError, node.span());
+                Pattern::Wildcard
+            }
+            _ => {
+                self.error(LoweringErrorKind::InvalidPattern, node.span());
+                Pattern::Wildcard
+            }
+        }
+    }
+
+    /// Lowers a piece of an interpolated string into an expression that evaluates to a string
+    fn format_string_part(&mut self, node: &Node) -> Expression {
+        match node.node_type() {
+            NodeType::Interpolation { value, repr: true } => {
+                Atom::Repr(Box::new(self.expression(value))).into()
+            }
+            NodeType::Interpolation { value, repr: false } => {
+                Atom::Str(Box::new(self.expression(value))).into()
+            }
+            _ => self.expression(node),
+        }
+    }
+
+    fn expressions(&mut self, nodes: &[Node]) -> Vec<Expression> {
+        nodes.iter().map(|node| self.expression(node)).collect()
+    }
+
+    fn dictionary_entry(&mut self, node: &Node) -> (Expression, Expression) {
+        match node.node_type() {
+            NodeType::DictionaryEntry { key, value } => {
+                (self.expression(key), self.expression(value))
+            }
+            _ => {
+                self.error(LoweringErrorKind::ExpectedDictionaryEntry, node.span());
+                let none = || Atom::Variable(Primitive::None.into_variable()).into();
+                (none(), none())
+            }
+        }
+    }
+
+    fn comprehension(&mut self, kind: ComprehensionKind, clauses: &[Node]) -> Expression {
+        let mut lowered = vec![];
+        for clause in clauses {
+            match clause.node_type() {
+                NodeType::ComprehensionFor {
+                    identifier,
+                    iterator,
+                } => lowered.push(ComprehensionClause::For {
+                    variable: self.identifier(identifier),
+                    iterable: self.expression(iterator),
+                }),
+                NodeType::ComprehensionIf(condition) => {
+                    lowered.push(ComprehensionClause::If(self.expression(condition)))
+                }
+                _ => self.error(LoweringErrorKind::ExpectedExpression, clause.span()),
+            }
+        }
+        Atom::Comprehension {
+            kind: Box::new(kind),
+            clauses: lowered,
+        }
+        .into()
+    }
+}
+
+/// Gets the value of a node if it's a literal
+fn constant(node: &Node) -> Option<Primitive> {
+    let value = match node.node_type() {
+        NodeType::Integer(i) => Primitive::Integer(i.clone()),
+        NodeType::Float(f) => Primitive::Float(*f),
+        NodeType::String(s) => Primitive::String(s.clone()),
+        NodeType::Boolean(b) => Primitive::Boolean(*b),
+        NodeType::None => Primitive::None,
+        NodeType::UnaryExpression { val, op } => match (op.node_type(), val.node_type()) {
+            (NodeType::Operator(Operator::Sub), NodeType::Integer(i)) => Primitive::Integer(-i),
+            (NodeType::Operator(Operator::Sub), NodeType::Float(f)) => Primitive::Float(-f),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn binary_operator(node: &Node) -> Option<BinaryOperator> {
+    let op = match node.node_type() {
+        NodeType::Operator(op) => op,
+        _ => return None,
+    };
+    let op = match op {
+        Operator::Eq => BinaryOperator::Eq,
+        Operator::Neq => BinaryOperator::Neq,
+        Operator::Lt => BinaryOperator::Lt,
+        Operator::Gt => BinaryOperator::Gt,
+        Operator::Lte => BinaryOperator::Lte,
+        Operator::Gte => BinaryOperator::Gte,
+        Operator::Add => BinaryOperator::Add,
+        Operator::Sub => BinaryOperator::Sub,
+        Operator::Div => BinaryOperator::Div,
+        Operator::Mul => BinaryOperator::Mul,
+        Operator::Rem => BinaryOperator::Rem,
+        Operator::And => BinaryOperator::And,
+        Operator::Or => BinaryOperator::Or,
+        Operator::Not => return None,
+    };
+    Some(op)
+}
+
+fn unary_operator(node: &Node) -> Option<UnaryOperator> {
+    match node.node_type() {
+        NodeType::Operator(Operator::Sub) => Some(UnaryOperator::Neg),
+        NodeType::Operator(Operator::Not) => Some(UnaryOperator::Not),
+        _ => None,
+    }
+}
+
+/// Lowers a parsed [Node] tree, reporting every construct that can't be lowered
+pub fn lower(node: &Node) -> Result<WithSpan<CodeBlock>, Vec<LoweringError>> {
+    Lowerer::new().lower(node)
+}
+
+#[derive(Debug, Clone)]
+pub struct LoweringError {
+    kind: LoweringErrorKind,
+    span: Span,
+}
+
+impl LoweringError {
+    pub fn new(kind: LoweringErrorKind, span: Span) -> Self {
+        LoweringError { kind, span }
+    }
+
+    pub fn kind(&self) -> &LoweringErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.kind.to_string(), self.span.clone());
+        match &self.kind {
+            LoweringErrorKind::Unsupported(_) => {
+                diagnostic.with_note("this parses, but the interpreter can't run it yet")
+            }
+            LoweringErrorKind::NonConstantDefault => diagnostic
+                .with_help("use `None` as the default and compute the value in the function"),
+            LoweringErrorKind::InvalidPattern => diagnostic.with_help(
+                "patterns are literals, names, `a.b` values, lists, dictionaries with literal keys \
+                 and class patterns with keyword arguments like `Point(x=0)`",
+            ),
+            _ => diagnostic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoweringErrorKind {
+    /// A construct that parses, but that the interpreter can't run yet
+    Unsupported(&'static str),
+    /// An [Error](NodeType::Error) node left behind by the parser
+    SyntaxError,
+    /// A default parameter value that isn't a literal
+    NonConstantDefault,
+    /// Something other than a function or a literal assignment inside of a class
+    InvalidClassBody,
+    /// An operator used in a position it can't be used in, such as a unary `*`
+    InvalidOperator,
+    /// An expression in a `case` that can't be used as a pattern, such as `a + b`
+    InvalidPattern,
+    /// A decorator in front of something other than a `def` or a class
+    ExpectedDefinition,
+    ExpectedBlock,
+    ExpectedIdentifier,
+    ExpectedExpression,
+    ExpectedDictionaryEntry,
+}
+
+impl Display for LoweringErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoweringErrorKind::Unsupported(construct) => {
+                write!(f, "{} are not supported yet", construct)
+            }
+            LoweringErrorKind::SyntaxError => write!(f, "can't lower code with a syntax error"),
+            LoweringErrorKind::NonConstantDefault => {
+                write!(f, "default parameter values must be literals")
+            }
+            LoweringErrorKind::InvalidClassBody => write!(
+                f,
+                "class bodies can only contain functions and assignments of literals"
+            ),
+            LoweringErrorKind::InvalidOperator => write!(f, "invalid operator"),
+            LoweringErrorKind::InvalidPattern => write!(f, "invalid pattern"),
+            LoweringErrorKind::ExpectedDefinition => {
+                write!(f, "expected a function or class definition")
+            }
+            LoweringErrorKind::ExpectedBlock => write!(f, "expected a block"),
+            LoweringErrorKind::ExpectedIdentifier => write!(f, "expected an identifier"),
+            LoweringErrorKind::ExpectedExpression => write!(f, "expected an expression"),
+            LoweringErrorKind::ExpectedDictionaryEntry => {
+                write!(f, "expected a `key: value` entry")
+            }
+        }
+    }
+}
+
+impl Display for LoweringError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for LoweringError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, parse_recovering};
+    use crate::test_util::source;
+    use basil_core::span::LineColumn;
+
+    fn lower_str(source: &str) -> Result<WithSpan<CodeBlock>, Vec<LoweringError>> {
+        lower(&parse(&self::source(source)).unwrap())
+    }
+
+    #[test]
+    fn keeps_spans() {
+        let file = source("x = 1\n\nif x:\n    f(x, y=2)\nelif y:\n    pass\nelse:\n    a.b.c\n");
+        let block = lower(&parse(&file).unwrap()).unwrap();
+        let location = |offset| file.location(offset);
+        let statements = block.get_object().statements();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(
+            location(statements[1].get_span().start()),
+            LineColumn::new(3, 0)
+        );
+        match statements[1].get_object() {
+            Statement::If {
+                block,
+                elifs,
+                r#else,
+                ..
+            } => {
+                let call = &block.statements()[0];
+                assert_eq!(location(call.get_span().start()), LineColumn::new(4, 4));
+                assert_eq!(location(call.get_span().end()), LineColumn::new(4, 13));
+                assert_eq!(elifs.len(), 1);
+                assert!(elifs[0].1.statements().is_empty());
+                let member = &r#else.as_ref().unwrap().statements()[0];
+                match member.get_object() {
+                    Statement::Expression(e) => {
+                        assert!(matches!(e.tail(), Some(ExpressionTail::GetMember(m)) if m == "c"));
+                        assert!(matches!(e.head(), Atom::Expression(_)));
+                    }
+                    other => panic!("expected an expression, found {:?}", other),
+                }
+            }
+            other => panic!("expected an if statement, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn functions_and_classes() {
+        let block = lower_str(
+            "def f(a, b=-1):\n    return a\nclass C(B):\n    x = 'x'\n    def g():\n        pass\n",
+        )
+        .unwrap();
+        let statements = block.get_object().statements();
+        match statements[0].get_object() {
+            Statement::Assignment(_, value) => match value.head() {
+                Atom::Function(f) => {
+                    assert_eq!(f.get_object().name(), "f");
+                    assert_eq!(f.get_object().positional_arguments(), &["a".to_string()]);
+                    assert_eq!(f.get_object().keyword_arguments()[0].0, "b");
+                    assert_eq!(f.get_span().start(), 0);
+                }
+                other => panic!("expected a function, found {:?}", other),
+            },
+            other => panic!("expected an assignment, found {:?}", other),
+        }
+        match statements[1].get_object() {
+            Statement::Class {
+                name,
+                parents,
+                definitions,
+                decorators,
+            } => {
+                assert_eq!(name, "C");
+                assert_eq!(parents.len(), 1);
+                assert!(decorators.is_empty());
+                let names: Vec<_> = definitions.iter().map(|(name, _)| name.as_str()).collect();
+                assert_eq!(names, vec!["x", "g"]);
+            }
+            other => panic!("expected a class, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        let file = source("for x in y:\n    yield x\nimport z\ndef f(a=b):\n    pass\n");
+        let errors = lower(&parse(&file).unwrap()).unwrap_err();
+        let kinds: Vec<_> = errors.iter().map(|e| e.kind().clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LoweringErrorKind::Unsupported("yield"),
+                LoweringErrorKind::Unsupported("imports"),
+                LoweringErrorKind::NonConstantDefault
+            ]
+        );
+        assert_eq!(
+            file.location(errors[1].span().start()),
+            LineColumn::new(3, 0)
+        );
+    }
+
+    #[test]
+    fn decorators() {
+        let block = lower_str("@a\n@b.c\nclass C:\n    pass\n").unwrap();
+        match block.get_object().statements()[0].get_object() {
+            Statement::Decorated {
+                name,
+                decorators,
+                definition,
+            } => {
+                assert_eq!(name, "C");
+                assert_eq!(decorators.len(), 2);
+                assert!(matches!(**definition, Statement::Class { .. }));
+            }
+            other => panic!("expected a decorated class, found {:?}", other),
+        }
+
+        let block = lower_str("class C:\n    @a\n    def f():\n        pass\n").unwrap();
+        match block.get_object().statements()[0].get_object() {
+            Statement::Class {
+                definitions,
+                decorators,
+                ..
+            } => {
+                assert_eq!(definitions.len(), 1);
+                assert_eq!(definitions[0].0, "f");
+                assert_eq!(decorators.len(), 1);
+                assert_eq!(decorators[0].0, "f");
+                assert_eq!(decorators[0].1.len(), 1);
+            }
+            other => panic!("expected a class, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        let block = lower_str(
+            "match m:\n    case {'k': [a, _]} if a:\n        pass\n    case C.D:\n        pass\n    case P(x=1):\n        pass\n",
+        )
+        .unwrap();
+        let cases = match block.get_object().statements()[0].get_object() {
+            Statement::Match { cases, .. } => cases,
+            other => panic!("expected a match statement, found {:?}", other),
+        };
+        match cases[0].pattern() {
+            Pattern::Dictionary(entries) => match &entries[0].1 {
+                Pattern::List(elements) => {
+                    assert!(matches!(&elements[0], Pattern::Capture(a) if a == "a"));
+                    assert!(matches!(&elements[1], Pattern::Wildcard));
+                }
+                other => panic!("expected a list pattern, found {:?}", other),
+            },
+            other => panic!("expected a dictionary pattern, found {:?}", other),
+        }
+        assert!(cases[0].guard().is_some());
+        assert!(matches!(cases[1].pattern(), Pattern::Value(_)));
+        assert!(
+            matches!(cases[2].pattern(), Pattern::Class { members, .. } if members[0].0 == "x")
+        );
+
+        let errors = lower_str("match m:\n    case a + b:\n        pass\n    case P(x):\n        pass\n    case {k: v}:\n        pass\n")
+            .unwrap_err();
+        let kinds: Vec<_> = errors.iter().map(|e| e.kind().clone()).collect();
+        assert_eq!(kinds, vec![LoweringErrorKind::InvalidPattern; 3]);
+    }
+
+    #[test]
+    fn try_statements() {
+        let block = lower_str(
+            "try:\n    a()\nexcept (E, F) as e:\n    pass\nexcept:\n    b()\nfinally:\n    c()\n",
+        )
+        .unwrap();
+        match block.get_object().statements()[0].get_object() {
+            Statement::Try {
+                handlers,
+                r#else,
+                finally,
+                ..
+            } => {
+                assert_eq!(handlers[0].name().map(String::as_str), Some("e"));
+                assert!(handlers[0].block().statements().is_empty());
+                assert!(handlers[1].class().is_none() && handlers[1].name().is_none());
+                assert!(r#else.is_none());
+                assert_eq!(finally.as_ref().unwrap().statements().len(), 1);
+            }
+            other => panic!("expected a try statement, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_nodes() {
+        let (node, _) = parse_recovering(&source("x = )\ny = 2\n"));
+        let errors = lower(&node).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind(), &LoweringErrorKind::SyntaxError);
+    }
+}
@@ -0,0 +1,1774 @@
+use std::fmt::{Display, Formatter};
+
+use basil_ast::node::{Node, NodeType};
+use basil_ast::operators::Operator;
+use basil_core::diagnostic::Diagnostic;
+use basil_core::source_map::SourceFile;
+use basil_core::span::Span;
+
+use crate::lexer::{LexError, LexErrorKind, Lexer};
+use crate::token::{FormatStringPart, Keyword, Token, TokenKind};
+use crate::trivia::attach_trivia;
+
+/// A recursive descent parser that turns [Token]s into a [Node] tree.
+///
+/// Operator precedence, from loosest to tightest binding:
+/// 1. `or`
+/// 2. `and`
+/// 3. `not`
+/// 4. `==`, `!=`, `<`, `>`, `<=`, `>=`
+/// 5. `+`, `-`
+/// 6. `*`, `/`, `%`
+/// 7. unary `-`
+/// 8. calls, member accesses and indexing
+///
+/// The parser doesn't stop at the first syntax error. Statements that fail to parse are replaced by
+/// [Error](NodeType::Error) nodes, and parsing resumes at the next statement or block boundary.
+/// Every error encountered is collected, and can be retrieved with [Parser::errors].
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    errors: Vec<ParseError>,
+}
+
+type ParseResult = Result<Node, ParseError>;
+
+impl Parser {
+    /// Creates a new parser over a list of tokens, which should end with an
+    /// [Eof](TokenKind::Eof) token
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+            errors: vec![],
+        }
+    }
+
+    /// The errors found so far
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<ParseError> {
+        self.errors
+    }
+
+    fn peek(&self) -> &Token {
+        let index = self.position.min(self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+
+    fn peek_kind(&self) -> &TokenKind {
+        self.peek().kind()
+    }
+
+    fn peek_nth_kind(&self, n: usize) -> &TokenKind {
+        let index = (self.position + n).min(self.tokens.len() - 1);
+        self.tokens[index].kind()
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind() != &TokenKind::Eof {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        self.peek_kind() == kind
+    }
+
+    fn check_keyword(&self, keyword: Keyword) -> bool {
+        self.check(&TokenKind::Keyword(keyword))
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> Option<Token> {
+        if self.check(kind) {
+            Some(self.advance())
+        } else {
+            None
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
+        match self.eat(&kind) {
+            Some(token) => Ok(token),
+            None => Err(self.unexpected(kind.to_string())),
+        }
+    }
+
+    /// Expects the bracket that closes `open`. The lexer only ends a line inside of brackets when
+    /// they can't be closed, so reaching the end of the line reports `open` as unclosed instead.
+    fn close(&mut self, open: &Token, kind: TokenKind) -> Result<Token, ParseError> {
+        self.expect(kind)
+            .map_err(|error| Parser::unclosed(open, error))
+    }
+
+    /// Turns an error at the end of a line inside of the bracket `open` into one about the bracket
+    fn unclosed(open: &Token, error: ParseError) -> ParseError {
+        match error.kind() {
+            ParseErrorKind::UnexpectedToken {
+                found: TokenKind::Newline | TokenKind::Dedent | TokenKind::Eof,
+                ..
+            } => ParseError::new(
+                ParseErrorKind::UnclosedBracket(open.kind().clone()),
+                open.span().clone(),
+            ),
+            _ => error,
+        }
+    }
+
+    fn unexpected<S: Into<String>>(&self, expected: S) -> ParseError {
+        let token = self.peek();
+        ParseError::new(
+            ParseErrorKind::UnexpectedToken {
+                found: token.kind().clone(),
+                expected: expected.into(),
+            },
+            token.span().clone(),
+        )
+    }
+
+    /// Parses an entire file, producing a [Block](NodeType::Block) of every statement in it
+    pub fn parse_module(&mut self) -> Node {
+        let start = self.peek().span().clone();
+        let mut statements = vec![];
+        loop {
+            match self.peek_kind() {
+                TokenKind::Eof => break,
+                TokenKind::Newline => {
+                    self.advance();
+                }
+                _ => statements.push(self.statement_or_recover()),
+            }
+        }
+        let span = start.join(self.peek().span());
+        Node::new(span, NodeType::Block(statements))
+    }
+
+    /// Parses a statement, replacing it with an [Error](NodeType::Error) node if it is malformed
+    fn statement_or_recover(&mut self) -> Node {
+        self.recover(Parser::statement)
+    }
+
+    /// Parses something that starts its own line, like a statement or a `case`, replacing it with
+    /// an [Error](NodeType::Error) node and skipping to the next line if it is malformed
+    fn recover(&mut self, parse: fn(&mut Self) -> ParseResult) -> Node {
+        let start = self.position;
+        match parse(self) {
+            Ok(statement) => statement,
+            Err(error) => {
+                let mut span = error.span().clone();
+                self.errors.push(error);
+                self.synchronize();
+                if self.position == start {
+                    // always make progress, even when the error is at a block boundary
+                    self.advance();
+                }
+                if self.position > start {
+                    span = self.tokens[start]
+                        .span()
+                        .join(self.tokens[self.position - 1].span());
+                }
+                Node::new(span, NodeType::Error)
+            }
+        }
+    }
+
+    /// Skips to the start of the next statement. If the broken statement was followed by an
+    /// indented block, that block is skipped as well.
+    fn synchronize(&mut self) {
+        loop {
+            match self.peek_kind() {
+                TokenKind::Eof | TokenKind::Dedent => return,
+                TokenKind::Newline => {
+                    self.advance();
+                    break;
+                }
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+
+        let mut depth = 0;
+        while self.check(&TokenKind::Indent) || depth > 0 {
+            match self.advance().kind() {
+                TokenKind::Indent => depth += 1,
+                TokenKind::Dedent => depth -= 1,
+                TokenKind::Eof => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Records an error for a missing node, and creates an [Error](NodeType::Error) node in its
+    /// place
+    fn missing(&mut self, expected: &str) -> Node {
+        let error = self.unexpected(expected);
+        let span = error.span().clone();
+        self.errors.push(error);
+        Node::new(span, NodeType::Error)
+    }
+
+    fn statement(&mut self) -> ParseResult {
+        match self.peek_kind() {
+            TokenKind::Keyword(Keyword::If) => self.if_statement(),
+            TokenKind::Keyword(Keyword::While) => self.while_statement(),
+            TokenKind::Keyword(Keyword::For) => self.for_statement(),
+            TokenKind::Keyword(Keyword::Match) => self.match_statement(),
+            TokenKind::Keyword(Keyword::Try) => self.try_statement(),
+            TokenKind::Keyword(Keyword::Def) => self.function_definition(),
+            TokenKind::Keyword(Keyword::Class) => self.class_definition(),
+            TokenKind::At => self.decorated(),
+            _ => {
+                let statement = self.simple_statement()?;
+                self.expect(TokenKind::Newline)?;
+                Ok(statement)
+            }
+        }
+    }
+
+    /// Parses a `:` followed by either an indented block, or a single statement on the same line
+    fn block(&mut self) -> ParseResult {
+        let colon = match self.eat(&TokenKind::Colon) {
+            Some(colon) => colon,
+            None if self.check(&TokenKind::Newline) => {
+                // the block is still there, so only the `:` needs to be reported
+                let error = self.unexpected(TokenKind::Colon.to_string());
+                self.errors.push(error);
+                self.peek().clone()
+            }
+            None => return Err(self.unexpected(TokenKind::Colon.to_string())),
+        };
+        if self.eat(&TokenKind::Newline).is_none() {
+            let statement = self.simple_statement()?;
+            self.expect(TokenKind::Newline)?;
+            let span = statement.span().clone();
+            return Ok(Node::new(span, NodeType::Block(vec![statement])));
+        }
+
+        if !self.check(&TokenKind::Indent) {
+            let missing = self.missing("an indented block");
+            return Ok(Node::new(
+                missing.span().clone(),
+                NodeType::Block(vec![missing]),
+            ));
+        }
+        self.advance();
+        let mut statements = vec![];
+        while self.eat(&TokenKind::Dedent).is_none() {
+            if self.eat(&TokenKind::Newline).is_some() {
+                continue;
+            }
+            if self.check(&TokenKind::Eof) {
+                statements.push(self.missing("a statement"));
+                break;
+            }
+            statements.push(self.statement_or_recover());
+        }
+        let span = match (statements.first(), statements.last()) {
+            (Some(first), Some(last)) => first.span().join(last.span()),
+            _ => colon.span().clone(),
+        };
+        Ok(Node::new(span, NodeType::Block(statements)))
+    }
+
+    fn block_statements(block: Node) -> Vec<Node> {
+        match block.into_node_type() {
+            NodeType::Block(statements) => statements,
+            other => unreachable!("Expected a block, found {:?}", other),
+        }
+    }
+
+    fn if_statement(&mut self) -> ParseResult {
+        // handles both `if` and `elif`
+        let keyword = self.advance();
+        let condition = self.expression()?;
+        let block = self.block()?;
+        let r#else = if self.check_keyword(Keyword::Elif) {
+            Some(self.if_statement()?)
+        } else if self.eat(&TokenKind::Keyword(Keyword::Else)).is_some() {
+            Some(self.block()?)
+        } else {
+            None
+        };
+        let end = r#else.as_ref().unwrap_or(&block).span();
+        let span = keyword.span().join(end);
+        Ok(Node::new(
+            span,
+            NodeType::If {
+                condition,
+                block,
+                r#else,
+            },
+        ))
+    }
+
+    fn while_statement(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let condition = self.expression()?;
+        let block = self.block()?;
+        let r#else = self.loop_else()?;
+        let span = keyword
+            .span()
+            .join(r#else.as_ref().unwrap_or(&block).span());
+        Ok(Node::new(
+            span,
+            NodeType::While {
+                condition,
+                block,
+                r#else,
+            },
+        ))
+    }
+
+    fn for_statement(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let identifier = self.postfix_expression()?;
+        self.expect(TokenKind::Keyword(Keyword::In))?;
+        let iterator = self.expression()?;
+        let block = self.block()?;
+        let r#else = self.loop_else()?;
+        let span = keyword
+            .span()
+            .join(r#else.as_ref().unwrap_or(&block).span());
+        Ok(Node::new(
+            span,
+            NodeType::For {
+                identifier,
+                iterator,
+                block,
+                r#else,
+            },
+        ))
+    }
+
+    /// Parses the `else` block of a loop, if it has one
+    fn loop_else(&mut self) -> Result<Option<Node>, ParseError> {
+        if self.eat(&TokenKind::Keyword(Keyword::Else)).is_some() {
+            Ok(Some(self.block()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses `match subject:` followed by an indented block of `case`s
+    fn match_statement(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let subject = self.expression()?;
+        self.expect(TokenKind::Colon)?;
+        self.expect(TokenKind::Newline)?;
+        self.expect(TokenKind::Indent)?;
+        let mut cases = vec![];
+        while self.eat(&TokenKind::Dedent).is_none() {
+            if self.eat(&TokenKind::Newline).is_some() {
+                continue;
+            }
+            if self.check(&TokenKind::Eof) {
+                cases.push(self.missing("`case`"));
+                break;
+            }
+            cases.push(self.recover(Parser::match_case));
+        }
+        let end = cases.last().map_or(subject.span(), Node::span);
+        let span = keyword.span().join(end);
+        Ok(Node::new(span, NodeType::Match { subject, cases }))
+    }
+
+    fn match_case(&mut self) -> ParseResult {
+        if !self.check_keyword(Keyword::Case) {
+            return Err(self.unexpected("`case`"));
+        }
+        let keyword = self.advance();
+        let pattern = self.expression()?;
+        let guard = match self.eat(&TokenKind::Keyword(Keyword::If)) {
+            Some(_) => Some(self.expression()?),
+            None => None,
+        };
+        let block = self.block()?;
+        let span = keyword.span().join(block.span());
+        Ok(Node::new(
+            span,
+            NodeType::MatchCase {
+                pattern,
+                guard,
+                block,
+            },
+        ))
+    }
+
+    /// Parses `try:` followed by its `except` clauses, and an optional `else` and `finally`. There
+    /// has to be at least one `except` or a `finally`, and the `else` needs an `except`.
+    fn try_statement(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let block = self.block()?;
+        let mut handlers: Vec<Node> = vec![];
+        while self.check_keyword(Keyword::Except) {
+            if let Some(previous) = handlers.last() {
+                if let NodeType::ExceptHandler { class: None, .. } = previous.node_type() {
+                    let error = ParseError::new(
+                        ParseErrorKind::DefaultExceptNotLast,
+                        previous.span().clone(),
+                    );
+                    self.errors.push(error);
+                }
+            }
+            handlers.push(self.except_handler()?);
+        }
+        let r#else = match handlers.is_empty() {
+            false if self.eat(&TokenKind::Keyword(Keyword::Else)).is_some() => Some(self.block()?),
+            _ => None,
+        };
+        let finally = match self.eat(&TokenKind::Keyword(Keyword::Finally)) {
+            Some(_) => Some(self.block()?),
+            None if handlers.is_empty() => return Err(self.unexpected("`except` or `finally`")),
+            None => None,
+        };
+        let end = finally
+            .as_ref()
+            .or(r#else.as_ref())
+            .or_else(|| handlers.last())
+            .unwrap_or(&block);
+        let span = keyword.span().join(end.span());
+        Ok(Node::new(
+            span,
+            NodeType::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            },
+        ))
+    }
+
+    /// Parses `except class as name:`, where the class and name are optional
+    fn except_handler(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let class = match self.check(&TokenKind::Colon) {
+            true => None,
+            false => Some(self.expression()?),
+        };
+        let name = match class {
+            Some(_) if self.eat(&TokenKind::Keyword(Keyword::As)).is_some() => {
+                Some(self.identifier()?)
+            }
+            _ => None,
+        };
+        let block = self.block()?;
+        let span = keyword.span().join(block.span());
+        Ok(Node::new(
+            span,
+            NodeType::ExceptHandler { class, name, block },
+        ))
+    }
+
+    fn function_definition(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let name = self.identifier()?;
+        let open = self.expect(TokenKind::LeftParen)?;
+        let parameters = self
+            .parameters(TokenKind::RightParen)
+            .map_err(|error| Parser::unclosed(&open, error))?;
+        let returns = match self.eat(&TokenKind::Arrow) {
+            Some(_) => Some(self.expression()?),
+            None => None,
+        };
+        let block = self.block()?;
+        let span = keyword.span().join(block.span());
+        Ok(Node::new(
+            span,
+            NodeType::Function {
+                name,
+                parameters,
+                returns,
+                block,
+            },
+        ))
+    }
+
+    fn class_definition(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let name = self.identifier()?;
+        let parent = match self.eat(&TokenKind::LeftParen) {
+            Some(open) => {
+                let parent = self.expression()?;
+                self.close(&open, TokenKind::RightParen)?;
+                Some(parent)
+            }
+            None => None,
+        };
+        let block = self.block()?;
+        let span = keyword.span().join(block.span());
+        let defs = Parser::block_statements(block);
+        Ok(Node::new(span, NodeType::Class { name, parent, defs }))
+    }
+
+    /// Parses `@decorator` lines and the `def` or class that they decorate
+    fn decorated(&mut self) -> ParseResult {
+        let start = self.peek().span().clone();
+        let mut decorators = vec![];
+        while self.eat(&TokenKind::At).is_some() {
+            decorators.push(self.expression()?);
+            self.expect(TokenKind::Newline)?;
+        }
+        let definition = match self.peek_kind() {
+            TokenKind::Keyword(Keyword::Def) => self.function_definition()?,
+            TokenKind::Keyword(Keyword::Class) => self.class_definition()?,
+            _ => return Err(self.unexpected("`def` or `class`")),
+        };
+        let span = start.join(definition.span());
+        Ok(Node::new(
+            span,
+            NodeType::Decorated {
+                decorators,
+                definition,
+            },
+        ))
+    }
+
+    fn simple_statement(&mut self) -> ParseResult {
+        let keyword = match self.peek_kind() {
+            TokenKind::Keyword(keyword) => Some(*keyword),
+            _ => None,
+        };
+        match keyword {
+            Some(Keyword::Pass) => {
+                let token = self.advance();
+                Ok(Node::new(token.span().clone(), NodeType::Pass))
+            }
+            Some(Keyword::Break) => {
+                let token = self.advance();
+                Ok(Node::new(token.span().clone(), NodeType::Break))
+            }
+            Some(Keyword::Continue) => {
+                let token = self.advance();
+                Ok(Node::new(token.span().clone(), NodeType::Continue))
+            }
+            Some(Keyword::Return) => {
+                let token = self.advance();
+                let value = if self.check(&TokenKind::Newline) {
+                    Node::new(token.span().clone(), NodeType::None)
+                } else {
+                    self.expression()?
+                };
+                let span = token.span().join(value.span());
+                Ok(Node::new(span, NodeType::Return(value)))
+            }
+            Some(Keyword::Yield) => {
+                let token = self.advance();
+                let value = self.expression()?;
+                let span = token.span().join(value.span());
+                Ok(Node::new(span, NodeType::Yield(value)))
+            }
+            Some(Keyword::Raise) => {
+                let token = self.advance();
+                let value = self.expression()?;
+                let span = token.span().join(value.span());
+                Ok(Node::new(span, NodeType::Raise(value)))
+            }
+            Some(Keyword::Import) => {
+                let token = self.advance();
+                let mut path = self.identifier()?;
+                while self.eat(&TokenKind::Dot).is_some() {
+                    let child = self.identifier()?;
+                    let span = path.span().join(child.span());
+                    path = Node::new(
+                        span,
+                        NodeType::QualifiedIdentifier {
+                            parent: path,
+                            child,
+                        },
+                    );
+                }
+                let span = token.span().join(path.span());
+                Ok(Node::new(span, NodeType::Import(path)))
+            }
+            _ => self.expression_statement(),
+        }
+    }
+
+    fn expression_statement(&mut self) -> ParseResult {
+        let lhs = self.expression_list(&TokenKind::Assign)?;
+        let var_type = match self.eat(&TokenKind::Colon) {
+            Some(_) => {
+                let var_type = self.expression()?;
+                self.expect(TokenKind::Assign)?;
+                Some(var_type)
+            }
+            None if self.eat(&TokenKind::Assign).is_some() => None,
+            None => return Ok(lhs),
+        };
+        let invalid = match lhs.node_type() {
+            // the names that a list or tuple is unpacked into can't be annotated
+            NodeType::List(_) | NodeType::Tuple(_) if var_type.is_some() => Some(&lhs),
+            _ => invalid_target(&lhs),
+        };
+        if let Some(invalid) = invalid {
+            return Err(ParseError::new(
+                ParseErrorKind::InvalidAssignmentTarget,
+                invalid.span().clone(),
+            ));
+        }
+        let rhs = self.expression_list(&TokenKind::Newline)?;
+        let span = lhs.span().join(rhs.span());
+        Ok(Node::new(span, NodeType::Assignment { lhs, var_type, rhs }))
+    }
+
+    /// Parses an expression, or a tuple without parentheses such as `a, b`, which can only be
+    /// written where it's followed by `close`
+    fn expression_list(&mut self, close: &TokenKind) -> ParseResult {
+        let first = self.expression()?;
+        if !self.check(&TokenKind::Comma) {
+            return Ok(first);
+        }
+        let elements = self.elements(first, close, Parser::expression)?;
+        let span = elements[0].span().join(elements[elements.len() - 1].span());
+        Ok(Node::new(span, NodeType::Tuple(elements)))
+    }
+
+    fn identifier(&mut self) -> ParseResult {
+        match self.peek_kind() {
+            TokenKind::Identifier(id) => {
+                let id = id.clone();
+                let token = self.advance();
+                Ok(Node::new(token.span().clone(), NodeType::Identifier(id)))
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    /// Parses a comma separated list of parameters, up to and including the `end` token. Unless
+    /// the list ends with a `:`, as a lambda's does, the parameters can be annotated.
+    fn parameters(&mut self, end: TokenKind) -> Result<Vec<Node>, ParseError> {
+        let mut parameters = vec![];
+        while self.eat(&end).is_none() {
+            let name = self.identifier()?;
+            let annotation = match end {
+                TokenKind::Colon => None,
+                _ => match self.eat(&TokenKind::Colon) {
+                    Some(_) => Some(self.expression()?),
+                    None => None,
+                },
+            };
+            let default = match self.eat(&TokenKind::Assign) {
+                Some(_) => Some(self.expression()?),
+                None => None,
+            };
+            let span = match default.as_ref().or(annotation.as_ref()) {
+                Some(last) => name.span().join(last.span()),
+                None => name.span().clone(),
+            };
+            parameters.push(Node::new(
+                span,
+                NodeType::Parameter {
+                    name,
+                    annotation,
+                    default,
+                },
+            ));
+            if self.eat(&TokenKind::Comma).is_none() {
+                self.expect(end)?;
+                break;
+            }
+        }
+        Ok(parameters)
+    }
+
+    /// Parses a full expression
+    pub fn expression(&mut self) -> ParseResult {
+        if self.check_keyword(Keyword::Lambda) {
+            return self.lambda();
+        }
+        self.or_expression()
+    }
+
+    /// Parses `lambda parameters: body`, which has the lowest precedence of any expression
+    fn lambda(&mut self) -> ParseResult {
+        let keyword = self.advance();
+        let parameters = self.parameters(TokenKind::Colon)?;
+        let body = self.expression()?;
+        let span = keyword.span().join(body.span());
+        Ok(Node::new(span, NodeType::Lambda { parameters, body }))
+    }
+
+    fn peek_operator(&self, operators: &[Operator]) -> Option<Operator> {
+        match self.peek_kind() {
+            TokenKind::Operator(op) if operators.contains(op) => Some(*op),
+            _ => None,
+        }
+    }
+
+    /// Parses a left associative chain of binary operators
+    fn binary(
+        &mut self,
+        operators: &[Operator],
+        next: fn(&mut Self) -> ParseResult,
+    ) -> ParseResult {
+        let mut lhs = next(self)?;
+        while let Some(op) = self.peek_operator(operators) {
+            let op_token = self.advance();
+            let rhs = next(self)?;
+            let span = lhs.span().join(rhs.span());
+            let op = Node::new(op_token.span().clone(), NodeType::Operator(op));
+            lhs = Node::new(span, NodeType::BinaryExpression { lhs, rhs, op });
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self, operator: Operator, next: fn(&mut Self) -> ParseResult) -> ParseResult {
+        if self.peek_operator(&[operator]).is_none() {
+            return next(self);
+        }
+        let op_token = self.advance();
+        let val = self.unary(operator, next)?;
+        let span = op_token.span().join(val.span());
+        let op = Node::new(op_token.span().clone(), NodeType::Operator(operator));
+        Ok(Node::new(span, NodeType::UnaryExpression { val, op }))
+    }
+
+    fn or_expression(&mut self) -> ParseResult {
+        self.binary(&[Operator::Or], Parser::and_expression)
+    }
+
+    fn and_expression(&mut self) -> ParseResult {
+        self.binary(&[Operator::And], Parser::not_expression)
+    }
+
+    fn not_expression(&mut self) -> ParseResult {
+        self.unary(Operator::Not, Parser::comparison)
+    }
+
+    fn comparison(&mut self) -> ParseResult {
+        self.binary(
+            &[
+                Operator::Eq,
+                Operator::Neq,
+                Operator::Lt,
+                Operator::Gt,
+                Operator::Lte,
+                Operator::Gte,
+            ],
+            Parser::additive,
+        )
+    }
+
+    fn additive(&mut self) -> ParseResult {
+        self.binary(&[Operator::Add, Operator::Sub], Parser::multiplicative)
+    }
+
+    fn multiplicative(&mut self) -> ParseResult {
+        self.binary(
+            &[Operator::Mul, Operator::Div, Operator::Rem],
+            Parser::negation,
+        )
+    }
+
+    fn negation(&mut self) -> ParseResult {
+        self.unary(Operator::Sub, Parser::postfix_expression)
+    }
+
+    fn postfix_expression(&mut self) -> ParseResult {
+        let mut value = self.atom()?;
+        loop {
+            match self.peek_kind() {
+                TokenKind::LeftParen => {
+                    let open = self.advance();
+                    let parameters = self.arguments()?;
+                    let close = self.close(&open, TokenKind::RightParen)?;
+                    let span = value.span().join(close.span());
+                    value = Node::new(
+                        span,
+                        NodeType::FunctionCall {
+                            name: value,
+                            parameters,
+                        },
+                    );
+                }
+                TokenKind::Dot => {
+                    self.advance();
+                    let child = self.identifier()?;
+                    let span = value.span().join(child.span());
+                    value = Node::new(
+                        span,
+                        NodeType::QualifiedIdentifier {
+                            parent: value,
+                            child,
+                        },
+                    );
+                }
+                TokenKind::LeftBracket => {
+                    let open = self.advance();
+                    let index = self.expression()?;
+                    let close = self.close(&open, TokenKind::RightBracket)?;
+                    let span = value.span().join(close.span());
+                    value = Node::new(span, NodeType::Index { value, index });
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Node>, ParseError> {
+        let mut arguments = vec![];
+        while !self.check(&TokenKind::RightParen) {
+            let is_keyword = matches!(self.peek_kind(), TokenKind::Identifier(_))
+                && self.peek_nth_kind(1) == &TokenKind::Assign;
+            if is_keyword {
+                let name = self.identifier()?;
+                self.advance();
+                let value = self.expression()?;
+                let span = name.span().join(value.span());
+                arguments.push(Node::new(span, NodeType::KeywordArgument { name, value }));
+            } else {
+                arguments.push(self.expression()?);
+            }
+            if self.eat(&TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn atom(&mut self) -> ParseResult {
+        let node_type = match self.peek_kind() {
+            TokenKind::Identifier(id) => NodeType::Identifier(id.clone()),
+            TokenKind::Integer(i) => NodeType::Integer(i.clone()),
+            TokenKind::Float(f) => NodeType::Float(*f),
+            TokenKind::String(s) => NodeType::String(s.clone()),
+            TokenKind::Keyword(Keyword::True) => NodeType::Boolean(true),
+            TokenKind::Keyword(Keyword::False) => NodeType::Boolean(false),
+            TokenKind::Keyword(Keyword::None) => NodeType::None,
+            TokenKind::Error => NodeType::Error,
+            TokenKind::FormatString(_) => return Ok(self.format_string()),
+            TokenKind::LeftParen => return self.parenthesized(),
+            TokenKind::LeftBracket => return self.list(),
+            TokenKind::LeftBrace => return self.dictionary_or_set(),
+            TokenKind::Newline | TokenKind::Dedent | TokenKind::Eof => {
+                return Ok(self.missing("an expression"))
+            }
+            _ => return Err(self.unexpected("an expression")),
+        };
+        let token = self.advance();
+        Ok(Node::new(token.span().clone(), node_type))
+    }
+
+    /// Parses an interpolated string, where the expressions inside of it have already been
+    /// tokenized
+    fn format_string(&mut self) -> Node {
+        let token = self.advance();
+        let span = token.span().clone();
+        let parts = match token.into_kind() {
+            TokenKind::FormatString(parts) => parts,
+            _ => unreachable!("format_string is only called on format strings"),
+        };
+        let mut nodes = vec![];
+        for part in parts {
+            match part {
+                FormatStringPart::Literal(value, span) => {
+                    nodes.push(Node::new(span, NodeType::String(value)))
+                }
+                FormatStringPart::Expression { tokens, repr, span } => {
+                    let mut parser = Parser::new(tokens);
+                    let value = match parser.expression() {
+                        Ok(value) => {
+                            if !parser.check(&TokenKind::Eof) {
+                                let error = parser.unexpected("`}`");
+                                parser.errors.push(error);
+                            }
+                            value
+                        }
+                        Err(error) => {
+                            let span = error.span().clone();
+                            parser.errors.push(error);
+                            Node::new(span, NodeType::Error)
+                        }
+                    };
+                    self.errors.extend(parser.into_errors());
+                    nodes.push(Node::new(span, NodeType::Interpolation { value, repr }));
+                }
+            }
+        }
+        Node::new(span, NodeType::FormatString(nodes))
+    }
+
+    /// Parses either a parenthesized expression or a tuple, which needs a comma unless it's empty
+    fn parenthesized(&mut self) -> ParseResult {
+        let open = self.advance();
+        if let Some(close) = self.eat(&TokenKind::RightParen) {
+            let span = open.span().join(close.span());
+            return Ok(Node::new(span, NodeType::Tuple(vec![])));
+        }
+        let first = self.expression()?;
+        if !self.check(&TokenKind::Comma) {
+            self.close(&open, TokenKind::RightParen)?;
+            return Ok(first);
+        }
+        let elements = self.elements(first, &TokenKind::RightParen, Parser::expression)?;
+        let close = self.close(&open, TokenKind::RightParen)?;
+        let span = open.span().join(close.span());
+        Ok(Node::new(span, NodeType::Tuple(elements)))
+    }
+
+    fn list(&mut self) -> ParseResult {
+        let open = self.advance();
+        let node_type = if self.check(&TokenKind::RightBracket) {
+            NodeType::List(vec![])
+        } else {
+            let first = self.expression()?;
+            if self.check_keyword(Keyword::For) {
+                let clauses = self.comprehension_clauses()?;
+                NodeType::ListComprehension {
+                    element: first,
+                    clauses,
+                }
+            } else {
+                NodeType::List(self.elements(
+                    first,
+                    &TokenKind::RightBracket,
+                    Parser::expression,
+                )?)
+            }
+        };
+        let close = self.close(&open, TokenKind::RightBracket)?;
+        Ok(Node::new(open.span().join(close.span()), node_type))
+    }
+
+    /// Parses a `{...}` literal, which is a set if its first element isn't a `key: value` entry
+    fn dictionary_or_set(&mut self) -> ParseResult {
+        let open = self.advance();
+        let node_type = if self.check(&TokenKind::RightBrace) {
+            NodeType::Dictionary(vec![])
+        } else {
+            let first = self.expression()?;
+            if self.eat(&TokenKind::Colon).is_some() {
+                let value = self.expression()?;
+                let span = first.span().join(value.span());
+                let entry = Node::new(span, NodeType::DictionaryEntry { key: first, value });
+                if self.check_keyword(Keyword::For) {
+                    let clauses = self.comprehension_clauses()?;
+                    NodeType::DictionaryComprehension {
+                        element: entry,
+                        clauses,
+                    }
+                } else {
+                    let entries =
+                        self.elements(entry, &TokenKind::RightBrace, Parser::dictionary_entry)?;
+                    NodeType::Dictionary(entries)
+                }
+            } else if self.check_keyword(Keyword::For) {
+                let clauses = self.comprehension_clauses()?;
+                NodeType::SetComprehension {
+                    element: first,
+                    clauses,
+                }
+            } else {
+                NodeType::Set(self.elements(first, &TokenKind::RightBrace, Parser::expression)?)
+            }
+        };
+        let close = self.close(&open, TokenKind::RightBrace)?;
+        Ok(Node::new(open.span().join(close.span()), node_type))
+    }
+
+    fn dictionary_entry(&mut self) -> ParseResult {
+        let key = self.expression()?;
+        self.expect(TokenKind::Colon)?;
+        let value = self.expression()?;
+        let span = key.span().join(value.span());
+        Ok(Node::new(span, NodeType::DictionaryEntry { key, value }))
+    }
+
+    /// Parses the comma separated elements of a collection after the first one, allowing a
+    /// trailing comma before `close`
+    fn elements(
+        &mut self,
+        first: Node,
+        close: &TokenKind,
+        element: fn(&mut Self) -> ParseResult,
+    ) -> Result<Vec<Node>, ParseError> {
+        let mut elements = vec![first];
+        while self.eat(&TokenKind::Comma).is_some() {
+            if self.check(close) {
+                break;
+            }
+            elements.push(element(self)?);
+        }
+        Ok(elements)
+    }
+
+    fn comprehension_clauses(&mut self) -> Result<Vec<Node>, ParseError> {
+        let mut clauses = vec![];
+        loop {
+            if self.check_keyword(Keyword::For) {
+                let keyword = self.advance();
+                let identifier = self.postfix_expression()?;
+                self.expect(TokenKind::Keyword(Keyword::In))?;
+                let iterator = self.expression()?;
+                let span = keyword.span().join(iterator.span());
+                clauses.push(Node::new(
+                    span,
+                    NodeType::ComprehensionFor {
+                        identifier,
+                        iterator,
+                    },
+                ));
+            } else if self.check_keyword(Keyword::If) {
+                let keyword = self.advance();
+                let condition = self.expression()?;
+                let span = keyword.span().join(condition.span());
+                clauses.push(Node::new(span, NodeType::ComprehensionIf(condition)));
+            } else {
+                return Ok(clauses);
+            }
+        }
+    }
+}
+
+/// Finds the part of an assignment target that can't be assigned to, if there is one
+fn invalid_target(target: &Node) -> Option<&Node> {
+    match target.node_type() {
+        NodeType::Identifier(_) | NodeType::QualifiedIdentifier { .. } | NodeType::Index { .. } => {
+            None
+        }
+        NodeType::List(elements) | NodeType::Tuple(elements) if !elements.is_empty() => {
+            elements.iter().find_map(invalid_target)
+        }
+        _ => Some(target),
+    }
+}
+
+/// Parses a source file into a [Block](NodeType::Block) node, failing with the first error in the
+/// file
+pub fn parse(source: &SourceFile) -> Result<Node, ParseError> {
+    let (node, mut errors) = parse_recovering(source);
+    if errors.is_empty() {
+        Ok(node)
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+/// Parses a source file into a [Block](NodeType::Block) node, recovering from errors.
+///
+/// Returns the partial tree, where anything that couldn't be parsed is an
+/// [Error](NodeType::Error) node, along with every error in the file ordered by position. Comments
+/// and blank lines are attached to the statements of the tree as [Trivia](basil_ast::trivia::Trivia).
+pub fn parse_recovering(source: &SourceFile) -> (Node, Vec<ParseError>) {
+    let mut lexer = Lexer::new(source.id(), source.text());
+    let (mut node, errors) = parse_tokens(&mut lexer);
+    attach_trivia(&mut node, source, lexer.take_trivia());
+    (node, errors)
+}
+
+/// Parses everything that a lexer produces, recovering from errors. The lexer's trivia is left for
+/// the caller to attach.
+pub(crate) fn parse_tokens(lexer: &mut Lexer) -> (Node, Vec<ParseError>) {
+    let mut tokens = vec![];
+    let mut errors = vec![];
+    for result in lexer {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => {
+                if error.kind() != &LexErrorKind::InconsistentDedent {
+                    tokens.push(Token::new(TokenKind::Error, error.span().clone()));
+                }
+                errors.push(ParseError::from(error));
+            }
+        }
+    }
+    let mut parser = Parser::new(tokens);
+    let node = parser.parse_module();
+    errors.extend(parser.into_errors());
+    errors.sort_by_key(|error| error.span().start());
+    (node, errors)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    kind: Box<ParseErrorKind>,
+    span: Span,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, span: Span) -> Self {
+        ParseError {
+            kind: Box::new(kind),
+            span,
+        }
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        match &*self.kind {
+            ParseErrorKind::Lex(kind) => kind.diagnostic(self.span.clone()),
+            ParseErrorKind::UnexpectedToken { expected, .. } => {
+                Diagnostic::error(self.kind.to_string(), self.span.clone())
+                    .with_primary_label(format!("expected {}", expected))
+            }
+            ParseErrorKind::InvalidAssignmentTarget => {
+                Diagnostic::error(self.kind.to_string(), self.span.clone()).with_help(
+                    "only names, members, indexes, and lists or tuples of them can be assigned to",
+                )
+            }
+            ParseErrorKind::DefaultExceptNotLast => {
+                Diagnostic::error(self.kind.to_string(), self.span.clone())
+                    .with_help("it catches every exception, so the clauses after it never run")
+            }
+            ParseErrorKind::UnclosedBracket(_) => {
+                Diagnostic::error(self.kind.to_string(), self.span.clone())
+                    .with_primary_label("this bracket is never closed")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    Lex(LexErrorKind),
+    UnexpectedToken {
+        found: TokenKind,
+        expected: String,
+    },
+    InvalidAssignmentTarget,
+    /// An `except` without a class, which catches everything, followed by another `except`
+    DefaultExceptNotLast,
+    /// A bracket that the line, or the file, ended inside of
+    UnclosedBracket(TokenKind),
+}
+
+impl From<LexError> for ParseError {
+    fn from(e: LexError) -> Self {
+        ParseError::new(ParseErrorKind::Lex(e.kind().clone()), e.span().clone())
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Lex(kind) => write!(f, "{}", kind),
+            ParseErrorKind::UnexpectedToken { found, expected } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ParseErrorKind::InvalidAssignmentTarget => write!(f, "can't assign to this expression"),
+            ParseErrorKind::DefaultExceptNotLast => {
+                write!(f, "an `except` without a class must be the last `except`")
+            }
+            ParseErrorKind::UnclosedBracket(open) => write!(f, "unclosed {}", open),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{source, source_map};
+    use basil_ast::builder::Builder;
+    use basil_ast::dump::{from_sexpr, to_sexpr};
+    use basil_core::span::LineColumn;
+
+    fn parse_str(source: &str) -> String {
+        to_sexpr(&parse(&self::source(source)).unwrap(), false)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            parse_str("a or b and not c == d + e * -f\n"),
+            "(Block [(BinaryExpression (Identifier \"a\") (BinaryExpression (Identifier \"b\") \
+             (UnaryExpression (BinaryExpression (Identifier \"c\") (BinaryExpression \
+             (Identifier \"d\") (BinaryExpression (Identifier \"e\") (UnaryExpression \
+             (Identifier \"f\") (Operator Sub)) (Operator Mul)) (Operator Add)) (Operator Eq)) \
+             (Operator Not)) (Operator And)) (Operator Or))])"
+        );
+        assert_eq!(
+            parse_str("(a - b) - c % d"),
+            "(Block [(BinaryExpression (BinaryExpression (Identifier \"a\") (Identifier \"b\") \
+             (Operator Sub)) (BinaryExpression (Identifier \"c\") (Identifier \"d\") \
+             (Operator Rem)) (Operator Sub))])"
+        );
+        assert_eq!(
+            parse_str("x.y[1](2, k='v') <= 1.5"),
+            "(Block [(BinaryExpression (FunctionCall (Index (QualifiedIdentifier \
+             (Identifier \"x\") (Identifier \"y\")) (Integer 1)) [(Integer 2) (KeywordArgument \
+             (Identifier \"k\") (String \"v\"))]) (Float 3/2) (Operator Lte))])"
+        );
+    }
+
+    #[test]
+    fn collections() {
+        assert_eq!(
+            parse_str("[1, (2,), (3), (), {}, {'a': [],}, {x}]"),
+            "(Block [(List [(Integer 1) (Tuple [(Integer 2)]) (Integer 3) (Tuple []) (Dictionary \
+             []) (Dictionary [(DictionaryEntry (String \"a\") (List []))]) (Set \
+             [(Identifier \"x\")])])])"
+        );
+        assert_eq!(
+            parse_str("[x * y for x in xs if x\n for y in ys]"),
+            "(Block [(ListComprehension (BinaryExpression (Identifier \"x\") (Identifier \"y\") \
+             (Operator Mul)) [(ComprehensionFor (Identifier \"x\") (Identifier \"xs\")) \
+             (ComprehensionIf (Identifier \"x\")) (ComprehensionFor (Identifier \"y\") \
+             (Identifier \"ys\"))])])"
+        );
+        assert_eq!(
+            parse_str("{k: v for k in d}\n{x for x in xs}"),
+            "(Block [(DictionaryComprehension (DictionaryEntry (Identifier \"k\") \
+             (Identifier \"v\")) [(ComprehensionFor (Identifier \"k\") (Identifier \"d\"))]) \
+             (SetComprehension (Identifier \"x\") [(ComprehensionFor (Identifier \"x\") \
+             (Identifier \"xs\"))])])"
+        );
+    }
+
+    #[test]
+    fn format_strings() {
+        assert_eq!(
+            parse_str("f\"x = {x + 1} {y!r}\""),
+            "(Block [(FormatString [(String \"x = \") (Interpolation (BinaryExpression \
+             (Identifier \"x\") (Integer 1) (Operator Add)) false) (String \" \") (Interpolation \
+             (Identifier \"y\") true)])])"
+        );
+        let (_, errors) = parse_recovering(&source("f'{x +}'\n"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span().start(), 6);
+    }
+
+    #[test]
+    fn statements() {
+        let source = "\
+def f(a, b=None):
+    if a:
+        return b
+    elif not a:
+        pass
+    else:
+        raise a
+class C(B):
+    def g(): return 1
+for i in xs: x.y = i
+while True:
+    break
+import a.b
+";
+        assert_eq!(
+            parse_str(source),
+            "(Block [(Function (Identifier \"f\") [(Parameter (Identifier \"a\") _ _) (Parameter \
+             (Identifier \"b\") _ (None))] _ (Block [(If (Identifier \"a\") (Block [(Return \
+             (Identifier \"b\"))]) (If (UnaryExpression (Identifier \"a\") (Operator Not)) \
+             (Block [(Pass)]) (Block [(Raise (Identifier \"a\"))])))])) (Class \
+             (Identifier \"C\") (Identifier \"B\") [(Function (Identifier \"g\") [] _ (Block \
+             [(Return (Integer 1))]))]) (For (Identifier \"i\") (Identifier \"xs\") (Block \
+             [(Assignment (QualifiedIdentifier (Identifier \"x\") (Identifier \"y\")) _ \
+             (Identifier \"i\"))]) _) (While (Boolean true) (Block [(Break)]) _) (Import \
+             (QualifiedIdentifier (Identifier \"a\") (Identifier \"b\")))])"
+        );
+    }
+
+    #[test]
+    fn loops() {
+        let source = "\
+for x in xs:
+    if x:
+        continue
+    break
+else:
+    pass
+while a: b
+else: c
+";
+        assert_eq!(
+            parse_str(source),
+            "(Block [(For (Identifier \"x\") (Identifier \"xs\") (Block [(If (Identifier \"x\") \
+             (Block [(Continue)]) _) (Break)]) (Block [(Pass)])) (While (Identifier \"a\") \
+             (Block [(Identifier \"b\")]) (Block [(Identifier \"c\")]))])"
+        );
+
+        let b = Builder::new();
+        let module = b.module(vec![
+            b.for_(b.identifier("x"), b.identifier("xs"))
+                .body_else(vec![b.continue_(), b.break_()], b.pass()),
+            b.while_(b.identifier("a"))
+                .body_else(b.identifier("b"), b.identifier("c")),
+        ]);
+        assert_eq!(
+            to_sexpr(&module, false),
+            parse_str(
+                "for x in xs:\n    continue\n    break\nelse:\n    pass\nwhile a: b\nelse: c\n"
+            )
+        );
+
+        let module = parse(&self::source(source)).unwrap();
+        let dumped = to_sexpr(&module, true);
+        assert_eq!(to_sexpr(&from_sexpr(&dumped).unwrap(), true), dumped);
+        let statements = Parser::block_statements(module);
+        // the `else` block is part of the loop
+        assert_eq!(statements[0].span().end(), source.find("pass").unwrap() + 4);
+    }
+
+    #[test]
+    fn try_statements() {
+        let source = "\
+try:
+    risky()
+except KeyError as e:
+    print(e)
+except (A, B): pass
+except:
+    raise Failed
+else:
+    done()
+finally:
+    close()
+try: a
+finally: b
+";
+        assert_eq!(
+            parse_str(source),
+            "(Block [(Try (Block [(FunctionCall (Identifier \"risky\") [])]) [(ExceptHandler \
+             (Identifier \"KeyError\") (Identifier \"e\") (Block [(FunctionCall \
+             (Identifier \"print\") [(Identifier \"e\")])])) (ExceptHandler (Tuple \
+             [(Identifier \"A\") (Identifier \"B\")]) _ (Block [(Pass)])) (ExceptHandler _ _ \
+             (Block [(Raise (Identifier \"Failed\"))]))] (Block [(FunctionCall \
+             (Identifier \"done\") [])]) (Block [(FunctionCall (Identifier \"close\") [])])) \
+             (Try (Block [(Identifier \"a\")]) [] _ (Block [(Identifier \"b\")]))])"
+        );
+
+        let b = Builder::new();
+        let statement = b
+            .try_(b.identifier("a"))
+            .except_as(b.identifier("E"), "e", b.identifier("e"))
+            .except(b.pass())
+            .else_(b.identifier("b"))
+            .build();
+        assert_eq!(
+            to_sexpr(&b.module(vec![statement]), false),
+            parse_str("try: a\nexcept E as e: e\nexcept: pass\nelse: b\n")
+        );
+
+        let module = parse(&self::source(source)).unwrap();
+        let dumped = to_sexpr(&module, true);
+        assert_eq!(to_sexpr(&from_sexpr(&dumped).unwrap(), true), dumped);
+
+        let (_, errors) = parse_all("try: a\nexcept: b\nexcept E: c\n");
+        assert_eq!(errors, vec![ParseErrorKind::DefaultExceptNotLast]);
+        let (tree, errors) = parse_all("try: a\nelse: b\nc = 1\n");
+        assert_eq!(
+            tree,
+            "(Block [(Error) (Assignment (Identifier \"c\") _ (Integer 1))])"
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn assignment_targets() {
+        assert_eq!(
+            parse_str("a, b = b, a\n[x, (y, z.w)] = v\np[0], = 1,\nc, d\n"),
+            "(Block [(Assignment (Tuple [(Identifier \"a\") (Identifier \"b\")]) _ (Tuple \
+             [(Identifier \"b\") (Identifier \"a\")])) (Assignment (List [(Identifier \"x\") \
+             (Tuple [(Identifier \"y\") (QualifiedIdentifier (Identifier \"z\") \
+             (Identifier \"w\"))])]) _ (Identifier \"v\")) (Assignment (Tuple [(Index \
+             (Identifier \"p\") (Integer 0))]) _ (Tuple [(Integer 1)])) (Tuple \
+             [(Identifier \"c\") (Identifier \"d\")])])"
+        );
+        assert_eq!(
+            parse_str("t = (a, b)\n"),
+            "(Block [(Assignment (Identifier \"t\") _ (Tuple [(Identifier \"a\") \
+             (Identifier \"b\")]))])"
+        );
+
+        let file = source("a, f(x) = 1, 2\n");
+        let error = parse(&file).unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::InvalidAssignmentTarget);
+        assert_eq!(error.span().start(), 3);
+        let error = parse(&source("a, b: Integer = 1, 2\n")).unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::InvalidAssignmentTarget);
+        let error = parse(&source("[] = x\n")).unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::InvalidAssignmentTarget);
+    }
+
+    #[test]
+    fn lambdas() {
+        assert_eq!(
+            parse_str(
+                "f = lambda: x
+g = lambda a, b=1: a or b
+"
+            ),
+            "(Block [(Assignment (Identifier \"f\") _ (Lambda [] (Identifier \"x\"))) \
+             (Assignment (Identifier \"g\") _ (Lambda [(Parameter (Identifier \"a\") _ _) \
+             (Parameter (Identifier \"b\") _ (Integer 1))] (BinaryExpression (Identifier \"a\") \
+             (Identifier \"b\") (Operator Or))))])"
+        );
+        assert_eq!(
+            parse_str(
+                "h = lambda a: lambda: {a: (lambda: 1)()}
+"
+            ),
+            "(Block [(Assignment (Identifier \"h\") _ (Lambda [(Parameter \
+             (Identifier \"a\") _ _)] (Lambda [] (Dictionary [(DictionaryEntry \
+             (Identifier \"a\") (FunctionCall (Lambda [] (Integer 1)) []))]))))])"
+        );
+        let b = Builder::new();
+        let lambda = b.lambda(
+            vec![b.parameter("a", None), b.parameter("b", Some(b.integer(1)))],
+            b.identifier("a"),
+        );
+        assert_eq!(
+            to_sexpr(&lambda, false),
+            "(Lambda [(Parameter (Identifier \"a\") _ _) (Parameter (Identifier \"b\") _ \
+             (Integer 1))] (Identifier \"a\"))"
+        );
+    }
+
+    #[test]
+    fn match_statements() {
+        let source = "\
+match message:
+    case {'type': 'move', 'to': [x, y]} if x > 0:
+        go(x, y)
+
+    case Point(x=0, y=_): pass
+    case other:
+        return other
+";
+        assert_eq!(
+            parse_str(source),
+            "(Block [(Match (Identifier \"message\") [(MatchCase (Dictionary [(DictionaryEntry \
+             (String \"type\") (String \"move\")) (DictionaryEntry (String \"to\") (List \
+             [(Identifier \"x\") (Identifier \"y\")]))]) (BinaryExpression (Identifier \"x\") \
+             (Integer 0) (Operator Gt)) (Block [(FunctionCall (Identifier \"go\") \
+             [(Identifier \"x\") (Identifier \"y\")])])) (MatchCase (FunctionCall \
+             (Identifier \"Point\") [(KeywordArgument (Identifier \"x\") (Integer 0)) \
+             (KeywordArgument (Identifier \"y\") (Identifier \"_\"))]) _ (Block [(Pass)])) \
+             (MatchCase (Identifier \"other\") _ (Block [(Return (Identifier \"other\"))]))])])"
+        );
+
+        let b = Builder::new();
+        let statement = b
+            .match_(b.identifier("message"))
+            .guarded_case(b.list(vec![b.identifier("x")]), b.identifier("x"), b.pass())
+            .case(b.identifier("_"), b.break_())
+            .build();
+        assert_eq!(
+            to_sexpr(&b.module(vec![statement]), false),
+            parse_str(
+                "match message:\n    case [x] if x:\n        pass\n    case _:\n        break\n"
+            )
+        );
+
+        let module = parse(&self::source(source)).unwrap();
+        let dumped = to_sexpr(&module, true);
+        assert_eq!(to_sexpr(&from_sexpr(&dumped).unwrap(), true), dumped);
+
+        let source =
+            "match x:\n    case 1 +:\n        a = 1\n    b = 2\n    case 2:\n        pass\nc = 3\n";
+        let (tree, errors) = parse_all(source);
+        assert_eq!(
+            tree,
+            "(Block [(Match (Identifier \"x\") [(Error) (Error) (MatchCase (Integer 2) _ (Block \
+             [(Pass)]))]) (Assignment (Identifier \"c\") _ (Integer 3))])"
+        );
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn annotations() {
+        let source = "\
+count: Integer = 0
+def scale(x: Float, by: Integer = 2) -> Float:
+    return x * by
+f = lambda a: a
+";
+        assert_eq!(
+            parse_str(source),
+            "(Block [(Assignment (Identifier \"count\") (Identifier \"Integer\") (Integer 0)) \
+             (Function (Identifier \"scale\") [(Parameter (Identifier \"x\") \
+             (Identifier \"Float\") _) (Parameter (Identifier \"by\") (Identifier \"Integer\") \
+             (Integer 2))] (Identifier \"Float\") (Block [(Return (BinaryExpression \
+             (Identifier \"x\") (Identifier \"by\") (Operator Mul)))])) (Assignment \
+             (Identifier \"f\") _ (Lambda [(Parameter (Identifier \"a\") _ _)] \
+             (Identifier \"a\")))])"
+        );
+
+        let b = Builder::new();
+        let statement = b
+            .function("f")
+            .annotated_parameter("x", b.identifier("String"))
+            .returns(b.none())
+            .body(b.assign_annotated(b.identifier("y"), b.identifier("Any"), b.identifier("x")));
+        assert_eq!(
+            to_sexpr(&b.module(vec![statement]), false),
+            parse_str("def f(x: String) -> None:\n    y: Any = x\n")
+        );
+
+        let module = parse(&self::source(source)).unwrap();
+        let dumped = to_sexpr(&module, true);
+        assert_eq!(to_sexpr(&from_sexpr(&dumped).unwrap(), true), dumped);
+
+        let (_, errors) = parse_all("x: Integer\n");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn decorators() {
+        let source = "\
+@app.on('click')
+@log
+def handler(event):
+    pass
+@register
+class Button: pass
+";
+        assert_eq!(
+            parse_str(source),
+            "(Block [(Decorated [(FunctionCall (QualifiedIdentifier (Identifier \"app\") \
+             (Identifier \"on\")) [(String \"click\")]) (Identifier \"log\")] (Function \
+             (Identifier \"handler\") [(Parameter (Identifier \"event\") _ _)] _ (Block \
+             [(Pass)]))) (Decorated [(Identifier \"register\")] (Class (Identifier \"Button\") _ \
+             [(Pass)]))])"
+        );
+
+        let b = Builder::new();
+        let statement = b.decorated(vec![b.identifier("log")], b.function("f").body(b.pass()));
+        assert_eq!(
+            to_sexpr(&b.module(vec![statement]), false),
+            parse_str("@log\ndef f():\n    pass\n")
+        );
+
+        let module = parse(&self::source(source)).unwrap();
+        let dumped = to_sexpr(&module, true);
+        assert_eq!(to_sexpr(&from_sexpr(&dumped).unwrap(), true), dumped);
+
+        let (tree, errors) = parse_all("@log\nx = 1\ny = 2\n");
+        assert_eq!(
+            tree,
+            "(Block [(Error) (Assignment (Identifier \"y\") _ (Integer 2))])"
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn spans() {
+        let file = source("x = 1\nwhile a +\\\n");
+        let error = parse(&file).unwrap_err();
+        assert_eq!(file.location(error.span().start()), LineColumn::new(2, 9));
+
+        let file = source("if x:\n    y = f(1,\n     2)\n");
+        let node = parse(&file).unwrap();
+        let statement = match node.node_type() {
+            NodeType::Block(statements) => &statements[0],
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            file.location(statement.span().start()),
+            LineColumn::new(1, 0)
+        );
+        assert_eq!(file.location(statement.span().end()), LineColumn::new(3, 7));
+        assert_eq!(file.slice(statement.span()), Some(&file.text()[..26]));
+        if let NodeType::If { block, .. } = statement.node_type() {
+            assert_eq!(file.location(block.span().start()), LineColumn::new(2, 4));
+        } else {
+            panic!("expected an if statement");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = parse(&source("f(x) = 3\n")).unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::InvalidAssignmentTarget);
+        let error = parse(&source("if x\n    y\n")).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ParseErrorKind::UnexpectedToken {
+                found: TokenKind::Newline,
+                expected: "`:`".to_string()
+            }
+        );
+        assert_eq!(error.span().start(), 4);
+    }
+
+    fn parse_all(source: &str) -> (String, Vec<ParseErrorKind>) {
+        let (node, errors) = parse_recovering(&self::source(source));
+        let errors = errors.into_iter().map(|e| e.kind().clone()).collect();
+        (to_sexpr(&node, false), errors)
+    }
+
+    #[test]
+    fn recovers_at_statements() {
+        let source = "\
+a = )
+b = 1
+while x y:
+    c = 2
+    f(,)
+    d = 3
+e = $
+";
+        let (tree, errors) = parse_all(source);
+        assert_eq!(
+            tree,
+            "(Block [(Error) (Assignment (Identifier \"b\") _ (Integer 1)) (Error) (Assignment \
+             (Identifier \"e\") _ (Error))])"
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[2],
+            ParseErrorKind::Lex(LexErrorKind::UnexpectedCharacter('$'))
+        );
+
+        let source = "if x:\n    a = )\n    b = 2\n";
+        let (tree, errors) = parse_all(source);
+        assert_eq!(
+            tree,
+            "(Block [(If (Identifier \"x\") (Block [(Error) (Assignment (Identifier \"b\") _ \
+             (Integer 2))]) _)])"
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn unclosed_brackets() {
+        let source = "\
+def f(:
+    return 1
+x = g(1, 2
+def h():
+    return [3,
+        4]
+z = ]
+";
+        let (tree, errors) = parse_all(source);
+        assert_eq!(
+            tree,
+            "(Block [(Error) (Error) (Function (Identifier \"h\") [] _ (Block [(Return (List \
+             [(Integer 3) (Integer 4)]))])) (Error)])"
+        );
+        assert_eq!(
+            errors,
+            [
+                ParseErrorKind::UnexpectedToken {
+                    found: TokenKind::Colon,
+                    expected: "an identifier".to_string()
+                },
+                ParseErrorKind::UnclosedBracket(TokenKind::LeftParen),
+                ParseErrorKind::UnexpectedToken {
+                    found: TokenKind::RightBracket,
+                    expected: "an expression".to_string()
+                },
+            ]
+        );
+
+        let (sources, file) = source_map(source);
+        let (_, errors) = parse_recovering(sources.get(file).unwrap());
+        assert_eq!(
+            errors[1].diagnostic().render(&sources, false),
+            "error: unclosed `(`\n \
+             --> test.basil:3:6\n  \
+               |\n\
+             3 | x = g(1, 2\n  \
+               |      ^ this bracket is never closed\n"
+        );
+
+        // a dedent ends the brackets too, and so does the end of the file
+        let (tree, errors) = parse_all("if x:\n    y = {1: [2\nz = (3\n");
+        assert_eq!(
+            tree,
+            "(Block [(If (Identifier \"x\") (Block [(Error)]) _) (Error)])"
+        );
+        assert_eq!(
+            errors,
+            [
+                ParseErrorKind::UnclosedBracket(TokenKind::LeftBracket),
+                ParseErrorKind::UnclosedBracket(TokenKind::LeftParen),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_nodes() {
+        let (tree, errors) = parse_all("x =\nif y\n    z\nwhile w:\nv\n");
+        assert_eq!(
+            tree,
+            "(Block [(Assignment (Identifier \"x\") _ (Error)) (If (Identifier \"y\") (Block \
+             [(Identifier \"z\")]) _) (While (Identifier \"w\") (Block [(Error)]) _) \
+             (Identifier \"v\")])"
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ParseErrorKind::UnexpectedToken {
+                    found: TokenKind::Newline,
+                    expected: "an expression".to_string()
+                },
+                ParseErrorKind::UnexpectedToken {
+                    found: TokenKind::Newline,
+                    expected: "`:`".to_string()
+                },
+                ParseErrorKind::UnexpectedToken {
+                    found: TokenKind::Identifier("v".to_string()),
+                    expected: "an indented block".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        let (sources, file) = source_map("x = 1\n\nx.y = )\n");
+        let error = parse(sources.get(file).unwrap()).unwrap_err();
+        let diagnostic = error.diagnostic();
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: expected an expression, found `)`\n \
+             --> test.basil:3:7\n  \
+               |\n\
+             3 | x.y = )\n  \
+               |       ^ expected an expression\n"
+        );
+
+        let diagnostic = diagnostic
+            .with_label(Span::new(file, 0, 5), "x is assigned here")
+            .with_note("a note")
+            .with_help("a \"help\"");
+        assert_eq!(
+            diagnostic.render(&sources, false),
+            "error: expected an expression, found `)`\n \
+             --> test.basil:3:7\n  \
+               |\n\
+             1 | x = 1\n  \
+               | ----- x is assigned here\n\
+             ...\n\
+             3 | x.y = )\n  \
+               |       ^ expected an expression\n  \
+               |\n  \
+               = note: a note\n  \
+               = help: a \"help\"\n"
+        );
+        assert!(diagnostic
+            .render(&sources, true)
+            .contains("\u{1b}[31m^ expected an expression\u{1b}[0m"));
+        assert_eq!(
+            diagnostic.to_json(&sources),
+            "{\"severity\":\"error\",\"message\":\"expected an expression, found `)`\",\"spans\":[\
+             {\"file\":\"test.basil\",\"line_start\":3,\"column_start\":7,\"line_end\":3,\"column_end\":8,\
+             \"byte_start\":13,\"byte_end\":14,\"primary\":true,\"label\":\"expected an expression\"},\
+             {\"file\":\"test.basil\",\"line_start\":1,\"column_start\":1,\"line_end\":1,\"column_end\":6,\
+             \"byte_start\":0,\"byte_end\":5,\"primary\":false,\"label\":\"x is assigned here\"}],\
+             \"notes\":[\"a note\"],\"help\":[\"a \\\"help\\\"\"]}"
+        );
+    }
+}
@@ -0,0 +1,1014 @@
+//! Works out where the variable behind every name is defined, and finds the variables that
+//! functions capture from the functions they're defined in.
+//!
+//! A name that's assigned to anywhere in a function is local to it, like its parameters, and any
+//! other name it uses is free. A free name that's local to an enclosing function is captured when
+//! the function is created. Captures are by reference, so the closure sees the assignments that
+//! the enclosing function makes later on, and assigning to a captured name rebinds the variable
+//! of the enclosing function instead of defining a local. Inside of a method, free names can also be members of
+//! its class. Any other name is a global, which is looked up when the code runs, so it has to be
+//! assigned somewhere in the module or be defined by the host.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use basil_core::code_block::CodeBlock;
+use basil_core::diagnostic::Diagnostic;
+use basil_core::expression::{
+    Atom, ComprehensionClause, ComprehensionKind, Expression, ExpressionTail, NameScope,
+};
+use basil_core::function::Function;
+use basil_core::pattern::Pattern;
+use basil_core::primitive::Primitive;
+use basil_core::span::{Span, WithSpan};
+use basil_core::statements::Statement;
+
+/// Fills in the [scope](NameScope) of every identifier in a module, along with the
+/// [free](Function::free_variables) and [cell](Function::cell_variables) variables of every
+/// function in it
+pub fn resolve(module: &mut CodeBlock) {
+    let mut used = HashSet::new();
+    Resolver::new(module).block(module, &mut used);
+}
+
+/// [Resolves](resolve) a module, and reports every global that it uses without defining it, along
+/// with every `break` and `continue` outside of a loop. `globals` tells whether the host defines
+/// a name, as it does for builtins.
+pub fn check_names(
+    module: &mut WithSpan<CodeBlock>,
+    globals: &dyn Fn(&str) -> bool,
+) -> Result<(), Vec<ResolveError>> {
+    let mut resolver = Resolver::new(module.get_object());
+    resolver.globals = Some(globals);
+    resolver.span = module.get_span().clone();
+    resolver.block(module.get_object_mut(), &mut HashSet::new());
+    if resolver.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+struct Resolver<'g> {
+    /// The functions and comprehensions that enclose the code being resolved, innermost last
+    scopes: Vec<Scope>,
+    /// The names that the module assigns to
+    module: HashSet<String>,
+    /// The members of every class in the module, by name
+    classes: HashMap<String, ClassScope>,
+    /// The class whose methods are being resolved
+    class: Option<ClassScope>,
+    /// The names the host defines. Undefined names are only reported if it's set.
+    globals: Option<&'g dyn Fn(&str) -> bool>,
+    /// Whether the identifier being resolved is the object of a member or index assignment
+    assigning: bool,
+    /// How many loops enclose the statement being resolved, inside of the innermost function
+    loops: usize,
+    /// The span of the statement being resolved
+    span: Span,
+    errors: Vec<ResolveError>,
+}
+
+#[derive(Default)]
+struct Scope {
+    locals: HashSet<String>,
+    /// The locals that a function defined inside of this scope captures
+    cells: BTreeSet<String>,
+    /// Whether this is the scope of a function, rather than of a comprehension
+    function: bool,
+}
+
+#[derive(Clone, Default)]
+struct ClassScope {
+    /// The names defined by the class and the parents that are known
+    members: HashSet<String>,
+    /// Whether the class has parents that aren't classes of the module, so that it can have
+    /// members that aren't known
+    open: bool,
+}
+
+impl<'g> Resolver<'g> {
+    fn new(module: &CodeBlock) -> Self {
+        let mut names = HashSet::new();
+        bound_names(module, &mut names);
+        Resolver {
+            scopes: vec![],
+            module: names,
+            classes: HashMap::new(),
+            class: None,
+            globals: None,
+            assigning: false,
+            loops: 0,
+            span: Span::native(),
+            errors: vec![],
+        }
+    }
+
+    fn block(&mut self, block: &mut CodeBlock, used: &mut HashSet<String>) {
+        let outer = self.span.clone();
+        for statement in block.statements_mut() {
+            self.span = statement.get_span().clone();
+            self.statement(statement.get_object_mut(), used);
+        }
+        self.span = outer;
+    }
+
+    fn statement(&mut self, statement: &mut Statement, used: &mut HashSet<String>) {
+        match statement {
+            Statement::Assignment(target, value) => {
+                self.target(target, used);
+                self.expression(value, used);
+            }
+            Statement::If {
+                condition,
+                block,
+                elifs,
+                r#else,
+            } => {
+                self.expression(condition, used);
+                self.block(block, used);
+                for (condition, block) in elifs {
+                    self.expression(condition, used);
+                    self.block(block, used);
+                }
+                if let Some(block) = r#else {
+                    self.block(block, used);
+                }
+            }
+            Statement::While {
+                condition,
+                block,
+                r#else,
+            } => {
+                self.expression(condition, used);
+                self.loop_block(block, r#else, used);
+            }
+            Statement::For {
+                iterable,
+                block,
+                r#else,
+                ..
+            } => {
+                self.expression(iterable, used);
+                self.loop_block(block, r#else, used);
+            }
+            Statement::Break if self.loops == 0 => {
+                self.error(ResolveErrorKind::BreakOutsideLoop);
+            }
+            Statement::Continue if self.loops == 0 => {
+                self.error(ResolveErrorKind::ContinueOutsideLoop);
+            }
+            Statement::Break | Statement::Continue => {}
+            Statement::Match { subject, cases } => {
+                self.expression(subject, used);
+                for case in cases {
+                    self.pattern(case.pattern_mut(), used);
+                    if let Some(guard) = case.guard_mut() {
+                        self.expression(guard, used);
+                    }
+                    self.block(case.block_mut(), used);
+                }
+            }
+            Statement::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                self.block(block, used);
+                for handler in handlers {
+                    if let Some(class) = handler.class_mut() {
+                        self.expression(class, used);
+                    }
+                    self.block(handler.block_mut(), used);
+                }
+                for block in r#else.iter_mut().chain(finally) {
+                    self.block(block, used);
+                }
+            }
+            Statement::Expression(value) | Statement::Return(value) | Statement::Raise(value) => {
+                self.expression(value, used)
+            }
+            Statement::Class {
+                name,
+                parents,
+                definitions,
+                decorators,
+            } => {
+                // decorators run where the class is defined, like its parents
+                for (_, expressions) in decorators.iter_mut() {
+                    for decorator in expressions {
+                        self.expression(decorator, used);
+                    }
+                }
+                let mut class = ClassScope::default();
+                for parent in parents.iter_mut() {
+                    self.expression(parent, used);
+                    let known = match (parent.head(), parent.tail()) {
+                        (Atom::Identifier(parent, ..), None) => self.classes.get(parent),
+                        _ => None,
+                    };
+                    match known {
+                        Some(known) => {
+                            class.members.extend(known.members.iter().cloned());
+                            class.open |= known.open;
+                        }
+                        None => class.open = true,
+                    }
+                }
+                class
+                    .members
+                    .extend(definitions.iter().map(|(name, _)| name.clone()));
+                self.classes.insert(name.clone(), class.clone());
+
+                // methods are shared by every object of the class, so they can't capture anything
+                let scopes = std::mem::take(&mut self.scopes);
+                let outer = self.class.replace(class);
+                for (_, definition) in definitions {
+                    if let Primitive::Function(function) = &mut **definition {
+                        self.function(function.get_object_mut());
+                    }
+                }
+                self.class = outer;
+                self.scopes = scopes;
+            }
+            Statement::Decorated {
+                decorators,
+                definition,
+                ..
+            } => {
+                for decorator in decorators {
+                    self.expression(decorator, used);
+                }
+                self.statement(definition, used);
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &mut Pattern, used: &mut HashSet<String>) {
+        match pattern {
+            Pattern::Wildcard | Pattern::Capture(_) => {}
+            Pattern::Value(value) => self.expression(value, used),
+            Pattern::List(patterns) => {
+                for pattern in patterns {
+                    self.pattern(pattern, used);
+                }
+            }
+            Pattern::Dictionary(entries) => {
+                for (key, pattern) in entries {
+                    self.expression(key, used);
+                    self.pattern(pattern, used);
+                }
+            }
+            Pattern::Class { class, members } => {
+                self.expression(class, used);
+                for (_, pattern) in members {
+                    self.pattern(pattern, used);
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &mut Expression, used: &mut HashSet<String>) {
+        self.atom(expression.head_mut(), used);
+        self.assigning = false;
+        match expression.tail_mut() {
+            None | Some(ExpressionTail::GetMember(_)) => {}
+            Some(ExpressionTail::CallMethod { positional, named }) => {
+                for argument in positional.iter_mut().chain(named.values_mut()) {
+                    self.expression(argument, used);
+                }
+            }
+            Some(ExpressionTail::Index(value))
+            | Some(ExpressionTail::BinaryOperation { rhs: value, .. }) => {
+                self.expression(value, used)
+            }
+        }
+    }
+
+    fn atom(&mut self, atom: &mut Atom, used: &mut HashSet<String>) {
+        match atom {
+            Atom::Identifier(name, scope, span) => {
+                *scope = self.classify(name, span);
+                used.insert(name.clone());
+            }
+            Atom::Variable(_) => {}
+            Atom::Expression(value)
+            | Atom::UnaryOperation { value, .. }
+            | Atom::Str(value)
+            | Atom::Repr(value) => self.expression(value, used),
+            Atom::List(elements) | Atom::Set(elements) => {
+                for element in elements {
+                    self.expression(element, used);
+                }
+            }
+            Atom::Dictionary(entries) => {
+                for (key, value) in entries {
+                    self.expression(key, used);
+                    self.expression(value, used);
+                }
+            }
+            Atom::Comprehension { kind, clauses } => self.comprehension(kind, clauses, used),
+            Atom::Function(function) => {
+                let free = self.function(function.get_object_mut());
+                used.extend(free);
+            }
+        }
+    }
+
+    /// Resolves a comprehension, whose loop variables are local to it
+    fn comprehension(
+        &mut self,
+        kind: &mut ComprehensionKind,
+        clauses: &mut [ComprehensionClause],
+        used: &mut HashSet<String>,
+    ) {
+        let locals = clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                ComprehensionClause::For { variable, .. } => Some(variable.clone()),
+                ComprehensionClause::If(_) => None,
+            })
+            .collect();
+        self.scopes.push(Scope {
+            locals,
+            ..Scope::default()
+        });
+        self.assigning = false;
+        let mut inner = HashSet::new();
+        for clause in clauses.iter_mut() {
+            match clause {
+                ComprehensionClause::For { iterable, .. } => self.expression(iterable, &mut inner),
+                ComprehensionClause::If(condition) => self.expression(condition, &mut inner),
+            }
+        }
+        match kind {
+            ComprehensionKind::List(element) | ComprehensionKind::Set(element) => {
+                self.expression(element, &mut inner)
+            }
+            ComprehensionKind::Dictionary(key, value) => {
+                self.expression(key, &mut inner);
+                self.expression(value, &mut inner);
+            }
+        }
+        let scope = self.scopes.pop().unwrap();
+        used.extend(
+            inner
+                .into_iter()
+                .filter(|name| !scope.locals.contains(name)),
+        );
+    }
+
+    /// Resolves the target of an assignment, which can be a list of targets to unpack into
+    fn target(&mut self, target: &mut Expression, used: &mut HashSet<String>) {
+        if target.tail().is_none() {
+            if let Atom::List(targets) = target.head_mut() {
+                for target in targets {
+                    self.target(target, used);
+                }
+                return;
+            }
+        }
+        // the object of `a.b = c` or `a[b] = c` has to exist already
+        self.assigning = target.tail().is_some();
+        self.expression(target, used);
+    }
+
+    /// Resolves the body of a loop, followed by its `else` block, which isn't part of the loop
+    fn loop_block(
+        &mut self,
+        block: &mut CodeBlock,
+        r#else: &mut Option<CodeBlock>,
+        used: &mut HashSet<String>,
+    ) {
+        self.loops += 1;
+        self.block(block, used);
+        self.loops -= 1;
+        if let Some(r#else) = r#else {
+            self.block(r#else, used);
+        }
+    }
+
+    /// Resolves a function, returning the names it uses that aren't local to it
+    fn function(&mut self, function: &mut Function) -> HashSet<String> {
+        let mut assigned = HashSet::new();
+        bound_names(function.code_block().get_object(), &mut assigned);
+        let mut locals: HashSet<String> = assigned
+            .into_iter()
+            .filter(|name| !self.scopes.iter().any(|scope| scope.locals.contains(name)))
+            .collect();
+        locals.extend(function.positional_arguments().iter().cloned());
+        locals.extend(
+            function
+                .keyword_arguments()
+                .iter()
+                .map(|(name, _)| name.clone()),
+        );
+
+        self.scopes.push(Scope {
+            locals,
+            function: true,
+            ..Scope::default()
+        });
+        self.assigning = false;
+        // loops outside of the function can't be left from inside of it
+        let loops = std::mem::take(&mut self.loops);
+        let mut used = HashSet::new();
+        self.block(function.code_block_mut().get_object_mut(), &mut used);
+        self.loops = loops;
+        let Scope { locals, cells, .. } = self.scopes.pop().unwrap();
+        function.set_cell_variables(cells.into_iter().collect());
+
+        let free: HashSet<String> = used
+            .into_iter()
+            .filter(|name| !locals.contains(name))
+            .collect();
+        let mut captured = BTreeSet::new();
+        for name in &free {
+            // the innermost scope that defines the name is the one that owns it
+            if let Some(owner) = self
+                .scopes
+                .iter_mut()
+                .rev()
+                .find(|scope| scope.locals.contains(name))
+            {
+                owner.cells.insert(name.clone());
+                captured.insert(name.clone());
+            }
+        }
+        function.set_free_variables(captured.into_iter().collect());
+        free
+    }
+}
+
+impl Resolver<'_> {
+    /// Works out where a name is defined, reporting it at `span` if it isn't defined anywhere
+    fn classify(&mut self, name: &str, span: &Span) -> NameScope {
+        let mut enclosing = false;
+        for scope in self.scopes.iter().rev() {
+            if scope.locals.contains(name) {
+                return if enclosing {
+                    NameScope::Captured
+                } else {
+                    NameScope::Local
+                };
+            }
+            enclosing |= scope.function;
+        }
+
+        let global = self.module.contains(name) || self.globals.is_none_or(|host| host(name));
+        if let Some(class) = &self.class {
+            // methods don't declare `this`, it's defined next to the members of the class when
+            // they're called
+            if name == "this" || class.members.contains(name) || (class.open && !global) {
+                return NameScope::Class;
+            }
+        }
+        if !global {
+            let name = name.to_string();
+            let kind = match self.assigning {
+                true => ResolveErrorKind::AssignmentToUndefined(name),
+                false => ResolveErrorKind::UndefinedName(name),
+            };
+            self.error_at(kind, span.clone());
+        }
+        NameScope::Global
+    }
+
+    /// Reports an error at the statement being resolved
+    fn error(&mut self, kind: ResolveErrorKind) {
+        self.error_at(kind, self.span.clone());
+    }
+
+    /// Reports an error at a span, unless it was just reported
+    fn error_at(&mut self, kind: ResolveErrorKind, span: Span) {
+        let error = ResolveError::new(kind, span);
+        if self.errors.last() != Some(&error) {
+            self.errors.push(error);
+        }
+    }
+}
+
+/// Collects the names that an assignment target binds, including the names it unpacks into
+fn target_names(target: &Expression, names: &mut HashSet<String>) {
+    match (target.head(), target.tail()) {
+        (Atom::Identifier(name, ..), None) => {
+            names.insert(name.clone());
+        }
+        (Atom::List(targets), None) => {
+            for target in targets {
+                target_names(target, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collects the names that a block assigns to, without looking inside of the functions and
+/// comprehensions in it
+fn bound_names(block: &CodeBlock, names: &mut HashSet<String>) {
+    for statement in block.statements() {
+        match statement.get_object() {
+            Statement::Assignment(target, _) => target_names(target, names),
+            Statement::If {
+                block,
+                elifs,
+                r#else,
+                ..
+            } => {
+                bound_names(block, names);
+                for (_, block) in elifs {
+                    bound_names(block, names);
+                }
+                if let Some(block) = r#else {
+                    bound_names(block, names);
+                }
+            }
+            Statement::While { block, r#else, .. } => {
+                bound_names(block, names);
+                if let Some(block) = r#else {
+                    bound_names(block, names);
+                }
+            }
+            Statement::For {
+                variable,
+                block,
+                r#else,
+                ..
+            } => {
+                names.insert(variable.clone());
+                bound_names(block, names);
+                if let Some(block) = r#else {
+                    bound_names(block, names);
+                }
+            }
+            Statement::Match { cases, .. } => {
+                for case in cases {
+                    pattern_names(case.pattern(), names);
+                    bound_names(case.block(), names);
+                }
+            }
+            Statement::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                bound_names(block, names);
+                for handler in handlers {
+                    names.extend(handler.name().cloned());
+                    bound_names(handler.block(), names);
+                }
+                for block in r#else.iter().chain(finally) {
+                    bound_names(block, names);
+                }
+            }
+            Statement::Class { name, .. } | Statement::Decorated { name, .. } => {
+                names.insert(name.clone());
+            }
+            Statement::Expression(_)
+            | Statement::Return(_)
+            | Statement::Raise(_)
+            | Statement::Break
+            | Statement::Continue => {}
+        }
+    }
+}
+
+/// Collects the names that a pattern binds
+fn pattern_names(pattern: &Pattern, names: &mut HashSet<String>) {
+    match pattern {
+        Pattern::Capture(name) => {
+            names.insert(name.clone());
+        }
+        Pattern::Wildcard | Pattern::Value(_) => {}
+        Pattern::List(patterns) => {
+            for pattern in patterns {
+                pattern_names(pattern, names);
+            }
+        }
+        Pattern::Dictionary(entries) => {
+            for (_, pattern) in entries {
+                pattern_names(pattern, names);
+            }
+        }
+        Pattern::Class { members, .. } => {
+            for (_, pattern) in members {
+                pattern_names(pattern, names);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    kind: ResolveErrorKind,
+    span: Span,
+}
+
+impl ResolveError {
+    pub fn new(kind: ResolveErrorKind, span: Span) -> Self {
+        ResolveError { kind, span }
+    }
+
+    pub fn kind(&self) -> &ResolveErrorKind {
+        &self.kind
+    }
+
+    /// The span of the name, or of the `break` or `continue` statement
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.kind.to_string(), self.span.clone());
+        match &self.kind {
+            ResolveErrorKind::AssignmentToUndefined(name) => diagnostic.with_help(format!(
+                "assign a value to `{}` first, such as `{} = {{}}`",
+                name, name
+            )),
+            _ => diagnostic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveErrorKind {
+    /// A name that isn't a variable, a member of the class or a global
+    UndefinedName(String),
+    /// An assignment to a member or an element of a variable that isn't defined, as in `a.b = 1`
+    AssignmentToUndefined(String),
+    /// A `break` that isn't inside of a loop in the same function
+    BreakOutsideLoop,
+    /// A `continue` that isn't inside of a loop in the same function
+    ContinueOutsideLoop,
+}
+
+impl Display for ResolveErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveErrorKind::UndefinedName(name) => write!(f, "`{}` is not defined", name),
+            ResolveErrorKind::AssignmentToUndefined(name) => {
+                write!(
+                    f,
+                    "can't assign to a member of `{}`, which is not defined",
+                    name
+                )
+            }
+            ResolveErrorKind::BreakOutsideLoop => write!(f, "`break` outside of a loop"),
+            ResolveErrorKind::ContinueOutsideLoop => write!(f, "`continue` outside of a loop"),
+        }
+    }
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::lower;
+    use crate::parser::parse;
+    use crate::test_util::source;
+
+    /// Lowers a module and finds the function that's assigned to a name, searching through the
+    /// bodies of the functions in it
+    fn function(source: &str, path: &[&str]) -> Function {
+        let module = lower(&parse(&self::source(source)).unwrap()).unwrap();
+        let mut block = module.get_object().clone();
+        let mut found = None;
+        for name in path {
+            let function = block
+                .statements()
+                .iter()
+                .find_map(|statement| match statement.get_object() {
+                    Statement::Assignment(target, value) => match (target.head(), value.head()) {
+                        (Atom::Identifier(id, ..), Atom::Function(function)) if id == name => {
+                            Some(function.get_object().clone())
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .unwrap_or_else(|| panic!("no function named {}", name));
+            block = function.code_block().get_object().clone();
+            found = Some(function);
+        }
+        found.unwrap()
+    }
+
+    /// Collects the identifiers in an expression made of names and binary operations
+    fn names(expression: &Expression, names: &mut Vec<(String, NameScope)>) {
+        match expression.head() {
+            Atom::Identifier(name, scope, _) => names.push((name.clone(), *scope)),
+            Atom::Expression(inner) => self::names(inner, names),
+            _ => {}
+        }
+        if let Some(ExpressionTail::BinaryOperation { rhs, .. }) = expression.tail() {
+            self::names(rhs, names);
+        }
+    }
+
+    /// The scopes of the names in the first `return` of a function
+    fn returned(function: &Function) -> Vec<(String, NameScope)> {
+        let mut found = vec![];
+        for statement in function.code_block().get_object().statements() {
+            if let Statement::Return(value) = statement.get_object() {
+                names(value, &mut found);
+                break;
+            }
+        }
+        found
+    }
+
+    /// Runs [check_names] on a module, with `print` as the only name the host defines
+    fn check(source: &str) -> Result<(), Vec<ResolveError>> {
+        let mut module = lower(&parse(&self::source(source)).unwrap()).unwrap();
+        check_names(&mut module, &|name| name == "print")
+    }
+
+    fn undefined(source: &str) -> Vec<ResolveErrorKind> {
+        match check(source) {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|error| error.kind).collect(),
+        }
+    }
+
+    #[test]
+    fn scopes() {
+        let source = "\
+x = 1
+def outer(a):
+    def inner(b):
+        return a + b + x + print
+    return inner
+";
+        use NameScope::*;
+        let inner = function(source, &["outer", "inner"]);
+        let expected = [
+            ("a", Captured),
+            ("b", Local),
+            ("x", Global),
+            ("print", Global),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(name, scope)| (name.to_string(), *scope))
+            .collect();
+        assert_eq!(returned(&inner), expected);
+        assert_eq!(
+            returned(&function(source, &["outer"])),
+            [("inner".to_string(), Local)]
+        );
+    }
+
+    #[test]
+    fn class_members() {
+        let source = "\
+class Base:
+    def base():
+        return 1
+class Derived(Base):
+    def method(a):
+        return a + this + base + method
+class Open(print):
+    def method():
+        return anything
+";
+        assert_eq!(check(source), Ok(()));
+
+        let module = lower(&parse(&self::source(source)).unwrap()).unwrap();
+        let scopes: Vec<_> = module
+            .get_object()
+            .statements()
+            .iter()
+            .filter_map(|statement| match statement.get_object() {
+                Statement::Class {
+                    name, definitions, ..
+                } if name == "Derived" => Some(definitions),
+                _ => None,
+            })
+            .flat_map(|definitions| definitions.iter())
+            .filter_map(|(_, definition)| match &**definition {
+                Primitive::Function(function) => Some(returned(function.get_object())),
+                _ => None,
+            })
+            .collect();
+        use NameScope::*;
+        assert_eq!(
+            scopes,
+            [vec![
+                ("a".to_string(), Local),
+                ("this".to_string(), Class),
+                ("base".to_string(), Class),
+                ("method".to_string(), Class),
+            ]]
+        );
+    }
+
+    #[test]
+    fn undefined_names() {
+        assert_eq!(undefined("x = 1\nprint(x)\n"), []);
+        // globals can be assigned after the functions that use them are defined
+        assert_eq!(undefined("def f():\n    return y\ny = 1\n"), []);
+        assert_eq!(
+            undefined("print(x)\ndef f(a):\n    return a + b\n"),
+            [
+                ResolveErrorKind::UndefinedName("x".to_string()),
+                ResolveErrorKind::UndefinedName("b".to_string()),
+            ]
+        );
+        assert_eq!(
+            undefined("point.x = 1\nitems[0] = point\n"),
+            [
+                ResolveErrorKind::AssignmentToUndefined("point".to_string()),
+                ResolveErrorKind::AssignmentToUndefined("items".to_string()),
+                ResolveErrorKind::UndefinedName("point".to_string()),
+            ]
+        );
+        // only the object of the assignment has to exist, not the members of it
+        assert_eq!(
+            undefined("p = {}\np[key] = 1\n"),
+            [ResolveErrorKind::UndefinedName("key".to_string())]
+        );
+        assert_eq!(
+            undefined("class A:\n    def f():\n        return g\n"),
+            [ResolveErrorKind::UndefinedName("g".to_string())]
+        );
+        assert_eq!(
+            undefined("[y for y in [1]]\nprint(y)\n"),
+            [ResolveErrorKind::UndefinedName("y".to_string())]
+        );
+    }
+
+    #[test]
+    fn unpacking() {
+        assert_eq!(undefined("a, [b, c] = 1, [2, 3]\nprint(a + b + c)\n"), []);
+        assert_eq!(
+            undefined("p = {}\np.x, q.y = 1, 2\n"),
+            [ResolveErrorKind::AssignmentToUndefined("q".to_string())]
+        );
+        let f = function("def f(pair):\n    a, b = pair\n    return a\n", &["f"]);
+        assert_eq!(returned(&f), [("a".to_string(), NameScope::Local)]);
+    }
+
+    #[test]
+    fn loops() {
+        let source = "\
+for x in [1, 2]:
+    if x:
+        continue
+    def f():
+        break
+    while x:
+        break
+    else:
+        continue
+else:
+    break
+continue
+";
+        assert_eq!(
+            undefined(source),
+            [
+                ResolveErrorKind::BreakOutsideLoop,
+                ResolveErrorKind::BreakOutsideLoop,
+                ResolveErrorKind::ContinueOutsideLoop,
+            ]
+        );
+        let errors = check(source).unwrap_err();
+        let start = source.find("break").unwrap();
+        assert_eq!(errors[0].span().start(), start);
+        assert_eq!(errors[0].span().end(), start + "break".len());
+        assert_eq!(errors[2].to_string(), "`continue` outside of a loop");
+    }
+
+    #[test]
+    fn exception_handlers() {
+        let source = "\
+class Oops:
+    pass
+def f():
+    try:
+        pass
+    except Oops as error:
+        print(error)
+    except Missing:
+        pass
+    finally:
+        print(cleanup)
+    return error
+";
+        assert_eq!(
+            undefined(source),
+            [
+                ResolveErrorKind::UndefinedName("Missing".to_string()),
+                ResolveErrorKind::UndefinedName("cleanup".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        let errors = check("a = 1\nb = a + c\n").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "`c` is not defined");
+        let diagnostic = errors[0].diagnostic();
+        assert_eq!(diagnostic.message(), "`c` is not defined");
+        assert_eq!(errors[0].span().start(), 14);
+        assert_eq!(errors[0].span().end(), 15);
+
+        // each use is reported where the name is written, rather than at the whole statement
+        let source = "value = 1
+def f():
+    return value + missing
+record.x = other
+";
+        let errors = check(source).unwrap_err();
+        let spans: Vec<_> = errors
+            .iter()
+            .map(|error| (error.span().start(), error.span().end()))
+            .collect();
+        let at = |name: &str| {
+            let start = source.find(name).unwrap();
+            (start, start + name.len())
+        };
+        assert_eq!(spans, [at("missing"), at("record"), at("other")]);
+        assert_eq!(
+            errors[1].to_string(),
+            "can't assign to a member of `record`, which is not defined"
+        );
+    }
+
+    #[test]
+    fn captures() {
+        let source = "\
+def outer(a):
+    b = 1
+    def middle():
+        def inner(c):
+            return a + b + c + d + inner
+        return inner
+    d = [b for b in range(2)]
+    return middle
+";
+        let outer = function(source, &["outer"]);
+        assert!(outer.free_variables().is_empty());
+        assert_eq!(outer.cell_variables(), &["a", "b", "d"]);
+
+        let middle = function(source, &["outer", "middle"]);
+        assert_eq!(middle.free_variables(), &["a", "b", "d"]);
+        assert_eq!(middle.cell_variables(), &["inner"]);
+
+        let inner = function(source, &["outer", "middle", "inner"]);
+        assert_eq!(inner.free_variables(), &["a", "b", "d", "inner"]);
+        assert!(inner.cell_variables().is_empty());
+    }
+
+    #[test]
+    fn assigning_to_captures() {
+        let source = "\
+x = 1
+def counter():
+    count = 0
+    def increment():
+        count = count + 1
+        x = 2
+        return count + x
+    def shadow(count):
+        return count
+    return increment
+";
+        assert_eq!(check(source), Ok(()));
+        use NameScope::*;
+        let counter = function(source, &["counter"]);
+        assert_eq!(counter.cell_variables(), &["count"]);
+
+        let increment = function(source, &["counter", "increment"]);
+        assert_eq!(increment.free_variables(), &["count"]);
+        let expected = [("count".to_string(), Captured), ("x".to_string(), Local)];
+        assert_eq!(returned(&increment), expected);
+
+        let shadow = function(source, &["counter", "shadow"]);
+        assert!(shadow.free_variables().is_empty());
+        assert_eq!(returned(&shadow), [("count".to_string(), Local)]);
+    }
+
+    #[test]
+    fn globals_and_comprehensions_are_not_captured() {
+        let source = "\
+x = 1
+def f(y):
+    g = lambda: [x + y + z for z in range(y)]
+    return g
+";
+        let f = function(source, &["f"]);
+        assert!(f.free_variables().is_empty());
+        assert_eq!(f.cell_variables(), &["y"]);
+    }
+}
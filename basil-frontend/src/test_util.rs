@@ -0,0 +1,16 @@
+//! Fixtures shared by the test modules of the crate.
+
+use basil_core::source_map::{FileId, SourceFile, SourceMap};
+
+/// Registers a source as `test.basil` in a new source map, so spans in it can be resolved
+pub fn source_map(text: &str) -> (SourceMap, FileId) {
+    let mut sources = SourceMap::new();
+    let file = sources.add_file("test.basil", text);
+    (sources, file)
+}
+
+/// Registers a source as `test.basil`, for the passes that only need the one file
+pub fn source(text: &str) -> SourceFile {
+    let (sources, file) = source_map(text);
+    sources.get(file).unwrap().clone()
+}
@@ -0,0 +1,241 @@
+use basil_ast::operators::Operator;
+use basil_core::span::Span;
+use num_bigint::BigInt;
+use num_rational::Rational;
+use std::fmt::{Display, Formatter};
+
+/// A single token produced by the [Lexer](crate::lexer::Lexer), along with where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, span: Span) -> Self {
+        Token { kind, span }
+    }
+
+    pub fn kind(&self) -> &TokenKind {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn into_kind(self) -> TokenKind {
+        self.kind
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Identifier(String),
+    Keyword(Keyword),
+    Integer(BigInt),
+    Float(Rational),
+    String(String),
+    /// An interpolated string, such as `f"hello {name}"`
+    FormatString(Vec<FormatStringPart>),
+    Operator(Operator),
+    /// `=`
+    Assign,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Colon,
+    Dot,
+    /// `->`
+    Arrow,
+    /// `@`, which starts a decorator
+    At,
+    /// The end of a logical line
+    Newline,
+    /// The start of a block that is indented further than the previous line
+    Indent,
+    /// The end of an indented block
+    Dedent,
+    /// Stands in for source text that couldn't be lexed
+    Error,
+    Eof,
+}
+
+/// A piece of an interpolated string
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatStringPart {
+    /// Text outside of any braces, with its escapes already processed
+    Literal(String, Span),
+    /// An embedded `{expression}`, which is converted with `repr` instead of `str` if it ends with
+    /// `!r`. The tokens end with an [Eof](TokenKind::Eof) token.
+    Expression {
+        tokens: Vec<Token>,
+        repr: bool,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Keyword {
+    Def,
+    Class,
+    If,
+    Elif,
+    Else,
+    While,
+    For,
+    In,
+    Match,
+    Case,
+    Lambda,
+    Break,
+    Continue,
+    Return,
+    Yield,
+    Import,
+    Raise,
+    Try,
+    Except,
+    Finally,
+    As,
+    Pass,
+    True,
+    False,
+    None,
+}
+
+impl Keyword {
+    /// Gets the keyword that this identifier represents, if it is one
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        let keyword = match identifier {
+            "def" => Keyword::Def,
+            "class" => Keyword::Class,
+            "if" => Keyword::If,
+            "elif" => Keyword::Elif,
+            "else" => Keyword::Else,
+            "while" => Keyword::While,
+            "for" => Keyword::For,
+            "in" => Keyword::In,
+            "match" => Keyword::Match,
+            "case" => Keyword::Case,
+            "lambda" => Keyword::Lambda,
+            "break" => Keyword::Break,
+            "continue" => Keyword::Continue,
+            "return" => Keyword::Return,
+            "yield" => Keyword::Yield,
+            "import" => Keyword::Import,
+            "raise" => Keyword::Raise,
+            "try" => Keyword::Try,
+            "except" => Keyword::Except,
+            "finally" => Keyword::Finally,
+            "as" => Keyword::As,
+            "pass" => Keyword::Pass,
+            "True" => Keyword::True,
+            "False" => Keyword::False,
+            "None" => Keyword::None,
+            _ => return None,
+        };
+        Some(keyword)
+    }
+
+    /// Whether the keyword can only start a statement or one of its clauses, so that it's never
+    /// part of an expression
+    pub fn starts_statement(&self) -> bool {
+        !matches!(
+            self,
+            Keyword::If
+                | Keyword::For
+                | Keyword::In
+                | Keyword::Lambda
+                | Keyword::As
+                | Keyword::True
+                | Keyword::False
+                | Keyword::None
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Def => "def",
+            Keyword::Class => "class",
+            Keyword::If => "if",
+            Keyword::Elif => "elif",
+            Keyword::Else => "else",
+            Keyword::While => "while",
+            Keyword::For => "for",
+            Keyword::In => "in",
+            Keyword::Match => "match",
+            Keyword::Case => "case",
+            Keyword::Lambda => "lambda",
+            Keyword::Break => "break",
+            Keyword::Continue => "continue",
+            Keyword::Return => "return",
+            Keyword::Yield => "yield",
+            Keyword::Import => "import",
+            Keyword::Raise => "raise",
+            Keyword::Try => "try",
+            Keyword::Except => "except",
+            Keyword::Finally => "finally",
+            Keyword::As => "as",
+            Keyword::Pass => "pass",
+            Keyword::True => "True",
+            Keyword::False => "False",
+            Keyword::None => "None",
+        }
+    }
+}
+
+/// Gets the source representation of an operator
+pub fn operator_str(op: Operator) -> &'static str {
+    match op {
+        Operator::Eq => "==",
+        Operator::Neq => "!=",
+        Operator::Lt => "<",
+        Operator::Gt => ">",
+        Operator::Lte => "<=",
+        Operator::Gte => ">=",
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Div => "/",
+        Operator::Mul => "*",
+        Operator::Rem => "%",
+        Operator::And => "and",
+        Operator::Or => "or",
+        Operator::Not => "not",
+    }
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Identifier(id) => write!(f, "identifier `{}`", id),
+            TokenKind::Keyword(keyword) => write!(f, "`{}`", keyword.as_str()),
+            TokenKind::Integer(i) => write!(f, "integer `{}`", i),
+            TokenKind::Float(fl) => write!(f, "float `{}`", fl),
+            TokenKind::String(s) => write!(f, "string {:?}", s),
+            TokenKind::FormatString(_) => write!(f, "interpolated string"),
+            TokenKind::Operator(op) => write!(f, "`{}`", operator_str(*op)),
+            TokenKind::Assign => write!(f, "`=`"),
+            TokenKind::LeftParen => write!(f, "`(`"),
+            TokenKind::RightParen => write!(f, "`)`"),
+            TokenKind::LeftBracket => write!(f, "`[`"),
+            TokenKind::RightBracket => write!(f, "`]`"),
+            TokenKind::LeftBrace => write!(f, "`{{`"),
+            TokenKind::RightBrace => write!(f, "`}}`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Colon => write!(f, "`:`"),
+            TokenKind::Dot => write!(f, "`.`"),
+            TokenKind::Arrow => write!(f, "`->`"),
+            TokenKind::At => write!(f, "`@`"),
+            TokenKind::Newline => write!(f, "end of line"),
+            TokenKind::Indent => write!(f, "indent"),
+            TokenKind::Dedent => write!(f, "dedent"),
+            TokenKind::Error => write!(f, "invalid token"),
+            TokenKind::Eof => write!(f, "end of file"),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Attaches the comments and blank lines found by the [Lexer](crate::lexer::Lexer) to the
+//! statements of a parsed tree.
+//!
+//! Trivia before a statement is its leading trivia, and a comment on the same line as the end of
+//! a statement is its trailing comment. For statements with a block the trailing comment is the
+//! one on the first line, after the `:`. Comments after the last statement of a block belong to
+//! the block if they're indented at least as far as its statements, and to whatever comes next
+//! otherwise.
+
+use basil_ast::node::{Node, NodeType};
+use basil_ast::trivia::TriviaPiece;
+use basil_ast::visit::{walk_mut, VisitorMut};
+use basil_core::source_map::SourceFile;
+
+/// Trivia that hasn't been attached to a node yet, in source order, with the byte offsets they
+/// start at
+pub(crate) struct Pieces<'s> {
+    source: &'s SourceFile,
+    pieces: Vec<(usize, TriviaPiece)>,
+    next: usize,
+}
+
+impl<'s> Pieces<'s> {
+    pub fn new(source: &'s SourceFile, pieces: Vec<(usize, TriviaPiece)>) -> Self {
+        Pieces {
+            source,
+            pieces,
+            next: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&(usize, TriviaPiece)> {
+        self.pieces.get(self.next)
+    }
+
+    /// Takes every piece that starts before `location`
+    pub fn take_before(&mut self, location: usize) -> Vec<TriviaPiece> {
+        let start = self.next;
+        while matches!(self.peek(), Some((at, _)) if *at < location) {
+            self.next += 1;
+        }
+        self.pieces[start..self.next]
+            .iter()
+            .map(|(_, piece)| piece.clone())
+            .collect()
+    }
+
+    /// Takes a comment on the same line as `offset`
+    fn take_comment_on(&mut self, offset: usize, bound: Option<usize>) -> Option<String> {
+        let line = self.source.location(offset).line;
+        match self.peek() {
+            Some((at, TriviaPiece::Comment(comment)))
+                if self.source.location(*at).line == line && before(*at, bound) =>
+            {
+                let comment = comment.clone();
+                self.next += 1;
+                Some(comment)
+            }
+            _ => None,
+        }
+    }
+
+    /// Takes the comments indented to at least `column`, along with the blank lines between them
+    pub fn take_dangling(&mut self, column: usize, bound: Option<usize>) -> Vec<TriviaPiece> {
+        let mut end = self.next;
+        for (index, (at, piece)) in self.pieces.iter().enumerate().skip(self.next) {
+            if !before(*at, bound) {
+                break;
+            }
+            match piece {
+                TriviaPiece::BlankLine => {}
+                TriviaPiece::Comment(_) if self.source.location(*at).column >= column => {
+                    end = index + 1
+                }
+                TriviaPiece::Comment(_) => break,
+            }
+        }
+        let taken = self.pieces[self.next..end]
+            .iter()
+            .map(|(_, piece)| piece.clone())
+            .collect();
+        self.next = end;
+        taken
+    }
+}
+
+fn before(location: usize, bound: Option<usize>) -> bool {
+    bound.is_none_or(|bound| location < bound)
+}
+
+/// Attaches all of the trivia in a file to its module
+pub(crate) fn attach_trivia(
+    module: &mut Node,
+    source: &SourceFile,
+    pieces: Vec<(usize, TriviaPiece)>,
+) {
+    let mut pieces = Pieces::new(source, pieces);
+    Attacher {
+        pieces: &mut pieces,
+        bound: None,
+    }
+    .visit_node_mut(module);
+}
+
+/// Attaches trivia to a list of statements, taking nothing at or after `bound`
+pub(crate) fn attach_statements(
+    statements: &mut [Node],
+    pieces: &mut Pieces,
+    bound: Option<usize>,
+) {
+    for index in 0..statements.len() {
+        let next = statements
+            .get(index + 1)
+            .map(|next| next.span().start())
+            .or(bound);
+        let statement = &mut statements[index];
+        let start = statement.span().start();
+        let end = statement.span().end();
+
+        let mut leading = pieces.take_before(start);
+        let trailing = if has_body(statement) {
+            let trailing = pieces.take_comment_on(start, next);
+            Attacher {
+                pieces,
+                bound: next,
+            }
+            .visit_node_mut(statement);
+            trailing
+        } else {
+            // comments inside of the statement are kept in front of it
+            leading.extend(pieces.take_before(end));
+            pieces.take_comment_on(end, next)
+        };
+        let trivia = statement.trivia_mut();
+        trivia.leading = leading;
+        trivia.trailing = trailing;
+    }
+}
+
+/// Attaches trivia to the statements of the blocks inside of a node, taking nothing at or after
+/// `bound`
+struct Attacher<'p, 's> {
+    pieces: &'p mut Pieces<'s>,
+    bound: Option<usize>,
+}
+
+impl VisitorMut for Attacher<'_, '_> {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        let column = match node.node_type_mut() {
+            NodeType::Block(statements)
+            | NodeType::Class {
+                defs: statements, ..
+            }
+            | NodeType::Match {
+                cases: statements, ..
+            } => {
+                attach_statements(statements, self.pieces, self.bound);
+                body_column(self.pieces.source, statements)
+            }
+            NodeType::Decorated { definition, .. } => {
+                // the definition gets the comments between the decorators and its own header line
+                attach_statements(std::slice::from_mut(definition), self.pieces, self.bound);
+                return;
+            }
+            _ => {
+                walk_mut(self, node);
+                return;
+            }
+        };
+        node.trivia_mut().dangling = self.pieces.take_dangling(column, self.bound);
+    }
+}
+
+/// Attaches the comments after a statement that belong to the blocks at its end, after they were
+/// removed with [clear_dangling]
+pub(crate) fn attach_dangling(node: &mut Node, pieces: &mut Pieces, bound: Option<usize>) {
+    let column = match node.node_type_mut() {
+        NodeType::Block(statements)
+        | NodeType::Class {
+            defs: statements, ..
+        }
+        | NodeType::Match {
+            cases: statements, ..
+        } => {
+            if let Some(last) = statements.last_mut() {
+                attach_dangling(last, pieces, bound);
+            }
+            body_column(pieces.source, statements)
+        }
+        _ => {
+            if let Some(last) = node.children_mut().pop() {
+                attach_dangling(last, pieces, bound);
+            }
+            return;
+        }
+    };
+    node.trivia_mut().dangling = pieces.take_dangling(column, bound);
+}
+
+/// Removes the comments attached to the blocks at the end of a statement
+pub(crate) fn clear_dangling(node: &mut Node) {
+    node.trivia_mut().dangling.clear();
+    let last = match node.node_type_mut() {
+        NodeType::Block(statements)
+        | NodeType::Class {
+            defs: statements, ..
+        }
+        | NodeType::Match {
+            cases: statements, ..
+        } => statements.last_mut(),
+        _ => node.children_mut().pop(),
+    };
+    if let Some(last) = last {
+        clear_dangling(last);
+    }
+}
+
+fn body_column(source: &SourceFile, statements: &[Node]) -> usize {
+    statements.first().map_or(0, |statement| {
+        source.location(statement.span().start()).column
+    })
+}
+
+/// Whether a statement ends with a block of statements
+fn has_body(statement: &Node) -> bool {
+    matches!(
+        statement.node_type(),
+        NodeType::If { .. }
+            | NodeType::While { .. }
+            | NodeType::For { .. }
+            | NodeType::Match { .. }
+            | NodeType::MatchCase { .. }
+            | NodeType::Try { .. }
+            | NodeType::Function { .. }
+            | NodeType::Class { .. }
+            | NodeType::Decorated { .. }
+    )
+}
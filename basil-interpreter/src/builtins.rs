@@ -0,0 +1,46 @@
+//! Functions that are defined in the global scope of every program.
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use basil_core::exception::ExpressionResult;
+use basil_core::function::Function;
+use basil_core::primitive::Primitive;
+use basil_core::variable::{IntoVariable, Variable};
+
+/// The name of the class that `__next__` raises once an iterator has no elements left
+pub const STOP_ITERATION: &str = "StopIteration";
+
+/// Every native builtin function
+pub fn functions() -> Vec<Function> {
+    vec![Function::native("range".to_string(), range)]
+}
+
+/// `range(stop)`, `range(start, stop)` or `range(start, stop, step)`
+fn range(arguments: Vec<Variable>, keywords: Vec<(String, Variable)>) -> ExpressionResult {
+    if !keywords.is_empty() {
+        Err("range() doesn't take keyword arguments")?
+    }
+    let mut bounds = vec![];
+    for argument in &arguments {
+        let object = argument.get_object();
+        let object = object.get();
+        match object.as_primitive() {
+            Primitive::Integer(i) if !object.is_class_object() => bounds.push(i.clone()),
+            other => Err(format!("range() takes Integers, not {}", other.type_name()))?,
+        }
+    }
+    let (start, stop, step) = match bounds.as_slice() {
+        [stop] => (BigInt::zero(), stop.clone(), BigInt::from(1)),
+        [start, stop] => (start.clone(), stop.clone(), BigInt::from(1)),
+        [start, stop, step] => (start.clone(), stop.clone(), step.clone()),
+        _ => Err(format!(
+            "range() takes from 1 to 3 arguments, but {} were given",
+            bounds.len()
+        ))?,
+    };
+    if step.is_zero() {
+        Err("range() step can't be zero")?
+    }
+    Ok(Primitive::Range { start, stop, step }.into_variable())
+}
@@ -1,17 +1,9 @@
-use std::borrow::Borrow;
-use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut, Index, IndexMut};
-use std::rc::Rc;
-use std::sync::RwLock;
+use std::ops::{Index, IndexMut};
 
-use petgraph::graph::node_index;
 use petgraph::prelude::*;
 
-use basil_core::class::Class;
 use basil_core::dictionary::Dictionary;
-use basil_core::object::Object;
 use basil_core::primitive::Primitive;
 use basil_core::variable::Variable;
 
@@ -23,6 +15,12 @@ pub struct ContextGraph {
     context_stack: Vec<NodeIndex>,
 }
 
+impl Default for ContextGraph {
+    fn default() -> Self {
+        ContextGraph::new()
+    }
+}
+
 impl ContextGraph {
     pub fn new() -> Self {
         let mut graph = StableGraph::new();
@@ -42,6 +40,17 @@ impl ContextGraph {
         &mut self.context_graph[self.global_context]
     }
 
+    /// The variables that were defined in a context itself, without the ones it can see through
+    /// its parents
+    pub fn context(&self, node: NodeIndex) -> Option<&Context> {
+        self.context_graph.node_weight(node)
+    }
+
+    /// Gets a variable that was defined in the current scope itself, without looking at its parents
+    pub fn local(&self, key: &str) -> Option<&Variable> {
+        self.context_graph[self.current_scope()].get(key)
+    }
+
     pub fn global_id(&self) -> &NodeIndex {
         &self.global_context
     }
@@ -73,14 +82,6 @@ impl ContextGraph {
         self.context_graph.remove_node(node);
     }
 
-    fn peek(&self) -> &Context {
-        let parent = *self
-            .context_stack
-            .last()
-            .expect("The Global context should not have been popped");
-        &self.context_graph[parent]
-    }
-
     fn peek_mut(&mut self) -> &mut Context {
         let parent = *self
             .context_stack
@@ -97,7 +98,7 @@ impl ContextGraph {
     pub fn shift_new_scope(&mut self) -> NodeIndex {
         let new_node = self.context_graph.add_node(Context::default());
         self.context_graph
-            .add_edge(new_node, self.global_context.clone(), ());
+            .add_edge(new_node, self.global_context, ());
         self.context_stack.push(new_node);
         new_node
     }
@@ -106,6 +107,14 @@ impl ContextGraph {
         self.context_stack.push(scope);
     }
 
+    /// Undoes [shift_to_scope](ContextGraph::shift_to_scope) without removing the scope from the
+    /// graph
+    pub fn unshift_scope(&mut self) {
+        self.context_stack
+            .pop()
+            .expect("The Global context should not have been popped");
+    }
+
     /// Adds a new context, with a single
     pub fn add_new_context(&mut self, context: Context) -> NodeIndex {
         let ret = self.context_graph.add_node(context);
@@ -119,10 +128,7 @@ impl ContextGraph {
             return false;
         }
         self.context_graph.add_edge(*child, *parent, ());
-        if let Some(edge) = self
-            .context_graph
-            .find_edge(*child, self.global_context.clone())
-        {
+        if let Some(edge) = self.context_graph.find_edge(*child, self.global_context) {
             self.context_graph.remove_edge(edge);
         }
         true
@@ -132,7 +138,7 @@ impl ContextGraph {
         *self.context_stack.last().unwrap()
     }
 
-    pub fn current_context(&mut self) -> CollectedContext {
+    pub fn current_context(&mut self) -> CollectedContext<'_> {
         /*
         let mut all_nodes = vec![];
         let mut visited = HashSet::new();
@@ -180,6 +186,16 @@ pub struct Context {
     data: HashMap<String, Variable>,
 }
 
+impl Context {
+    pub fn insert(&mut self, key: String, value: Variable) {
+        self.data.insert(key, value);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Variable> {
+        self.data.get(key)
+    }
+}
+
 impl From<&Dictionary> for Context {
     fn from(dict: &Dictionary) -> Self {
         let mut mapping = HashMap::new();
@@ -244,6 +260,13 @@ impl<'a> CollectedContext<'a> {
         self.get_with_index(key).map(|(ret, _)| ret)
     }
 
+    /// Gets a variable without looking at the global scope
+    pub fn get_enclosed(&self, key: &String) -> Option<&Variable> {
+        self.get_with_index(key)
+            .filter(|(_, index)| *index != self.data.global_context)
+            .map(|(ret, _)| ret)
+    }
+
     fn get_with_index(&self, key: &String) -> Option<(&Variable, NodeIndex)> {
         for (node, index) in self.context_order.iter().filter_map(|id| {
             self.data
@@ -262,8 +285,8 @@ impl<'a> CollectedContext<'a> {
         let mut found_index: Option<NodeIndex> = None;
         for node in self.context_order.iter().cloned() {
             if let Some(context) = self.data.context_graph.node_weight(node) {
-                if context.data.get(key).is_some() {
-                    found_index = Some(node.clone());
+                if context.data.contains_key(key) {
+                    found_index = Some(node);
                     break;
                 }
             }
@@ -278,7 +301,7 @@ impl<'a> CollectedContext<'a> {
         }
     }
 
-    pub fn entry(&mut self, key: String) -> Entry {
+    pub fn entry(&mut self, key: String) -> Entry<'_> {
         if let Some((_, var)) = self.get_with_index(&key) {
             return Entry::Occupied(Occupied::new(self.data, var, key));
         }
@@ -367,7 +390,7 @@ impl<'e> Occupied<'e> {
         variable
     }
 
-    pub fn get(mut self) -> &'e mut Variable {
+    pub fn get(self) -> &'e mut Variable {
         let Occupied {
             context_graph,
             node_index,
@@ -389,11 +412,7 @@ pub struct Vacant<'e> {
 }
 
 impl<'e> Vacant<'e> {
-    fn new(context_graph: &'e mut ContextGraph, key: String) -> Self {
-        Vacant { context_graph, key }
-    }
-
-    pub fn insert(mut self, value: Variable) -> &'e mut Variable {
+    pub fn insert(self, value: Variable) -> &'e mut Variable {
         let Vacant { context_graph, key } = self;
         let context = context_graph.peek_mut();
         context.data.insert(key.clone(), value);
@@ -1,32 +1,78 @@
-use std::borrow::Borrow;
 use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
-use std::ops::Deref;
 
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
 use petgraph::graph::NodeIndex;
 
 use basil_core::class::Class;
 use basil_core::code_block::CodeBlock;
+use basil_core::diagnostic::Diagnostic;
 use basil_core::dictionary::Dictionary;
 use basil_core::exception::Exception;
-use basil_core::expression::{Expression, ExpressionTail, Atom};
+use basil_core::expression::{
+    Atom, BinaryOperator, ComprehensionClause, ComprehensionKind, Expression, ExpressionTail,
+    NameScope, UnaryOperator,
+};
 use basil_core::function::Function;
 use basil_core::object::Object;
+use basil_core::pattern::Pattern;
 use basil_core::primitive::Primitive;
+use basil_core::ptr::Ptr;
 use basil_core::span::{Span, WithSpan};
-use basil_core::statements::Statement;
-use basil_core::type_id::TypeId;
+use basil_core::statements::{ExceptHandler, Statement};
+use basil_core::traceback::{Traceback, TracebackFrame};
+use basil_core::type_id::{Explicit, TypeId};
 use basil_core::variable::{IntoVariable, Variable};
 
-use crate::context::{Context, ContextGraph, Entry};
+use crate::builtins;
+use crate::context::{Context, ContextGraph};
 use crate::frame::Frame;
+use crate::operators;
 
 pub struct Interpreter {
     context_graph: ContextGraph,
     type_to_context_node: HashMap<TypeId, NodeIndex>,
+    classes: HashMap<Explicit, ClassInfo>,
+    stop_iteration: Explicit,
     frame_stack: Vec<Frame>,
+    /// The objects of the exceptions whose handlers are running, innermost last, along with
+    /// where they were first raised
+    handling: Vec<(Ptr<Object>, Traceback)>,
 }
 
+/// What the interpreter remembers about a class after it has been attached
+struct ClassInfo {
+    name: String,
+    parents: Vec<Explicit>,
+}
+
+/// How a statement or block finished running
+pub enum Completion {
+    /// It ran to the end, with the value of its last statement
+    Normal(Variable),
+    /// A `return` was run, which leaves every block up to the function it's in
+    Return(Variable),
+    /// A `break` was run, which leaves every block up to the innermost loop
+    Break,
+    /// A `continue` was run, which leaves every block up to the body of the innermost loop
+    Continue,
+}
+
+/// The state of a loop over an iterable
+enum Iteration {
+    /// The elements of a list or string or the keys of a dictionary, collected when the loop
+    /// started
+    Elements(std::vec::IntoIter<Variable>),
+    Range {
+        next: BigInt,
+        stop: BigInt,
+        step: BigInt,
+    },
+    /// An object returned by `__iter__`, whose `__next__` gives each element
+    Iterator(Variable),
+}
+
+#[allow(unused_macros)]
 macro_rules! basil {
     ($interpreter:expr, $variable:expr) => {
         $interpreter
@@ -109,12 +155,37 @@ macro_rules! basil {
 }
 
 impl Interpreter {
+    /// Creates an interpreter, defining the builtins in the global scope of `context_graph`
     pub fn new(context_graph: ContextGraph) -> Self {
-        Interpreter {
+        let stop_iteration = Class::new(builtins::STOP_ITERATION.to_string(), vec![], vec![]);
+        let mut interpreter = Interpreter {
             context_graph,
             type_to_context_node: Default::default(),
+            classes: Default::default(),
+            stop_iteration: stop_iteration.id(),
             frame_stack: vec![],
+            handling: vec![],
+        };
+        interpreter.attach_class(&stop_iteration);
+        let stop_iteration = Primitive::Class(WithSpan::empty(stop_iteration));
+        interpreter.context_graph.global_mut().insert(
+            builtins::STOP_ITERATION.to_string(),
+            stop_iteration.into_variable(),
+        );
+        for function in builtins::functions() {
+            let name = function.name().clone();
+            let function = Primitive::Function(WithSpan::empty(function));
+            interpreter
+                .context_graph
+                .global_mut()
+                .insert(name, function.into_variable());
         }
+        interpreter
+    }
+
+    /// Whether `name` is defined in the global scope, as the builtins are
+    pub fn is_global(&self, name: &str) -> bool {
+        self.context_graph.global().get(name).is_some()
     }
 
     pub fn current_frame(&self) -> &Frame {
@@ -129,6 +200,19 @@ impl Interpreter {
             .expect("There must always be a frame on the stack while running")
     }
 
+    /// The functions that are running, outermost first, each with the span of the statement it's
+    /// running
+    pub fn traceback(&self) -> Traceback {
+        let frames = self
+            .frame_stack
+            .iter()
+            .map(|frame| {
+                TracebackFrame::new(frame.name().to_string(), frame.current_span().clone())
+            })
+            .collect();
+        Traceback::new(frames)
+    }
+
     fn new_frame(&mut self, name: String, span: Span) {
         self.frame_stack.push(Frame::new(name, span))
     }
@@ -137,74 +221,69 @@ impl Interpreter {
         self.frame_stack.pop()
     }
 
+    /// Runs the body of a function or module, giving the value it returned, or the value of its
+    /// last statement if it didn't return
     pub fn execute_block(&mut self, block: &WithSpan<CodeBlock>) -> Result<Variable, Exception> {
-        let block = block.get_object();
-        let size = block.statements().len();
-        for i in 0..(size - 1) {
-            let statement = &block.statements()[i];
-            match self.execute_statement(statement) {
-                Ok(_) => {}
-                Err(e) => {
-                    let inner_var = e.inner().clone();
-                    return Interpreter::form_block_output(e, inner_var);
-                }
-            }
-        }
-        if let Some(last) = block.statements().last() {
-            match self.execute_statement(last) {
-                Ok(o) => Ok(o),
-                Err(e) => {
-                    let inner_var = e.inner().clone();
-                    Interpreter::form_block_output(e, inner_var)
-                }
-            }
-        } else {
-            Ok(Primitive::None.into_variable())
+        match self.run_block(block.get_object())? {
+            Completion::Normal(value) | Completion::Return(value) => Ok(value),
+            Completion::Break => Err("`break` outside of a loop")?,
+            Completion::Continue => Err("`continue` outside of a loop")?,
         }
     }
 
-    fn form_block_output(e: Exception, inner_var: Variable) -> Result<Variable, Exception> {
-        if let Ok(is_return) = basil!(inner_var.__is_return__) {
-            let x = is_return.to_inner().get();
-            let object = x.get();
-            match bool::try_from(object.as_primitive()) {
-                Ok(is_return) => basil!(inner_var.__return_val__),
-                Err(e) => Err(e),
+    /// Runs the statements of a block until one of them doesn't complete normally
+    fn run_block(&mut self, block: &CodeBlock) -> Result<Completion, Exception> {
+        let mut last = Primitive::None.into_variable();
+        for statement in block.statements() {
+            match self.execute_statement(statement)? {
+                Completion::Normal(value) => last = value,
+                completion => return Ok(completion),
             }
-        } else {
-            Err(e)
         }
+        Ok(Completion::Normal(last))
+    }
+
+    /// Runs the top level of a module in a new frame
+    pub fn execute_module(&mut self, module: &WithSpan<CodeBlock>) -> Result<Variable, Exception> {
+        self.new_frame("<module>".to_string(), module.get_span().clone());
+        let output = self.execute_block(module);
+        self.pop_frame();
+        output
     }
 
+    /// Executes a statement. Exceptions raised while executing it that don't have a location yet
+    /// are given the span of the statement, and the [traceback](Interpreter::traceback) of where
+    /// it runs.
     pub fn execute_statement(
         &mut self,
         statement: &WithSpan<Statement>,
-    ) -> Result<Variable, Exception> {
+    ) -> Result<Completion, Exception> {
+        self.execute_statement_kind(statement).map_err(|exception| {
+            let exception = exception.at(statement.get_span());
+            // the first statement to see the exception is the one that raised it, while all of
+            // the frames it was raised in are still on the stack
+            match exception.traceback() {
+                Some(_) => exception,
+                None => {
+                    let traceback = self.traceback();
+                    exception.with_traceback(traceback)
+                }
+            }
+        })
+    }
+
+    fn execute_statement_kind(
+        &mut self,
+        statement: &WithSpan<Statement>,
+    ) -> Result<Completion, Exception> {
         let span = statement.get_span();
         let statement = statement.get_object();
         self.current_frame_mut().set_current_span(span);
         match statement {
             Statement::Assignment(left, right) => {
-                let variable = self.evaluate_expression(right)?;
-                let mut current_context = self.context_graph.current_context();
-                let mut assign_to = self.evaluate_expression(left)?;
-                /*
-                let mut entry = current_context.entry(left.clone());
-
-                match entry {
-                    Entry::Occupied(o) => {
-                        let mut value = o.get();
-                        *value = variable.clone();
-                        Ok(variable)
-                    }
-                    Entry::Vacant(v) => {
-                        let x = v.insert(variable);
-                        Ok(x.clone())
-                    }
-                }
-
-                 */
-                Ok(assign_to)
+                let value = self.evaluate_expression(right)?;
+                self.assign(left, &value)?;
+                Ok(Completion::Normal(value))
             }
             Statement::If {
                 condition,
@@ -212,62 +291,917 @@ impl Interpreter {
                 elifs,
                 r#else,
             } => {
-                unimplemented!()
+                let branches = std::iter::once((condition, block))
+                    .chain(elifs.iter().map(|(condition, block)| (condition, block)));
+                for (condition, block) in branches {
+                    let condition = self.evaluate_expression(condition)?;
+                    if self.truthy(&condition)? {
+                        return self.run_block(block);
+                    }
+                }
+                match r#else {
+                    Some(block) => self.run_block(block),
+                    None => Ok(Completion::Normal(Primitive::None.into_variable())),
+                }
+            }
+            Statement::While {
+                condition,
+                block,
+                r#else,
+            } => {
+                loop {
+                    let value = self.evaluate_expression(condition)?;
+                    if !self.truthy(&value)? {
+                        break;
+                    }
+                    if let Some(completion) = self.run_loop_body(block)? {
+                        return Ok(completion);
+                    }
+                }
+                self.run_loop_else(r#else)
+            }
+            Statement::Expression(expression) => {
+                Ok(Completion::Normal(self.evaluate_expression(expression)?))
+            }
+            Statement::For {
+                variable,
+                iterable,
+                block,
+                r#else,
+            } => {
+                let iterable = self.evaluate_expression(iterable)?;
+                let mut iteration = self.iterate(&iterable)?;
+                while let Some(element) = self.next_element(&mut iteration)? {
+                    self.bind(variable, &element);
+                    if let Some(completion) = self.run_loop_body(block)? {
+                        return Ok(completion);
+                    }
+                }
+                self.run_loop_else(r#else)
             }
-            Statement::While { .. } => {
-                unimplemented!()
+            Statement::Break => Ok(Completion::Break),
+            Statement::Continue => Ok(Completion::Continue),
+            Statement::Match { subject, cases } => {
+                let subject = self.evaluate_expression(subject)?;
+                for case in cases {
+                    let mut bindings = vec![];
+                    if !self.match_pattern(case.pattern(), &subject, &mut bindings)? {
+                        continue;
+                    }
+                    // names stay bound even if the guard turns out to be false
+                    for (name, value) in &bindings {
+                        self.bind(name, value);
+                    }
+                    if let Some(guard) = case.guard() {
+                        let guard = self.evaluate_expression(guard)?;
+                        if !self.truthy(&guard)? {
+                            continue;
+                        }
+                    }
+                    return self.run_block(case.block());
+                }
+                Ok(Completion::Normal(Primitive::None.into_variable()))
             }
-            Statement::Expression(_) => {
-                unimplemented!()
+            Statement::Return(ret) => Ok(Completion::Return(self.evaluate_expression(ret)?)),
+            Statement::Raise(value) => {
+                let value = self.evaluate_expression(value)?;
+                let object = value.get_object();
+                let object = object.get();
+                let exception = match object.as_primitive() {
+                    // raising a class raises a new instance of it
+                    Primitive::Class(class) => self.instantiate(class.get_object(), vec![], vec![])?,
+                    _ => value.clone(),
+                };
+                // raising an exception that's being handled again keeps where it came from
+                let object = exception.get_object();
+                let handled = self
+                    .handling
+                    .iter()
+                    .rev()
+                    .find(|(handled, _)| handled.ptr_eq(&object))
+                    .map(|(_, traceback)| traceback.clone());
+                let exception = Exception::new(exception);
+                Err(match handled {
+                    Some(traceback) => exception.with_traceback(traceback),
+                    None => exception,
+                })
             }
-            Statement::Return(ret) => {
-                let value = self.evaluate_expression(ret)?;
-                let mut dictionary =
-                    Dictionary::with_entries(&["__is_return__", "__return_val__"]).into_variable();
-                basil!(dictionary.__is_return__ = true);
-                Ok(dictionary)
+            Statement::Try {
+                block,
+                handlers,
+                r#else,
+                finally,
+            } => {
+                let completion = match self.run_block(block) {
+                    Ok(Completion::Normal(value)) => match r#else {
+                        Some(block) => self.run_block(block),
+                        None => Ok(Completion::Normal(value)),
+                    },
+                    Ok(completion) => Ok(completion),
+                    Err(exception) => self.handle_exception(exception, handlers),
+                };
+                match finally {
+                    // leaving the `finally` block early replaces whatever the statement was
+                    // leaving with, including an exception
+                    Some(block) => match self.run_block(block)? {
+                        Completion::Normal(_) => completion,
+                        left => Ok(left),
+                    },
+                    None => completion,
+                }
             }
-            Statement::Raise(_) => {
-                unimplemented!()
+            Statement::Class {
+                name,
+                parents,
+                definitions,
+                decorators,
+            } => {
+                let class = self.define_class(name, parents, definitions, decorators, span)?;
+                self.context_graph
+                    .current_context()
+                    .insert(name.clone(), class.clone());
+                Ok(Completion::Normal(class))
             }
+            Statement::Decorated {
+                name,
+                decorators,
+                definition,
+            } => {
+                let mut functions = vec![];
+                for decorator in decorators {
+                    functions.push(self.evaluate_expression(decorator)?);
+                }
+                // the definition is created without binding it, so the name is only ever bound
+                // to the decorated value
+                let mut value = match definition.as_ref() {
+                    Statement::Assignment(_, function) => self.evaluate_expression(function)?,
+                    Statement::Class {
+                        name,
+                        parents,
+                        definitions,
+                        decorators,
+                    } => self.define_class(name, parents, definitions, decorators, span)?,
+                    _ => Err("Only functions and classes can be decorated")?,
+                };
+                for decorator in functions.iter().rev() {
+                    value = self.call(decorator, vec![value], vec![])?;
+                }
+                self.bind(name, &value);
+                Ok(Completion::Normal(value))
+            }
+        }
+    }
+
+    /// Runs one iteration of a loop, giving the completion of the loop statement if the loop has
+    /// to stop
+    fn run_loop_body(&mut self, block: &CodeBlock) -> Result<Option<Completion>, Exception> {
+        match self.run_block(block)? {
+            Completion::Normal(_) | Completion::Continue => Ok(None),
+            // the `else` is skipped when the loop is left with `break`
+            Completion::Break => Ok(Some(Completion::Normal(Primitive::None.into_variable()))),
+            completion @ Completion::Return(_) => Ok(Some(completion)),
+        }
+    }
+
+    /// Runs the `else` block of a loop that finished without a `break`
+    fn run_loop_else(&mut self, r#else: &Option<CodeBlock>) -> Result<Completion, Exception> {
+        match r#else {
+            Some(block) => self.run_block(block),
+            None => Ok(Completion::Normal(Primitive::None.into_variable())),
         }
     }
 
+    /// Runs the first handler that catches an exception, binding the exception to its name. The
+    /// exception is raised again if none of them catch it.
+    fn handle_exception(
+        &mut self,
+        exception: Exception,
+        handlers: &[ExceptHandler],
+    ) -> Result<Completion, Exception> {
+        for handler in handlers {
+            if !self.catches(handler, exception.inner())? {
+                continue;
+            }
+            if let Some(name) = handler.name() {
+                Interpreter::expose_traceback(&exception)?;
+                self.bind(name, exception.inner());
+            }
+            let traceback = exception.traceback().cloned().unwrap_or_default();
+            self.handling
+                .push((exception.inner().get_object(), traceback));
+            let completion = self.run_block(handler.block());
+            self.handling.pop();
+            return completion;
+        }
+        Err(exception)
+    }
+
+    /// Gives a class object that's being handled a `__traceback__` member, which lists the calls
+    /// that were running when it was raised, outermost first. Each call is a dictionary with its
+    /// `name`, and the `start` and `end` byte offsets of the statement it was running.
+    fn expose_traceback(exception: &Exception) -> Result<(), Exception> {
+        let inner = exception.inner();
+        let traceback = match exception.traceback() {
+            Some(traceback) if inner.get_object().get().is_class_object() => traceback,
+            _ => return Ok(()),
+        };
+        let mut frames = vec![];
+        for frame in traceback.frames() {
+            let span = frame.span();
+            let entries = vec![
+                ("name", Primitive::from(frame.name())),
+                ("start", Primitive::Integer(BigInt::from(span.start()))),
+                ("end", Primitive::Integer(BigInt::from(span.end()))),
+            ];
+            let entries = entries
+                .into_iter()
+                .map(|(key, value)| (Primitive::from(key).into_variable(), value.into_variable()))
+                .collect();
+            let frame = Interpreter::build_dictionary(entries)?;
+            frames.push(Object::from(Primitive::Dictionary(frame)));
+        }
+        let member = inner.get_member_or_create(
+            Object::from("__traceback__"),
+            Object::basic_hash,
+            Object::basic_eq,
+        )?;
+        member
+            .clone()
+            .set_object(Primitive::List(frames).into_variable());
+        Ok(())
+    }
+
+    /// Whether a handler catches an exception, which it does if the exception is an instance of
+    /// its class, or of one of the classes in its list or tuple
+    fn catches(
+        &mut self,
+        handler: &ExceptHandler,
+        exception: &Variable,
+    ) -> Result<bool, Exception> {
+        let classes = match handler.class() {
+            // the classes are evaluated one at a time, since putting them in a list would copy
+            // them, and a copy of a class is a different class
+            Some(class) => match (class.head(), class.tail()) {
+                (Atom::List(classes), None) => classes.iter().collect(),
+                _ => vec![class],
+            },
+            None => return Ok(true),
+        };
+        for class in classes {
+            let class = self.evaluate_expression(class)?;
+            let id = match class.get_object().get().as_primitive() {
+                Primitive::Class(class) => Some(class.get_object().id()),
+                _ => None,
+            };
+            match id {
+                Some(id) if self.is_instance(exception, id) => return Ok(true),
+                Some(_) => {}
+                None => Err(format!("{} is not a class", self.repr(&class)?))?,
+            }
+        }
+        Ok(false)
+    }
+
+    /// Creates a class and attaches its definitions to the context graph
+    fn define_class(
+        &mut self,
+        name: &str,
+        parents: &[Expression],
+        definitions: &[(String, Object)],
+        decorators: &[(String, Vec<Expression>)],
+        span: &Span,
+    ) -> Result<Variable, Exception> {
+        let mut parent_ids = vec![];
+        for parent in parents {
+            let parent = self.evaluate_expression(parent)?;
+            let parent = parent.get_object();
+            let parent = parent.get();
+            match parent.as_primitive() {
+                Primitive::Class(class) => parent_ids.push(class.get_object().id()),
+                other => Err(format!("{:?} is not a class", other))?,
+            }
+        }
+        let mut definitions = definitions.to_vec();
+        for (name, expressions) in decorators {
+            let mut functions = vec![];
+            for decorator in expressions {
+                functions.push(self.evaluate_expression(decorator)?);
+            }
+            if let Some((_, definition)) = definitions.iter_mut().find(|(n, _)| n == name) {
+                let mut value = Variable::new(definition.clone());
+                for decorator in functions.iter().rev() {
+                    value = self.call(decorator, vec![value], vec![])?;
+                }
+                *definition = value.get_object().get().clone();
+            }
+        }
+        let class = Class::new(name.to_string(), parent_ids, definitions);
+        self.attach_class(&class);
+        Ok(Primitive::Class(WithSpan::new(class, span.clone())).into_variable())
+    }
+
+    /// Checks whether a value matches a pattern, collecting the names that the pattern binds
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Variable,
+        bindings: &mut Vec<(String, Variable)>,
+    ) -> Result<bool, Exception> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Capture(name) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Value(expected) => {
+                let expected = self.evaluate_expression(expected)?;
+                let expected = expected.get_object();
+                let object = value.get_object();
+                let equal = !object.get().is_class_object()
+                    && operators::primitive_eq(
+                        expected.get().as_primitive(),
+                        object.get().as_primitive(),
+                    );
+                Ok(equal)
+            }
+            Pattern::List(patterns) => {
+                let elements: Vec<Variable> = {
+                    let object = value.get_object();
+                    let object = object.get();
+                    match object.as_primitive() {
+                        Primitive::List(list)
+                            if !object.is_class_object() && list.len() == patterns.len() =>
+                        {
+                            list.iter().cloned().map(Variable::new).collect()
+                        }
+                        _ => return Ok(false),
+                    }
+                };
+                for (pattern, element) in patterns.iter().zip(&elements) {
+                    if !self.match_pattern(pattern, element, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::Dictionary(entries) => {
+                {
+                    let object = value.get_object();
+                    let object = object.get();
+                    let dictionary = matches!(object.as_primitive(), Primitive::Dictionary(_));
+                    if object.is_class_object() || !dictionary {
+                        return Ok(false);
+                    }
+                }
+                for (key, pattern) in entries {
+                    let key = self.evaluate_expression(key)?;
+                    let mut key = key.get_object().get().clone();
+                    if !key.as_primitive().is_hashable() {
+                        let type_name = key.as_primitive().type_name();
+                        Err(format!("{} can't be used as a key", type_name))?
+                    }
+                    let found = match value.get_object().get().as_primitive() {
+                        Primitive::Dictionary(dictionary) => dictionary
+                            .get(&mut key, Object::basic_hash, Object::basic_eq)
+                            .cloned(),
+                        _ => None,
+                    };
+                    match found {
+                        Some(found) if self.match_pattern(pattern, &found, bindings)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::Class { class, members } => {
+                let class = self.evaluate_expression(class)?;
+                let class = match class.get_object().get().as_primitive() {
+                    Primitive::Class(class) => class.get_object().id(),
+                    _ => Err(format!("{} is not a class", self.repr(&class)?))?,
+                };
+                if !self.is_instance(value, class) {
+                    return Ok(false);
+                }
+                for (name, pattern) in members {
+                    match self.find_member(name, value) {
+                        Some(member) if self.match_pattern(pattern, &member, bindings)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    /// Finds a member of a class object in its own members, or in the definitions of its class and
+    /// the classes it inherits from
+    fn find_member(&self, name: &str, var: &Variable) -> Option<Variable> {
+        let object = var.get_object();
+        let object = object.get();
+        if let Primitive::Dictionary(dictionary) = object.as_primitive() {
+            let mut key: Object = Primitive::from(name).into();
+            if let Some(found) = dictionary.get(&mut key, Object::basic_hash, Object::basic_eq) {
+                return Some(found.clone());
+            }
+        }
+        match object.type_id() {
+            TypeId::Explicit(class) => self.class_member(class, name),
+            TypeId::Implicit(_) => None,
+        }
+    }
+
+    /// Finds a definition in a class, or in the classes it inherits from
+    fn class_member(&self, class: Explicit, name: &str) -> Option<Variable> {
+        let node = self.type_to_context_node.get(&TypeId::Explicit(class))?;
+        if let Some(found) = self.context_graph.context(*node)?.get(name) {
+            return Some(found.clone());
+        }
+        let info = self.classes.get(&class)?;
+        info.parents
+            .iter()
+            .find_map(|&parent| self.class_member(parent, name))
+    }
+
+    /// Stores a value in the target of an assignment, which is a name, a member, an element of a
+    /// list or dictionary, or a list of targets that the elements of the value are unpacked into
+    fn assign(&mut self, target: &Expression, value: &Variable) -> Result<(), Exception> {
+        match (target.head(), target.tail()) {
+            (Atom::Identifier(name, ..), None) => self.bind(name, value),
+            (Atom::List(targets), None) => {
+                let mut iteration = self.iterate(value)?;
+                let mut elements = vec![];
+                while let Some(element) = self.next_element(&mut iteration)? {
+                    if elements.len() == targets.len() {
+                        Err(format!("too many values to unpack (expected {})", targets.len()))?
+                    }
+                    elements.push(element);
+                }
+                if elements.len() < targets.len() {
+                    Err(format!(
+                        "not enough values to unpack (expected {}, got {})",
+                        targets.len(),
+                        elements.len()
+                    ))?
+                }
+                for (target, element) in targets.iter().zip(&elements) {
+                    self.assign(target, element)?;
+                }
+            }
+            (object, Some(ExpressionTail::GetMember(member))) => {
+                let object = self.evaluate_atom(object)?;
+                if object.get_object().get().get_dictionary().is_none() {
+                    let type_name = object.get_object().get().type_name();
+                    Err(format!("can't assign to member {} of {}", member, type_name))?
+                }
+                let member = object.get_member_or_create(
+                    Object::from(member.as_str()),
+                    Object::basic_hash,
+                    Object::basic_eq,
+                )?;
+                member.clone().set_object(value.clone());
+            }
+            (object, Some(ExpressionTail::Index(index))) => {
+                let object = self.evaluate_atom(object)?;
+                let index = self.evaluate_expression(index)?;
+                self.set_element(&object, &index, value)?;
+            }
+            _ => Err("can't assign to this expression")?,
+        }
+        Ok(())
+    }
+
+    /// Sets an element of a list, or the value of a key in a dictionary
+    fn set_element(
+        &mut self,
+        object: &Variable,
+        index: &Variable,
+        value: &Variable,
+    ) -> Result<(), Exception> {
+        // the value is copied first, because it may be the collection itself
+        let element = value.get_object().get().clone();
+        let key = index.get_object().get().clone();
+        let object = object.get_object();
+        let is_class_object = object.get().is_class_object();
+        let mut collection = object.get_mut();
+        match (&mut **collection, key.as_primitive()) {
+            _ if is_class_object => {}
+            (Primitive::List(list), Primitive::Integer(i)) => {
+                let i = Interpreter::sequence_index(i, list.len())?;
+                list[i] = element;
+                return Ok(());
+            }
+            (Primitive::Dictionary(dictionary), _)
+                if key.is_hashable() && !key.is_class_object() =>
+            {
+                let mut key = key;
+                match dictionary.get(&mut key, Object::basic_hash, Object::basic_eq) {
+                    Some(existing) => existing.clone().set_object(value.clone()),
+                    None => dictionary.insert(
+                        key,
+                        Variable::new(element),
+                        Object::basic_hash,
+                        Object::basic_eq,
+                    ),
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        Err(format!(
+            "{} doesn't support assigning to elements by {}",
+            collection.type_name(),
+            key.type_name()
+        ))?
+    }
+
+    /// Binds a name to a value in the current scope. A name that's already defined in the scope
+    /// is rebound in place, so closures that captured it see the new value.
+    fn bind(&mut self, name: &str, value: &Variable) {
+        match self.context_graph.local(name) {
+            Some(variable) => variable.clone().set_object(value.clone()),
+            None => self
+                .context_graph
+                .current_context()
+                .insert(name.to_string(), Variable::new(value.get_object())),
+        }
+    }
+
+    /// Describes an exception that escaped to the host, pointing at where it was raised
+    pub fn diagnostic(&mut self, exception: &Exception) -> Diagnostic {
+        let inner = exception.inner();
+        let message = match self.class_name(inner) {
+            // instances of classes that don't describe themselves are shown by their class
+            Some(name) if self.find_method("__str__", inner).is_err() => name,
+            _ => self
+                .str(inner)
+                .unwrap_or_else(|_| format!("{:?}", inner.get_object().get().as_primitive())),
+        };
+        let span = exception.span().cloned().unwrap_or_else(Span::native);
+        let diagnostic = Diagnostic::error(message, span);
+        match exception.traceback() {
+            Some(traceback) => diagnostic.with_traceback(traceback.clone()),
+            None => diagnostic,
+        }
+    }
+
+    /// Converts a value into a string that looks like the literal that creates it. Class objects
+    /// use `__repr__` if they have it, and collections show their elements with `repr`.
     pub fn repr(&mut self, var: &Variable) -> Result<String, Exception> {
         let object_ptr = var.get_object();
         let object = object_ptr.get();
+        if object.is_class_object() {
+            std::mem::drop(object);
+            return match self.class_name(var) {
+                Some(name) if self.find_method("__repr__", var).is_err() => {
+                    Ok(format!("<{} object>", name))
+                }
+                _ => self.call_string_method("__repr__", var),
+            };
+        }
+        // the entries are copied out so that `__repr__` can run without the collection borrowed
+        let (entries, brackets) = match object.as_primitive() {
+            Primitive::Boolean(true) => return Ok("True".to_string()),
+            Primitive::Boolean(false) => return Ok("False".to_string()),
+            Primitive::String(string) => return Ok(Interpreter::string_repr(string)),
+            Primitive::List(list) => {
+                let elements = list
+                    .iter()
+                    .map(|element| (Variable::new(element.clone()), None));
+                (elements.collect::<Vec<_>>(), ('[', ']'))
+            }
+            // `{}` is an empty dictionary, so empty sets have no literal
+            Primitive::Dictionary(dictionary) if dictionary.is_set() && dictionary.is_empty() => {
+                return Ok("set()".to_string())
+            }
+            Primitive::Dictionary(dictionary) => {
+                let set = dictionary.is_set();
+                let entries = dictionary.iter().map(|(key, value)| {
+                    let value = Some(value.clone()).filter(|_| !set);
+                    (Variable::new(key.clone()), value)
+                });
+                (entries.collect(), ('{', '}'))
+            }
+            other => return Ok(format!("{:?}", other)),
+        };
+        std::mem::drop(object);
+
+        let mut parts = vec![];
+        for (key, value) in &entries {
+            let mut part = self.repr(key)?;
+            if let Some(value) = value {
+                part = format!("{}: {}", part, self.repr(value)?);
+            }
+            parts.push(part);
+        }
+        Ok(format!("{}{}{}", brackets.0, parts.join(", "), brackets.1))
+    }
+
+    /// Quotes a string the way it would be written in a string literal
+    fn string_repr(string: &str) -> String {
+        let mut output = String::with_capacity(string.len() + 2);
+        output.push('"');
+        for c in string.chars() {
+            match c {
+                '"' => output.push_str("\\\""),
+                '\\' => output.push_str("\\\\"),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\t' => output.push_str("\\t"),
+                c => output.push(c),
+            }
+        }
+        output.push('"');
+        output
+    }
+
+    /// Converts a value into a string for display. Strings are used as is, class objects use
+    /// `__str__` if they have it and `__repr__` otherwise, and everything else is the same as
+    /// [repr](Interpreter::repr).
+    pub fn str(&mut self, var: &Variable) -> Result<String, Exception> {
+        let object_ptr = var.get_object();
+        let object = object_ptr.get();
+        if let Primitive::String(string) = object.as_primitive() {
+            if !object.is_class_object() {
+                return Ok(string.clone());
+            }
+        }
+        let is_class_object = object.is_class_object();
+        std::mem::drop(object);
+
+        if is_class_object && self.find_method("__str__", var).is_ok() {
+            self.call_string_method("__str__", var)
+        } else {
+            self.repr(var)
+        }
+    }
+
+    /// Whether a value counts as true in a condition. Class objects are true unless `__bool__`
+    /// returns false, or, if they don't have `__bool__`, `__len__` returns zero. Other values
+    /// follow [operators::truthy].
+    fn truthy(&mut self, var: &Variable) -> Result<bool, Exception> {
+        let object = var.get_object();
+        let object = object.get();
         if !object.is_class_object() {
-            return Ok(format!("{:?}", object.as_primitive()));
+            return Ok(operators::truthy(object.as_primitive()));
         }
         std::mem::drop(object);
 
-        let repr_result = basil!(var.__repr__);
-        if let Ok(repr) = repr_result {
-            let object_ptr = repr.get_object();
-            let object = object_ptr.get();
-            let primitive = object.as_primitive();
-            if let Primitive::Function(f) = primitive {
-                self.call_method("__repr__".to_string(), var, f, vec![], vec![]);
+        if self.find_method("__bool__", var).is_ok() {
+            let result = self.call_method_named("__bool__", var, vec![], vec![])?;
+            let result = result.get_object();
+            let result = result.get();
+            match result.as_primitive() {
+                Primitive::Boolean(b) if !result.is_class_object() => Ok(*b),
+                other => Err(format!(
+                    "__bool__ returned {} instead of a Boolean",
+                    other.type_name()
+                ))?,
+            }
+        } else if self.find_method("__len__", var).is_ok() {
+            let result = self.call_method_named("__len__", var, vec![], vec![])?;
+            let result = result.get_object();
+            let result = result.get();
+            match result.as_primitive() {
+                Primitive::Integer(len) if !result.is_class_object() => Ok(!len.is_zero()),
+                other => Err(format!(
+                    "__len__ returned {} instead of an Integer",
+                    other.type_name()
+                ))?,
             }
+        } else {
+            Ok(true)
         }
+    }
 
-        Err("A class object must have a __repr__ member that is a function")?
+    /// Calls a method that takes no arguments and must return a string, such as `__repr__`
+    fn call_string_method(&mut self, name: &str, var: &Variable) -> Result<String, Exception> {
+        let result = self.call_method_named(name, var, vec![], vec![])?;
+        let result = result.get_object();
+        let result = result.get();
+        match result.as_primitive() {
+            Primitive::String(string) => Ok(string.clone()),
+            other => Err(format!("{} returned {} instead of a String", name, other.type_name()))?,
+        }
     }
 
     fn evaluate_atom(&mut self, atom: &Atom) -> Result<Variable, Exception>{
         match atom {
-            Atom::Identifier(id) => {
-                Ok(self.context_graph.current_context().entry(id.clone()).or_insert(Primitive::None.into_variable()).clone())
+            Atom::Identifier(id, scope, _) => {
+                let variable = match scope {
+                    // a local that hasn't been assigned yet doesn't fall back to a global
+                    NameScope::Local => self
+                        .context_graph
+                        .current_context()
+                        .get_enclosed(id)
+                        .cloned(),
+                    NameScope::Global => self.context_graph.global().get(id).cloned(),
+                    NameScope::Captured | NameScope::Class | NameScope::Unresolved => {
+                        self.context_graph.current_context().get(id).cloned()
+                    }
+                };
+                variable.ok_or_else(|| Exception::from(format!("{} is not defined", id)))
             }
             Atom::Variable(v) => { Ok(v.clone()) }
+            Atom::Function(function) => {
+                let mut captures = HashMap::new();
+                for name in function.get_object().free_variables() {
+                    let variable = self.context_graph.current_context().get(name).cloned();
+                    let variable = variable.ok_or_else(|| format!("{} is not defined", name))?;
+                    captures.insert(name.clone(), variable);
+                }
+                let mut closure = function.get_object().clone();
+                closure.set_captures(captures);
+                let closure = WithSpan::new(closure, function.get_span().clone());
+                Ok(Primitive::Function(closure).into_variable())
+            }
+            Atom::Expression(expression) => self.evaluate_expression(expression),
+            Atom::UnaryOperation { op: UnaryOperator::Not, value } => {
+                let value = self.evaluate_expression(value)?;
+                Ok(Primitive::Boolean(!self.truthy(&value)?).into_variable())
+            }
+            Atom::UnaryOperation { op, value } => {
+                let value = self.evaluate_expression(value)?;
+                let object = value.get_object();
+                let result = operators::unary_operation(*op, object.get().as_primitive())?;
+                Ok(result.into_variable())
+            }
+            Atom::Str(value) => {
+                let value = self.evaluate_expression(value)?;
+                Ok(Primitive::String(self.str(&value)?).into_variable())
+            }
+            Atom::Repr(value) => {
+                let value = self.evaluate_expression(value)?;
+                Ok(Primitive::String(self.repr(&value)?).into_variable())
+            }
+            Atom::List(elements) => {
+                let mut list = vec![];
+                for element in elements {
+                    let element = self.evaluate_expression(element)?;
+                    list.push(element.get_object().get().clone());
+                }
+                Ok(Primitive::List(list).into_variable())
+            }
+            Atom::Set(elements) => {
+                let mut entries = vec![];
+                for element in elements {
+                    entries.push((self.evaluate_expression(element)?, Primitive::None.into_variable()));
+                }
+                Ok(Interpreter::build_dictionary(entries)?
+                    .into_set()
+                    .into_variable())
+            }
+            Atom::Dictionary(elements) => {
+                let mut entries = vec![];
+                for (key, value) in elements {
+                    let key = self.evaluate_expression(key)?;
+                    entries.push((key, self.evaluate_expression(value)?));
+                }
+                Ok(Interpreter::build_dictionary(entries)?.into_variable())
+            }
+            Atom::Comprehension { kind, clauses } => {
+                self.context_graph.higher_scope();
+                let mut output = vec![];
+                let result = self.run_comprehension(kind, clauses, &mut output);
+                self.context_graph.pop();
+                result?;
+                match kind.as_ref() {
+                    ComprehensionKind::List(_) => {
+                        let list = output.into_iter()
+                            .map(|(element, _)| element.get_object().get().clone())
+                            .collect();
+                        Ok(Primitive::List(list).into_variable())
+                    }
+                    ComprehensionKind::Set(_) | ComprehensionKind::Dictionary(..) => {
+                        let entries = output.into_iter()
+                            .map(|(key, value)| (key, value.unwrap_or_else(|| Primitive::None.into_variable())))
+                            .collect();
+                        let dictionary = Interpreter::build_dictionary(entries)?;
+                        match kind.as_ref() {
+                            ComprehensionKind::Set(_) => Ok(dictionary.into_set().into_variable()),
+                            _ => Ok(dictionary.into_variable()),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs the first clause of a comprehension, which in turn runs the rest. Once every clause has
+    /// been run the element is evaluated and added to the output, along with the value if this is
+    /// a dictionary comprehension.
+    fn run_comprehension(
+        &mut self,
+        kind: &ComprehensionKind,
+        clauses: &[ComprehensionClause],
+        output: &mut Vec<(Variable, Option<Variable>)>,
+    ) -> Result<(), Exception> {
+        match clauses.split_first() {
+            None => {
+                let element = match kind {
+                    ComprehensionKind::List(element) | ComprehensionKind::Set(element) => {
+                        (self.evaluate_expression(element)?, None)
+                    }
+                    ComprehensionKind::Dictionary(key, value) => {
+                        let key = self.evaluate_expression(key)?;
+                        (key, Some(self.evaluate_expression(value)?))
+                    }
+                };
+                output.push(element);
+            }
+            Some((ComprehensionClause::If(condition), rest)) => {
+                let condition = self.evaluate_expression(condition)?;
+                if self.truthy(&condition)? {
+                    self.run_comprehension(kind, rest, output)?;
+                }
+            }
+            Some((ComprehensionClause::For { variable, iterable }, rest)) => {
+                let iterable = self.evaluate_expression(iterable)?;
+                let mut iteration = self.iterate(&iterable)?;
+                while let Some(element) = self.next_element(&mut iteration)? {
+                    self.context_graph.current_context().insert(variable.clone(), element);
+                    self.run_comprehension(kind, rest, output)?;
+                }
+            }
         }
+        Ok(())
+    }
+
+    /// Starts iterating over a list, string, dictionary or range, or over a class object with an
+    /// `__iter__` method
+    fn iterate(&mut self, iterable: &Variable) -> Result<Iteration, Exception> {
+        let object = iterable.get_object();
+        let object = object.get();
+        if object.is_class_object() {
+            std::mem::drop(object);
+            if self.find_method("__iter__", iterable).is_err() {
+                Err(format!("{} is not iterable", self.repr(iterable)?))?
+            }
+            let iterator = self.call_method_named("__iter__", iterable, vec![], vec![])?;
+            return Ok(Iteration::Iterator(iterator));
+        }
+        let elements: Vec<Variable> = match object.as_primitive() {
+            Primitive::List(list) => list.iter().map(|element| Variable::new(element.clone())).collect(),
+            Primitive::String(string) => {
+                string.chars().map(|c| Primitive::from(c.to_string()).into_variable()).collect()
+            }
+            Primitive::Dictionary(dictionary) => {
+                dictionary.iter().map(|(key, _)| Variable::new(key.clone())).collect()
+            }
+            Primitive::Range { start, stop, step } => {
+                return Ok(Iteration::Range {
+                    next: start.clone(),
+                    stop: stop.clone(),
+                    step: step.clone(),
+                })
+            }
+            other => Err(format!("{} is not iterable", other.type_name()))?,
+        };
+        Ok(Iteration::Elements(elements.into_iter()))
+    }
+
+    /// Gets the next element of an iteration, or None once it's finished. An iterator finishes by
+    /// raising `StopIteration`, or any class that inherits from it, from `__next__`.
+    fn next_element(&mut self, iteration: &mut Iteration) -> Result<Option<Variable>, Exception> {
+        match iteration {
+            Iteration::Elements(elements) => Ok(elements.next()),
+            Iteration::Range { next, stop, step } => {
+                let finished = if step.is_positive() { next >= stop } else { next <= stop };
+                if finished {
+                    return Ok(None);
+                }
+                let element = next.clone();
+                *next += &*step;
+                Ok(Some(Primitive::Integer(element).into_variable()))
+            }
+            Iteration::Iterator(iterator) => {
+                match self.call_method_named("__next__", iterator, vec![], vec![]) {
+                    Ok(element) => Ok(Some(element)),
+                    Err(exception) if self.is_instance(exception.inner(), self.stop_iteration) => {
+                        Ok(None)
+                    }
+                    Err(exception) => Err(exception),
+                }
+            }
+        }
+    }
+
+    fn build_dictionary(entries: Vec<(Variable, Variable)>) -> Result<Dictionary, Exception> {
+        let mut dictionary = Dictionary::new();
+        for (key, value) in entries {
+            let key = key.get_object().get().clone();
+            if !key.is_hashable() || key.is_class_object() {
+                Err(format!("{} can't be used as a key", key.type_name()))?
+            }
+            dictionary.insert(key, Variable::new(value.get_object()), Object::basic_hash, Object::basic_eq);
+        }
+        Ok(dictionary)
     }
 
     pub fn evaluate_expression(
         &mut self,
-        mut expression: &Expression,
+        expression: &Expression,
     ) -> Result<Variable, Exception> {
-        let mut head = self.evaluate_atom(expression.head())?;
+        let head = self.evaluate_atom(expression.head())?;
         let tail = expression.tail();
 
         if tail.is_none() {
@@ -294,33 +1228,86 @@ impl Interpreter {
                 }
             }
             ExpressionTail::CallMethod { positional, named } => {
-                let obj_ptr = head.get_object();
-                let obj = obj_ptr.get();
-                if let Primitive::Function(func) = obj.as_primitive() {
-                    let mut eval_positional = vec![];
-                    for expr in positional {
-                        eval_positional.push(self.evaluate_expression(expr)?);
-                    }
-
-                    let mut kw = vec![];
-                    for (name, expr) in named {
-                        kw.push((name.clone(), self.evaluate_expression(expr)?))
-                    }
+                let mut eval_positional = vec![];
+                for expr in positional {
+                    eval_positional.push(self.evaluate_expression(expr)?);
+                }
 
+                let mut kw = vec![];
+                for (name, expr) in named {
+                    kw.push((name.clone(), self.evaluate_expression(expr)?))
+                }
 
-                    self.call_function(
-                        func.get_object().name().clone(),
-                        func,
-                        eval_positional,
-                        kw
-                    )
-                } else {
-                    Err(format!("{:?} is not a function", head))?
+                self.call(&head, eval_positional, kw)
+            }
+            ExpressionTail::Index(index) => {
+                let index = self.evaluate_expression(index)?;
+                let object = head.get_object();
+                let object = object.get();
+                let index = index.get_object();
+                let mut index = index.get().clone();
+                match (object.as_primitive(), index.as_primitive()) {
+                    _ if object.is_class_object() => {}
+                    (Primitive::List(list), Primitive::Integer(i)) => {
+                        let element = Interpreter::sequence_index(i, list.len())
+                            .map(|i| list[i].clone());
+                        return element.map(Variable::new)
+                    }
+                    (Primitive::String(string), Primitive::Integer(i)) => {
+                        let chars: Vec<char> = string.chars().collect();
+                        let element = Interpreter::sequence_index(i, chars.len())
+                            .map(|i| Primitive::from(chars[i].to_string()));
+                        return element.map(IntoVariable::into_variable)
+                    }
+                    (Primitive::Dictionary(dictionary), key) if key.is_hashable() => {
+                        return dictionary.get(&mut index, Object::basic_hash, Object::basic_eq)
+                            .cloned()
+                            .ok_or_else(|| Exception::from(format!("{:?} is not a key", index.as_primitive())))
+                    }
+                    _ => {}
                 }
+                Err(format!(
+                    "{} can't be indexed by {}",
+                    object.type_name(),
+                    index.type_name()
+                ))?
+            }
+            ExpressionTail::BinaryOperation { op: op @ BinaryOperator::And, rhs }
+            | ExpressionTail::BinaryOperation { op: op @ BinaryOperator::Or, rhs } => {
+                // `and` stops at the first false value, `or` at the first true one
+                let short_circuit = *op == BinaryOperator::Or;
+                if self.truthy(&head)? == short_circuit {
+                    return Ok(head);
+                }
+                self.evaluate_expression(rhs)
+            }
+            ExpressionTail::BinaryOperation { op, rhs } => {
+                let rhs = self.evaluate_expression(rhs)?;
+                let lhs = head.get_object();
+                let rhs = rhs.get_object();
+                let result = operators::binary_operation(
+                    *op,
+                    lhs.get().as_primitive(),
+                    rhs.get().as_primitive()
+                )?;
+                Ok(result.into_variable())
             }
         }
     }
 
+    /// Converts an index into a position in a sequence, where negative indices count from the end
+    fn sequence_index(index: &BigInt, len: usize) -> Result<usize, Exception> {
+        let position = if index.is_negative() {
+            BigInt::from(len) + index
+        } else {
+            index.clone()
+        };
+        position
+            .to_usize()
+            .filter(|&position| position < len)
+            .ok_or_else(|| Exception::from(format!("index {} is out of range", index)))
+    }
+
     pub fn attach_class(&mut self, class: &Class) {
         if class.created() {
             return;
@@ -340,6 +1327,13 @@ impl Interpreter {
         let node = self.context_graph.add_new_context(new_context);
 
         self.type_to_context_node.insert(id, node); // Adds entry into class
+        self.classes.insert(
+            class.id(),
+            ClassInfo {
+                name: class.name().clone(),
+                parents: class.parents().clone(),
+            },
+        );
 
         for parent in class.parents() {
             let parent_id = TypeId::Explicit(*parent);
@@ -350,31 +1344,121 @@ impl Interpreter {
         class.set_created();
     }
 
-    fn find_method<S: AsRef<str>>(&self, name: S, var: &Variable) -> Result<Variable, Exception> {
+    /// Creates an object of a class, calling its `__init__` with the arguments if it has one
+    fn instantiate(
+        &mut self,
+        class: &Class,
+        positional_arguments: Vec<Variable>,
+        keywords: Vec<(String, Variable)>,
+    ) -> Result<Variable, Exception> {
+        let object = Object::construct_type_object(class.id(), Dictionary::new()).into_variable();
+        if self.find_method("__init__", &object).is_ok() {
+            self.call_method_named("__init__", &object, positional_arguments, keywords)?;
+        } else if !positional_arguments.is_empty() || !keywords.is_empty() {
+            Err(format!("{} takes no arguments", class.name()))?
+        }
+        Ok(object)
+    }
+
+    /// Whether a class is `ancestor` or inherits from it
+    fn is_subclass(&self, class: Explicit, ancestor: Explicit) -> bool {
+        class == ancestor
+            || self.classes.get(&class).is_some_and(|info| {
+                info.parents.iter().any(|&parent| self.is_subclass(parent, ancestor))
+            })
+    }
+
+    /// Whether a value is an object of `class` or of a class that inherits from it
+    fn is_instance(&self, var: &Variable, class: Explicit) -> bool {
+        match var.get_object().get().type_id() {
+            TypeId::Explicit(id) => self.is_subclass(id, class),
+            TypeId::Implicit(_) => false,
+        }
+    }
+
+    /// The name of the class of a class object
+    fn class_name(&self, var: &Variable) -> Option<String> {
+        match var.get_object().get().type_id() {
+            TypeId::Explicit(id) => self.classes.get(&id).map(|info| info.name.clone()),
+            TypeId::Implicit(_) => None,
+        }
+    }
+
+    /// Finds a method on an object, looking at the object's own members before the definitions
+    /// of its class and the classes it inherits from
+    fn find_method<S: AsRef<str>>(&mut self, name: S, var: &Variable) -> Result<Variable, Exception> {
         self.find_method_helper(name.as_ref(), var)
     }
 
-    fn find_method_helper(&self, name: &str, var: &Variable) -> Result<Variable, Exception> {
+    fn find_method_helper(&mut self, name: &str, var: &Variable) -> Result<Variable, Exception> {
         let inner = var.to_inner().get();
         let inner = inner.get();
         let primitive = inner.as_primitive();
         let mut name_object: Object = Primitive::from(name).into();
 
-        if let Primitive::Dictionary(dict) = primitive {
-            if let Some(var) = dict.get(&mut name_object, Object::basic_hash, Object::basic_eq) {
-                if var.to_inner().get().get().as_primitive().is_function() {
-                    return Ok(var.clone());
-                } else {
-                    Err(format!("{} is not a function in {:?}", name, var))?
-                }
-            } else {
-                Err(format!("No entry {} in {:?}", name, var))?
-            }
+        let found = if let Primitive::Dictionary(dict) = primitive {
+            dict.get(&mut name_object, Object::basic_hash, Object::basic_eq).cloned()
         } else {
             Err("Can't find a method on a type that isn't backed by a dictionary")?
+        };
+        let found = match (found, self.type_to_context_node.get(&inner.type_id())) {
+            (Some(found), _) => found,
+            (None, Some(&node)) => {
+                self.context_graph.shift_to_scope(node);
+                let found = self.context_graph.current_context().get(&name.to_string()).cloned();
+                self.context_graph.unshift_scope();
+                found.ok_or_else(|| Exception::from(format!("No entry {} in {:?}", name, var)))?
+            }
+            (None, None) => Err(format!("No entry {} in {:?}", name, var))?,
+        };
+        if found.to_inner().get().get().as_primitive().is_function() {
+            Ok(found)
+        } else {
+            Err(format!("{} is not a function in {:?}", name, var))?
+        }
+    }
+
+    /// Calls a function, or creates an instance of a class
+    fn call(
+        &mut self,
+        callee: &Variable,
+        positional_arguments: Vec<Variable>,
+        keywords: Vec<(String, Variable)>,
+    ) -> Result<Variable, Exception> {
+        let object = callee.get_object();
+        let object = object.get();
+        match object.as_primitive() {
+            _ if object.is_class_object() => Err(format!("{:?} is not a function", callee))?,
+            Primitive::Function(function) => self.call_function(
+                function.get_object().name().clone(),
+                function,
+                positional_arguments,
+                keywords,
+            ),
+            Primitive::Class(class) => {
+                self.instantiate(class.get_object(), positional_arguments, keywords)
+            }
+            _ => Err(format!("{:?} is not a function", callee))?,
         }
     }
 
+    /// Finds a method on an object and calls it
+    fn call_method_named(
+        &mut self,
+        name: &str,
+        object: &Variable,
+        positional_arguments: Vec<Variable>,
+        keywords: Vec<(String, Variable)>,
+    ) -> Result<Variable, Exception> {
+        let method = self.find_method(name, object)?;
+        let method = method.get_object();
+        let function = match method.get().as_primitive() {
+            Primitive::Function(function) => function.clone(),
+            _ => Err(format!("{} is not a function", name))?,
+        };
+        self.call_method(name.to_string(), object, &function, positional_arguments, keywords)
+    }
+
     fn call_method(
         &mut self,
         name: String,
@@ -384,20 +1468,20 @@ impl Interpreter {
         keywords: Vec<(String, Variable)>,
     ) -> Result<Variable, Exception> {
         let var = object;
-        let my_function = function.get_object();
-        let object = object.to_inner().get();
-        let object = object.get_mut();
-        let type_id = object.type_id();
-        let node_index = self.type_to_context_node[&type_id];
+        let type_id = object.get_object().get().type_id();
+        let node_index = *self
+            .type_to_context_node
+            .get(&type_id)
+            .ok_or("Methods can only be called on class objects")?;
 
         self.context_graph.shift_to_scope(node_index); // shifts to the class scope
-        self.context_graph.higher_scope();
+        self.context_graph.higher_scope(); // sees `this` and the members of the class
 
         self.context_graph.current_context()
             .insert("this".to_string(), var.clone());
 
 
-        let output = self.call_function(name, function, positional_arguments, keywords);
+        let output = self.run_function(name, function, positional_arguments, keywords);
 
 
         /*
@@ -441,7 +1525,7 @@ impl Interpreter {
 
 
         self.context_graph.pop();
-        self.context_graph.pop();
+        self.context_graph.unshift_scope(); // leaves the class scope in the graph
 
         output
     }
@@ -449,10 +1533,30 @@ impl Interpreter {
     fn call_function(&mut self, name: String, function: &WithSpan<Function>, positional_arguments: Vec<Variable>,
                      keywords: Vec<(String, Variable)>) -> Result<Variable, Exception>
     {
+        if let Some(body) = function.get_object().native_body() {
+            return body(positional_arguments, keywords);
+        }
 
-        let my_function = function.get_object();
+        // functions only see the globals and what they captured, not the scope of their caller
+        self.context_graph.shift_new_scope();
+        let output = self.run_function(name, function, positional_arguments, keywords);
+        self.context_graph.pop();
+
+        output
+    }
 
-        self.context_graph.higher_scope();
+    /// Runs a function in the current scope, after binding its captures and arguments there
+    fn run_function(
+        &mut self,
+        name: String,
+        function: &WithSpan<Function>,
+        positional_arguments: Vec<Variable>,
+        keywords: Vec<(String, Variable)>,
+    ) -> Result<Variable, Exception> {
+        let my_function = function.get_object();
+        if let Some(body) = my_function.native_body() {
+            return body(positional_arguments, keywords);
+        }
 
         let mut context = self.context_graph.current_context();
 
@@ -460,21 +1564,35 @@ impl Interpreter {
             context.insert(capture.clone(), value.clone());
         }
 
-        let mut position_arguments_iter = function.get_object().positional_arguments()
+        let position_arguments_iter = function.get_object().positional_arguments()
             .iter()
-            .zip(positional_arguments.into_iter());
+            .zip(positional_arguments);
 
+        // arguments get variables of their own, so rebinding a parameter doesn't rebind the
+        // caller's variable
         for (name, value) in position_arguments_iter {
-            context.insert(name.clone(), value)
+            context.insert(name.clone(), Variable::new(value.get_object()))
         }
 
         for (name, value) in keywords {
-            context.insert(name, value)
+            context.insert(name, Variable::new(value.get_object()))
         }
 
-        for (name, value) in function.get_object().keyword_arguments() {
-            if !context.contains(name) {
-                context.insert(name.clone(), Variable::from(value.clone()))
+        // defaults and cells only fill in what the arguments didn't, without looking at the
+        // caller's scope
+        let defaults = my_function
+            .keyword_arguments()
+            .iter()
+            .map(|(name, value)| (name, Variable::from(value.clone())));
+        let cells = my_function
+            .cell_variables()
+            .iter()
+            .map(|name| (name, Primitive::None.into_variable()));
+        for (name, value) in defaults.chain(cells) {
+            if self.context_graph.local(name).is_none() {
+                self.context_graph
+                    .current_context()
+                    .insert(name.clone(), value);
             }
         }
 
@@ -486,8 +1604,6 @@ impl Interpreter {
 
         self.pop_frame();
 
-        self.context_graph.pop();
-
         output
     }
 }
@@ -495,31 +1611,32 @@ impl Interpreter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::convert::{TryFrom, TryInto};
+    use basil_core::source_map::SourceMap;
 
     #[test]
     fn set_member() {
-        let mut dict = Dictionary::with_entries(&["var_name"]).into_variable();
+        let dict = Dictionary::with_entries(["var_name"]).into_variable();
         println!("Dict: {:?}", dict);
         basil!(dict.var_name = true);
         println!("Dict: {:?}", dict);
         let var_name = basil!(dict.var_name).unwrap();
         let ptr = var_name.get_object();
-        let inner = ptr.get();
         let object = ptr.get();
         let primitive = object.as_primitive();
         if let Primitive::Boolean(bl) = primitive {
             assert_eq!(bl, &true);
-        } else if let b = primitive {
+        } else {
             panic!(
                 "dict.var_name wasn't set to a boolean, instead set to {:?}",
-                b
+                primitive
             );
         }
     }
 
     #[test]
     fn variables_separate() {
-        let mut dict = Dictionary::with_entries(&["var1", "var2"]).into_variable();
+        let dict = Dictionary::with_entries(["var1", "var2"]).into_variable();
         let val = 0i64.into_variable();
         basil!(dict.var1 = val.clone());
         basil!(dict.var2 = val);
@@ -539,8 +1656,8 @@ mod tests {
 
     #[test]
     fn layered_dict() {
-        let mut dict1 = Dictionary::with_entries(&["var1", "var2"]).into_variable();
-        let mut dict2 = Dictionary::with_entries(&["var3"]).into_variable();
+        let dict1 = Dictionary::with_entries(["var1", "var2"]).into_variable();
+        let dict2 = Dictionary::with_entries(["var3"]).into_variable();
         basil!(dict1.var1 = dict2.clone());
         basil!(dict1.var2 = dict1.var1);
         println!("{:?}", dict1);
@@ -553,9 +1670,911 @@ mod tests {
         println!("{:?}", dict2);
     }
 
+    fn compile(source: &str) -> WithSpan<CodeBlock> {
+        let mut sources = SourceMap::new();
+        let file = sources.add_file("test.basil", source);
+        let node = basil_frontend::parser::parse(sources.get(file).unwrap()).unwrap();
+        basil_frontend::lower::lower(&node).unwrap()
+    }
+
+    fn execute(interpreter: &mut Interpreter, source: &str) -> Result<Variable, Exception> {
+        interpreter.execute_module(&compile(source))
+    }
+
+    #[test]
+    fn define_class() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        execute(&mut interpreter, "class A:\n    x = 1\nclass B(A):\n    pass\n").unwrap();
+        let context = interpreter.context_graph.current_context();
+        let class_id = |name: &str| match context[name].get_object().get().as_primitive() {
+            Primitive::Class(class) => {
+                let class = class.get_object();
+                (class.id(), class.parents().clone())
+            }
+            other => panic!("{} should be a class, found {:?}", name, other),
+        };
+        let (a, _) = class_id("A");
+        let (b, b_parents) = class_id("B");
+        assert_eq!(b_parents, vec![a]);
+        assert!(interpreter.type_to_context_node.contains_key(&TypeId::Explicit(b)));
+    }
+
+    #[test]
+    fn exceptions_point_at_statements() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "class A:\n    pass\nclass B(1):\n    pass\n";
+        let exception = execute(&mut interpreter, source).unwrap_err();
+        let span = exception.span().expect("the exception should have a span");
+        assert_eq!(&source[span.start()..span.start() + 10], "class B(1)");
+
+        let mut sources = SourceMap::new();
+        sources.add_file("test.basil", source);
+        let rendered = interpreter.diagnostic(&exception).render(&sources, false);
+        assert!(rendered.contains(" --> test.basil:3:1\n"), "{}", rendered);
+        assert!(rendered.contains("3 | class B(1):\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn tracebacks() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class NotFound:
+    pass
+def load(name):
+    if name:
+        raise NotFound
+def main():
+    load('config')
+main()
+";
+        let exception = execute(&mut interpreter, source).unwrap_err();
+        let traceback = exception
+            .traceback()
+            .expect("the exception should have a traceback");
+        let names: Vec<&str> = traceback
+            .frames()
+            .iter()
+            .map(TracebackFrame::name)
+            .collect();
+        assert_eq!(names, ["<module>", "main", "load"]);
+        assert!(interpreter.traceback().frames().is_empty());
+
+        let mut sources = SourceMap::new();
+        sources.add_file("test.basil", source);
+        assert_eq!(
+            traceback.render(&sources),
+            "\
+traceback (most recent call last):
+  test.basil:8:1, in <module>
+    main()
+  test.basil:7:5, in main
+    load('config')
+  test.basil:5:9, in load
+    raise NotFound
+"
+        );
+        // JSON diagnostics list the same calls, outermost first
+        let json = interpreter.diagnostic(&exception).to_json(&sources);
+        let frames = &json[json
+            .find(",\"traceback\":")
+            .expect("the traceback should be in the JSON")..];
+        assert!(
+            frames.starts_with(
+                ",\"traceback\":[{\"function\":\"<module>\",\"span\":{\"file\":\"test.basil\",\
+                 \"line_start\":8,\"column_start\":1,\"line_end\":8,\"column_end\":7,"
+            ),
+            "{}",
+            json
+        );
+        assert!(
+            frames.contains("{\"function\":\"main\",\"span\":{"),
+            "{}",
+            json
+        );
+        let raised = source.find("raise").unwrap();
+        let end = format!(
+            "{{\"function\":\"load\",\"span\":{{\"file\":\"test.basil\",\"line_start\":5,\
+             \"column_start\":9,\"line_end\":5,\"column_end\":23,\"byte_start\":{},\"byte_end\":{}}}}}]}}",
+            raised,
+            raised + "raise NotFound".len()
+        );
+        assert!(frames.ends_with(&end), "{}", json);
+
+        // scripts see the traceback of the exceptions they handle
+        let handled = format!(
+            "{}try:\n    main()\nexcept NotFound as e:\n    names = [frame['name'] for frame in e.__traceback__]\n    start = e.__traceback__[-1]['start']\n",
+            &source[..source.find("main()\n").unwrap()]
+        );
+        execute(&mut interpreter, &handled).unwrap();
+        assert_eq!(
+            debug(&mut interpreter, "names"),
+            "[\"<module>\", \"main\", \"load\"]"
+        );
+        let start = source.find("raise").unwrap().to_string();
+        assert_eq!(debug(&mut interpreter, "start"), start);
+    }
+
+    #[test]
+    fn tracebacks_through_handlers() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class Failed:
+    pass
+def fail():
+    raise Failed
+def rethrow():
+    try:
+        fail()
+    except Failed as e:
+        raise e
+def cleanup():
+    try:
+        fail()
+    finally:
+        state[0] = 'cleaned'
+state = [None]
+";
+        let raised = source.find("raise Failed").unwrap();
+        let frames = |exception: &Exception| -> Vec<(String, usize)> {
+            let traceback = exception
+                .traceback()
+                .expect("the exception should have a traceback");
+            traceback
+                .frames()
+                .iter()
+                .map(|frame| (frame.name().to_string(), frame.span().start()))
+                .collect()
+        };
+
+        // raising the exception that's being handled again keeps the frames it was raised in
+        let exception = execute(&mut interpreter, &format!("{}rethrow()\n", source)).unwrap_err();
+        let traceback = frames(&exception);
+        assert_eq!(traceback.len(), 3);
+        assert_eq!(
+            traceback[1],
+            ("rethrow".to_string(), source.find("fail()\n").unwrap())
+        );
+        assert_eq!(traceback[2], ("fail".to_string(), raised));
+
+        // so does passing through a `finally` block
+        let exception = execute(&mut interpreter, &format!("{}cleanup()\n", source)).unwrap_err();
+        let traceback = frames(&exception);
+        assert_eq!(traceback.len(), 3);
+        assert_eq!(traceback[1].0, "cleanup");
+        assert_eq!(traceback[2], ("fail".to_string(), raised));
+        assert_eq!(debug(&mut interpreter, "state"), "[\"cleaned\"]");
+
+        // scripts see the same frames when they handle the exception that was raised again
+        let handled = format!(
+            "{}try:\n    rethrow()\nexcept Failed as e:\n    frames = e.__traceback__\n    \
+             names = [frame['name'] for frame in frames]\n    \
+             raised = [frames[-1]['start'], frames[-1]['end']]\n",
+            source
+        );
+        execute(&mut interpreter, &handled).unwrap();
+        assert_eq!(
+            debug(&mut interpreter, "names"),
+            "[\"<module>\", \"rethrow\", \"fail\"]"
+        );
+        assert_eq!(
+            debug(&mut interpreter, "raised"),
+            format!("[{}, {}]", raised, raised + "raise Failed".len())
+        );
+    }
+
+    #[test]
+    fn undefined_names() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let exception = execute(&mut interpreter, "x = 1\ny = x + z\n").unwrap_err();
+        assert_eq!(interpreter.diagnostic(&exception).message(), "z is not defined");
+        assert!(!interpreter.is_global("y"));
+        assert!(interpreter.is_global("x"));
+        assert!(interpreter.is_global("range"));
+    }
+
+    fn evaluate(interpreter: &mut Interpreter, source: &str) -> Result<Variable, Exception> {
+        let block = compile(source);
+        match block.get_object().statements()[0].get_object() {
+            Statement::Expression(expression) => interpreter.evaluate_expression(expression),
+            other => panic!("expected an expression, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let mut evaluate_to = |source: &str| {
+            let value = evaluate(&mut interpreter, source).unwrap();
+            let object = value.get_object();
+            let debug = format!("{:?}", object.get().as_primitive());
+            debug
+        };
+        assert_eq!(evaluate_to("1 + 2 * 3 - -4"), "11");
+        assert_eq!(evaluate_to("7 / 2 + 0.5"), "4");
+        assert_eq!(evaluate_to("'ab' * 2 + 'c'"), "\"ababc\"");
+        assert_eq!(evaluate_to("1 < 2 and not 'a' == 'b'"), "true");
+        assert_eq!(evaluate_to("False and undefined()"), "false");
+        assert_eq!(evaluate_to("True or undefined()"), "true");
+        assert_eq!(evaluate_to("1 and True"), "true");
+        assert_eq!(evaluate_to("not 0"), "true");
+        assert!(evaluate(&mut interpreter, "1 / 0").is_err());
+        assert!(evaluate(&mut interpreter, "1 + 'a'").is_err());
+        assert!(evaluate(&mut interpreter, "-'a'").is_err());
+    }
+
+    #[test]
+    fn collections() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let mut evaluate_to = |source: &str| {
+            let value = evaluate(&mut interpreter, source).unwrap();
+            let object = value.get_object();
+            let debug = format!("{:?}", object.get().as_primitive());
+            debug
+        };
+        assert_eq!(evaluate_to("[1, 'a', [True], (2, 3)]"), "[1, \"a\", [true], [2, 3]]");
+        assert_eq!(evaluate_to("{'a': [1, 2]}['a'][-1]"), "2");
+        assert_eq!(evaluate_to("{1: 2, 1: 3}"), "{1: **3}");
+        assert_eq!(evaluate_to("{'x', 'x'}"), "{\"x\": **None}");
+        assert_eq!(evaluate_to("'abc'[1]"), "\"b\"");
+        assert_eq!(evaluate_to("{1, 2} == {2, 1}"), "true");
+        assert_eq!(
+            evaluate_to("{'a': 1, 'b': [2]} == {'b': [2], 'a': 1}"),
+            "true"
+        );
+        assert_eq!(evaluate_to("{'a': 1} != {'a': 2}"), "true");
+        assert_eq!(evaluate_to("{'a': 1} == {'a': 1, 'b': 1}"), "false");
+        assert_eq!(evaluate_to("{1} == [1]"), "false");
+        assert!(evaluate(&mut interpreter, "[1][1]").is_err());
+        assert!(evaluate(&mut interpreter, "{[1]: 2}").is_err());
+    }
+
+    #[test]
+    fn format_strings() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        interpreter.context_graph.current_context().insert("name".to_string(), "basil".into_variable());
+        let string =
+            evaluate(&mut interpreter, "f'hello {name}, {name!r} is {1 + 2} {[True]}'").unwrap();
+        let string = String::try_from(string).unwrap();
+        assert_eq!(string, "hello basil, \"basil\" is 3 [True]");
+
+        // interpolated collections look like the literals that create them
+        let source = "\
+class Point:
+    def __repr__():
+        return 'Point()'
+d = {'k': [1, False]}
+flags = {True}
+empty = {x for x in []}
+shown = f'{[True, None, \"a\\\"b\\n\"]} {d} {flags} {empty} {[Point()]}'
+pair = f\"{ {'j': None, 'k': 2} }\"
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(
+            debug(&mut interpreter, "shown"),
+            "\"[True, None, \"a\\\"b\\n\"] {\"k\": [1, False]} {True} set() [Point()]\""
+        );
+        let pair = debug(&mut interpreter, "pair");
+        assert!(
+            pair == "\"{\"j\": None, \"k\": 2}\"" || pair == "\"{\"k\": 2, \"j\": None}\"",
+            "{}",
+            pair
+        );
+    }
+
+    #[test]
+    fn comprehensions() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        interpreter.context_graph.current_context().insert("n".to_string(), 10.into_variable());
+        let list = evaluate(
+            &mut interpreter,
+            "[x * y + n for x in [1, 2, 3] if x != 2 for y in [x, 10]]",
+        )
+        .unwrap();
+        assert_eq!(format!("{:?}", list.get_object().get().as_primitive()), "[11, 20, 19, 40]");
+        let dictionary = evaluate(&mut interpreter, "{c: c * 2 for c in 'a'}").unwrap();
+        assert_eq!(format!("{:?}", dictionary.get_object().get().as_primitive()), "{\"a\": **\"aa\"}");
+        let set = evaluate(&mut interpreter, "{x for x in [1, 1]}").unwrap();
+        assert_eq!(format!("{:?}", set.get_object().get().as_primitive()), "{1: **None}");
+        let context = interpreter.context_graph.current_context();
+        assert!(!context.contains(&"x".to_string()));
+        assert!(!context.contains(&"y".to_string()));
+        assert!(!context.contains(&"c".to_string()));
+    }
+
+    fn debug(interpreter: &mut Interpreter, name: &str) -> String {
+        let context = interpreter.context_graph.current_context();
+        let value = context[name].get_object();
+        let debug = format!("{:?}", value.get().as_primitive());
+        debug
+    }
+
+    #[test]
+    fn for_loops() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+total = 0
+for x in [1, 2, 3]:
+    total = total + x
+reversed = ''
+for c in 'abc':
+    reversed = c + reversed
+keys = ''
+for key in {'a': 1}:
+    keys = keys + key
+steps = []
+for i in range(1, 10, 3):
+    steps = steps + [i]
+for i in range(3, 0, -1):
+    steps = steps + [i]
+for unused in range(0):
+    steps = []
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "total"), "6");
+        assert_eq!(debug(&mut interpreter, "x"), "3");
+        assert_eq!(debug(&mut interpreter, "reversed"), "\"cba\"");
+        assert_eq!(debug(&mut interpreter, "keys"), "\"a\"");
+        assert_eq!(debug(&mut interpreter, "steps"), "[1, 4, 7, 3, 2, 1]");
+        assert_eq!(debug(&mut interpreter, "i"), "1");
+        assert!(!interpreter.context_graph.current_context().contains(&"unused".to_string()));
+    }
+
+    #[test]
+    fn loop_variables_bind_in_the_enclosing_scope() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+def last(items):
+    for item in items:
+        pass
+    return item
+def first(items):
+    for item in items:
+        return item
+    return None
+a = last([1, 2])
+b = first(range(5, 10))
+c = first([])
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "a"), "2");
+        assert_eq!(debug(&mut interpreter, "b"), "5");
+        assert_eq!(debug(&mut interpreter, "c"), "None");
+        assert!(!interpreter.context_graph.current_context().contains(&"item".to_string()));
+    }
+
+    #[test]
+    fn conditionals_and_while_loops() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+def sign(n):
+    if n < 0:
+        return -1
+    elif n == 0:
+        return 0
+    else:
+        return 1
+signs = [sign(-5), sign(0), sign(3)]
+def countdown(n):
+    steps = []
+    while n:
+        steps = steps + [n]
+        if n == 2:
+            return steps
+        n = n - 1
+    return steps
+short = countdown(1)
+long = countdown(4)
+if []:
+    branch = 'list'
+elif '':
+    branch = 'string'
+else:
+    branch = 'else'
+sign(1)
+";
+        let last = execute(&mut interpreter, source).unwrap();
+        assert_eq!(format!("{:?}", last.get_object().get().as_primitive()), "1");
+        assert_eq!(debug(&mut interpreter, "signs"), "[-1, 0, 1]");
+        assert_eq!(debug(&mut interpreter, "short"), "[1]");
+        assert_eq!(debug(&mut interpreter, "long"), "[4, 3, 2]");
+        assert_eq!(debug(&mut interpreter, "branch"), "\"else\"");
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+def find(items, wanted):
+    for item in items:
+        if item == wanted:
+            break
+    else:
+        return 'missing'
+    return 'found'
+found = [find([1, 2, 3], 2), find([1, 2, 3], 4), find([], 1)]
+odd = []
+for i in range(10):
+    if i % 2 == 0:
+        continue
+    if i > 6:
+        break
+    odd = odd + [i]
+n = 0
+pairs = []
+while n < 3:
+    n = n + 1
+    for m in range(3):
+        if m == n:
+            break
+        pairs = pairs + [[n, m]]
+    else:
+        continue
+    if n == 2:
+        break
+else:
+    pairs = []
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(
+            debug(&mut interpreter, "found"),
+            "[\"found\", \"missing\", \"missing\"]"
+        );
+        assert_eq!(debug(&mut interpreter, "odd"), "[1, 3, 5]");
+        assert_eq!(debug(&mut interpreter, "pairs"), "[[1, 0], [2, 0], [2, 1]]");
+
+        // without check_names, a misplaced `break` is only caught when it leaves the function
+        let source = "def f():\n    break\nfor i in range(2):\n    f()\n";
+        let exception = execute(&mut interpreter, source).unwrap_err();
+        let message = interpreter.diagnostic(&exception).message().to_string();
+        assert_eq!(message, "`break` outside of a loop");
+    }
+
+    #[test]
+    fn assignment_targets() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class Point:
+    def __init__(x, y):
+        this.x = x
+        this.y = y
+p = Point(1, 2)
+p.x = p.x + 10
+p.label = 'moved'
+coordinates = [p.x, p.y, p.label]
+items = [1, 2, 3]
+alias = items
+items[0] = 'first'
+items[-1] = items
+counts = {'a': 1}
+counts['a'] = counts['a'] + 1
+counts['b'] = 0
+a, b = 1, 2
+a, b = b, a
+[c, (d, e)] = ['c', 'de']
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "coordinates"), "[11, 2, \"moved\"]");
+        assert_eq!(debug(&mut interpreter, "alias"), "[\"first\", 2, [\"first\", 2, 3]]");
+        let counts = execute(&mut interpreter, "[counts['a'], counts['b']]\n").unwrap();
+        assert_eq!(format!("{:?}", counts.get_object().get().as_primitive()), "[2, 0]");
+        assert_eq!(debug(&mut interpreter, "a"), "2");
+        assert_eq!(debug(&mut interpreter, "b"), "1");
+        assert_eq!(debug(&mut interpreter, "e"), "\"e\"");
+
+        let errors = [
+            ("a, b = [1]\n", "not enough values to unpack (expected 2, got 1)"),
+            ("a, b = 'abc'\n", "too many values to unpack (expected 2)"),
+            ("a = 1\na.b = 2\n", "can't assign to member b of Integer"),
+            ("items = [1]\nitems[1] = 2\n", "index 1 is out of range"),
+            ("items = 'a'\nitems[0] = 'b'\n", "String doesn't support assigning to elements by Integer"),
+        ];
+        for (source, message) in errors {
+            let exception = execute(&mut interpreter, source).unwrap_err();
+            assert_eq!(interpreter.diagnostic(&exception).message(), message);
+        }
+    }
+
+    #[test]
+    fn try_statements() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class Error:
+    pass
+class NotFound(Error):
+    def __init__(message):
+        this.message = message
+class Invalid(Error):
+    pass
+def attempt(error):
+    log = []
+    try:
+        if error:
+            raise error
+        log = log + ['ran']
+    except NotFound as e:
+        log = log + ['not found: ' + e.message]
+    except (Invalid, StopIteration):
+        log = log + ['invalid']
+    else:
+        log = log + ['else']
+    finally:
+        log = log + ['finally']
+    return log
+ran = attempt(None)
+caught = attempt(NotFound('x'))
+either = attempt(Invalid)
+state = ['not run']
+def early():
+    try:
+        return 'try'
+    finally:
+        state[0] = 'finally ran'
+def replaced():
+    try:
+        return 'try'
+    finally:
+        return 'finally'
+returned = [early(), state, replaced()]
+def escaping():
+    try:
+        raise Invalid
+    except NotFound:
+        state[0] = 'wrong handler'
+    finally:
+        state[0] = 'cleaned up'
+outer = None
+try:
+    escaping()
+except Error as e:
+    outer = [e]
+log = [state[0]]
+anything = None
+try:
+    undefined_name
+except:
+    anything = 'caught'
+for i in range(3):
+    try:
+        break
+    finally:
+        log = log + [i]
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(
+            debug(&mut interpreter, "ran"),
+            "[\"ran\", \"else\", \"finally\"]"
+        );
+        assert_eq!(
+            debug(&mut interpreter, "caught"),
+            "[\"not found: x\", \"finally\"]"
+        );
+        assert_eq!(
+            debug(&mut interpreter, "either"),
+            "[\"invalid\", \"finally\"]"
+        );
+        assert_eq!(
+            debug(&mut interpreter, "returned"),
+            "[\"try\", [\"finally ran\"], \"finally\"]"
+        );
+        let outer = execute(&mut interpreter, "outer[0]\n").unwrap();
+        assert_eq!(interpreter.repr(&outer).unwrap(), "<Invalid object>");
+        assert_eq!(debug(&mut interpreter, "log"), "[\"cleaned up\", 0]");
+        assert_eq!(debug(&mut interpreter, "anything"), "\"caught\"");
+
+        let source = "try:\n    raise 1\nexcept 2:\n    pass\n";
+        let exception = execute(&mut interpreter, source).unwrap_err();
+        assert_eq!(
+            interpreter.diagnostic(&exception).message(),
+            "2 is not a class"
+        );
+    }
+
+    #[test]
+    fn truthiness() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class Empty:
+    def __len__():
+        return 0
+class Falsy(Empty):
+    def __bool__():
+        return False
+class Plain:
+    pass
+values = [None, 0, 0.0, '', [], {}, range(0), 1, 'a', [0], {0: 0}, range(1)]
+truths = [not not value for value in values]
+objects = [not Empty(), not Falsy(), not Plain()]
+kept = [value for value in values if value]
+either = 0 or 'default'
+both = 1 and []
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(
+            debug(&mut interpreter, "truths"),
+            "[false, false, false, false, false, false, false, true, true, true, true, true]"
+        );
+        assert_eq!(debug(&mut interpreter, "objects"), "[true, true, false]");
+        assert_eq!(debug(&mut interpreter, "kept"), "[1, \"a\", [0], {0: **0}, range(0, 1, 1)]");
+        assert_eq!(debug(&mut interpreter, "either"), "\"default\"");
+        assert_eq!(debug(&mut interpreter, "both"), "[]");
+
+        let source = "class Odd:\n    def __bool__():\n        return 1\nif Odd():\n    pass\n";
+        let exception = execute(&mut interpreter, source).unwrap_err();
+        let message = interpreter.diagnostic(&exception).message().to_string();
+        assert_eq!(message, "__bool__ returned Integer instead of a Boolean");
+    }
+
+    #[test]
+    fn iterator_protocol() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class Forever:
+    def __iter__():
+        return this
+    def __next__():
+        return 42
+class Done(StopIteration):
+    pass
+class EmptyIterator:
+    def __next__():
+        raise Done
+class Empty:
+    def __iter__():
+        return EmptyIterator()
+def first(items):
+    for item in items:
+        return item
+answer = first(Forever())
+result = 'empty'
+for x in Empty():
+    result = 'full'
+squares = [x * x for x in range(4)]
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "answer"), "42");
+        assert_eq!(debug(&mut interpreter, "result"), "\"empty\"");
+        assert_eq!(debug(&mut interpreter, "squares"), "[0, 1, 4, 9]");
+    }
+
+    #[test]
+    fn iteration_errors() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let mut message = |source: &str| {
+            let exception = execute(&mut interpreter, source).unwrap_err();
+            interpreter.diagnostic(&exception).message().to_string()
+        };
+        assert_eq!(message("for x in 1:\n    pass\n"), "Integer is not iterable");
+        assert_eq!(message("class A:\n    pass\nfor x in A():\n    pass\n"), "<A object> is not iterable");
+        assert_eq!(message("for x in range(0, 1, 0):\n    pass\n"), "range() step can't be zero");
+        assert_eq!(message("r = range('a')\n"), "range() takes Integers, not String");
+        assert_eq!(message("r = range()\n"), "range() takes from 1 to 3 arguments, but 0 were given");
+        assert_eq!(message("raise StopIteration\n"), "StopIteration");
+    }
+
+    #[test]
+    fn match_statements() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+def handle(message):
+    match message:
+        case {'type': 'move', 'to': [x, y]} if x >= 0:
+            return f'move {x} {y}'
+        case {'type': 'move'}:
+            return 'bad move'
+        case {'type': 'say', 'text': text}:
+            return text
+        case [1, _, last]:
+            return last
+        case None:
+            return 'nothing'
+        case other:
+            return f'unknown {other!r}'
+moved = handle({'type': 'move', 'to': [1, 2], 'extra': True})
+bad = handle({'type': 'move', 'to': [-1, 2]})
+said = handle({'type': 'say', 'text': 'hi'})
+last = handle([1, 2, 3])
+short = handle([1, 2])
+nothing = handle(None)
+result = 'unmatched'
+match 3:
+    case 4:
+        result = 'four'
+defaults = {'flags': {'debug', 'verbose'}, 'limits': {'depth': 3}}
+def configured(value):
+    match value:
+        case defaults.flags:
+            return 'flags'
+        case defaults.limits:
+            return 'limits'
+    return 'custom'
+flags = configured({'verbose', 'debug'})
+limits = configured({'depth': 3})
+custom = configured({'depth': 4})
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "moved"), "\"move 1 2\"");
+        assert_eq!(debug(&mut interpreter, "bad"), "\"bad move\"");
+        assert_eq!(debug(&mut interpreter, "said"), "\"hi\"");
+        assert_eq!(debug(&mut interpreter, "last"), "3");
+        assert_eq!(debug(&mut interpreter, "short"), "\"unknown [1, 2]\"");
+        assert_eq!(debug(&mut interpreter, "nothing"), "\"nothing\"");
+        assert_eq!(debug(&mut interpreter, "result"), "\"unmatched\"");
+        assert_eq!(debug(&mut interpreter, "flags"), "\"flags\"");
+        assert_eq!(debug(&mut interpreter, "limits"), "\"limits\"");
+        assert_eq!(debug(&mut interpreter, "custom"), "\"custom\"");
+    }
+
+    #[test]
+    fn class_patterns() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+class Shape:
+    sides = 0
+class Square(Shape):
+    sides = 4
+class Triangle(Shape):
+    sides = 3
+class Other:
+    pass
+def describe(value):
+    match value:
+        case Square(sides=n):
+            return f'square with {n}'
+        case Shape(sides=3, missing=_):
+            return 'impossible'
+        case Shape(sides=n):
+            return f'shape with {n}'
+        case Other():
+            return 'other'
+square = describe(Square())
+triangle = describe(Triangle())
+other = describe(Other())
+none = describe(1)
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "square"), "\"square with 4\"");
+        assert_eq!(debug(&mut interpreter, "triangle"), "\"shape with 3\"");
+        assert_eq!(debug(&mut interpreter, "other"), "\"other\"");
+        assert_eq!(debug(&mut interpreter, "none"), "None");
+
+        let exception = execute(&mut interpreter, "match 1:\n    case describe():\n        pass\n").unwrap_err();
+        assert_eq!(interpreter.diagnostic(&exception).message(), "<function describe> is not a class");
+    }
+
+    #[test]
+    fn closures() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+def make_getter(start):
+    count = start
+    def get():
+        return count
+    count = count + 1
+    return get
+def adder(n):
+    return lambda x, y=0: x + n + y
+def outer():
+    x = 'outer'
+    def middle():
+        def inner():
+            return x
+        return inner
+    f = middle()
+    x = 'changed'
+    return f
+def make_factorial():
+    def factorial(n):
+        match n:
+            case 0:
+                return 1
+            case _:
+                return n * factorial(n - 1)
+    return factorial
+def rebind(a):
+    a = 2
+    return a
+getter = make_getter(10)
+counted = getter()
+add = adder(5)
+added = [add(1), add(1, y=2)]
+nested = outer()()
+six = make_factorial()(3)
+original = 1
+rebound = rebind(original)
+def make_counter():
+    count = 0
+    def increment():
+        count = count + 1
+        return count
+    inside = [increment(), increment()]
+    return [increment, inside + [count]]
+[counter, counts] = make_counter()
+counts = counts + [counter(), counter()]
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "counted"), "11");
+        assert_eq!(debug(&mut interpreter, "added"), "[6, 8]");
+        assert_eq!(debug(&mut interpreter, "nested"), "\"changed\"");
+        assert_eq!(debug(&mut interpreter, "six"), "6");
+        assert_eq!(debug(&mut interpreter, "original"), "1");
+        assert_eq!(debug(&mut interpreter, "rebound"), "2");
+        assert_eq!(debug(&mut interpreter, "counts"), "[1, 2, 2, 3, 4]");
+    }
+
+    #[test]
+    fn functions_see_the_scope_they_are_defined_in() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+x = 'global'
+def f():
+    return x
+def g():
+    x = 'local'
+    return f()
+def h():
+    return lambda: x
+def k():
+    x = 'k'
+    return h()()
+seen = [g(), k()]
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "seen"), "[\"global\", \"global\"]");
+
+        let source = "def callee():\n    return secret\ndef caller():\n    secret = 1\n    return callee()\ncaller()\n";
+        let exception = execute(&mut interpreter, source).unwrap_err();
+        assert_eq!(
+            interpreter.diagnostic(&exception).message(),
+            "secret is not defined"
+        );
+    }
+
+    #[test]
+    fn decorators() {
+        let mut interpreter = Interpreter::new(ContextGraph::new());
+        let source = "\
+def twice(f):
+    return lambda x: f(f(x))
+def tag(label):
+    return lambda f: lambda x: label + f(x)
+def instance(c):
+    return c()
+@twice
+def increment(x):
+    return x + 1
+@tag('<')
+@tag('>')
+def name(x):
+    return x
+@instance
+class Greeter:
+    pass
+def unchanged(method):
+    return method
+def quiet():
+    return 'Quiet()'
+def silenced(method):
+    return quiet
+class Loud:
+    @silenced
+    @unchanged
+    def __repr__():
+        return 'Loud()'
+three = increment(1)
+tagged = name('a')
+greeter = f'{Greeter}'
+loud = f'{Loud()}'
+";
+        execute(&mut interpreter, source).unwrap();
+        assert_eq!(debug(&mut interpreter, "three"), "3");
+        assert_eq!(debug(&mut interpreter, "tagged"), "\"<>a\"");
+        assert_eq!(debug(&mut interpreter, "greeter"), "\"<Greeter object>\"");
+        assert_eq!(debug(&mut interpreter, "loud"), "\"Quiet()\"");
+    }
+
     #[test]
     fn set_val() {
-        let mut dict = Dictionary::new().into_variable();
+        let dict = Dictionary::new().into_variable();
         basil!(dict.yeet = "hello world");
         let string: String = String::try_from(basil!(dict.yeet).unwrap()).unwrap();
         assert_eq!(string, "hello world");
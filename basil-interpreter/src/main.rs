@@ -1,3 +1,196 @@
+use std::fs;
+use std::io::IsTerminal;
+use std::process::exit;
+
+use basil_ast::dump::{to_json, to_sexpr};
+use basil_core::diagnostic::Diagnostic;
+use basil_core::source_map::SourceMap;
+use basil_frontend::check::check;
+use basil_frontend::format::format;
+use basil_frontend::lower::lower;
+use basil_frontend::parser::parse_recovering;
+use basil_frontend::resolve::check_names;
+use basil_interpreter::context::ContextGraph;
+use basil_interpreter::interpreter::Interpreter;
+
+const USAGE: &str = "usage: basil [--message-format json] fmt [--check] <files>...
+       basil [--message-format json] run <file>
+       basil [--message-format json] dump [--json] <file>";
+
 fn main() {
-    println!("Hello, world!");
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let json = match args.iter().position(|arg| arg == "--message-format") {
+        Some(index) if args.get(index + 1).map(String::as_str) == Some("json") => {
+            args.drain(index..index + 2);
+            true
+        }
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            exit(2)
+        }
+        None => false,
+    };
+    let reporter = Reporter { json };
+    let status = match args.first().map(String::as_str) {
+        Some("fmt") => fmt(&args[1..], &reporter),
+        Some("run") if args.len() == 2 => run(&args[1], &reporter),
+        Some("dump") if args.len() == 2 => dump(&args[1], false, &reporter),
+        Some("dump") if args.len() == 3 && args[1] == "--json" => dump(&args[2], true, &reporter),
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    };
+    exit(status)
+}
+
+/// Formats files in place. With `--check` nothing is written, and the files that would change are
+/// listed instead.
+///
+/// Exits with 1 if a checked file isn't formatted, and 2 if a file couldn't be read, written or
+/// parsed.
+fn fmt(args: &[String], reporter: &Reporter) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+    let files: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+
+    let mut sources = SourceMap::new();
+    let mut status = 0;
+    for file in files {
+        let source = match sources.load_file(file) {
+            Ok(id) => sources.get(id).unwrap(),
+            Err(error) => {
+                eprintln!("{}: {}", file, error);
+                status = 2;
+                continue;
+            }
+        };
+        match format(source) {
+            Ok(formatted) if formatted == source.text() => {}
+            Ok(_) if check => {
+                println!("{}", file);
+                status = status.max(1);
+            }
+            Ok(formatted) => {
+                if let Err(error) = fs::write(file, formatted) {
+                    eprintln!("{}: {}", file, error);
+                    status = 2;
+                }
+            }
+            Err(errors) => {
+                for error in errors {
+                    reporter.report(&error.diagnostic(), &sources);
+                }
+                status = 2;
+            }
+        }
+    }
+    status
+}
+
+/// Prints the tree of a file as an S-expression with spans, or as JSON. The tree is printed even if
+/// the file has syntax errors, with the parts that couldn't be parsed replaced by `Error` nodes.
+///
+/// Exits with 2 if the file couldn't be read or has syntax errors.
+fn dump(file: &str, json: bool, reporter: &Reporter) -> i32 {
+    let mut sources = SourceMap::new();
+    let source = match sources.load_file(file) {
+        Ok(id) => sources.get(id).unwrap(),
+        Err(error) => {
+            eprintln!("{}: {}", file, error);
+            return 2;
+        }
+    };
+    let (module, errors) = parse_recovering(source);
+    if json {
+        println!("{}", to_json(&module));
+    } else {
+        println!("{}", to_sexpr(&module, true));
+    }
+    for error in &errors {
+        reporter.report(&error.diagnostic(), &sources);
+    }
+    if errors.is_empty() {
+        0
+    } else {
+        2
+    }
+}
+
+/// Parses, type checks, lowers and runs a file. Exits with 1 if an exception escapes, and 2 if the
+/// file couldn't be read, failed to type check, couldn't be compiled or uses undefined names.
+fn run(file: &str, reporter: &Reporter) -> i32 {
+    let mut sources = SourceMap::new();
+    let source = match sources.load_file(file) {
+        Ok(id) => sources.get(id).unwrap(),
+        Err(error) => {
+            eprintln!("{}: {}", file, error);
+            return 2;
+        }
+    };
+    let (module, errors) = parse_recovering(source);
+    if !errors.is_empty() {
+        for error in errors {
+            reporter.report(&error.diagnostic(), &sources);
+        }
+        return 2;
+    }
+    if let Err(errors) = check(&module) {
+        for error in errors {
+            reporter.report(&error.diagnostic(), &sources);
+        }
+        return 2;
+    }
+    let mut module = match lower(&module) {
+        Ok(module) => module,
+        Err(errors) => {
+            for error in errors {
+                reporter.report(&error.diagnostic(), &sources);
+            }
+            return 2;
+        }
+    };
+
+    let mut interpreter = Interpreter::new(ContextGraph::new());
+    if let Err(errors) = check_names(&mut module, &|name| interpreter.is_global(name)) {
+        for error in errors {
+            reporter.report(&error.diagnostic(), &sources);
+        }
+        return 2;
+    }
+    match interpreter.execute_module(&module) {
+        Ok(_) => 0,
+        Err(exception) => {
+            let diagnostic = interpreter.diagnostic(&exception);
+            reporter.report(&diagnostic, &sources);
+            1
+        }
+    }
+}
+
+/// Prints diagnostics to stderr, either rendered with their source or as one JSON object per line
+struct Reporter {
+    json: bool,
+}
+
+impl Reporter {
+    /// Prints a diagnostic, after the calls that led to it if it has a traceback. The traceback
+    /// is left out of the rendered output when the exception was raised at the top level, where
+    /// the diagnostic already shows everything.
+    fn report(&self, diagnostic: &Diagnostic, sources: &SourceMap) {
+        if self.json {
+            eprintln!("{}", diagnostic.to_json(sources));
+        } else {
+            if let Some(traceback) = diagnostic.traceback() {
+                if traceback.frames().len() > 1 {
+                    eprint!("{}", traceback.render(sources));
+                }
+            }
+            let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+            eprint!("{}", diagnostic.render(sources, color));
+        }
+    }
 }
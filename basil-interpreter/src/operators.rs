@@ -0,0 +1,460 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational};
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Signed, ToPrimitive, Zero};
+
+use basil_core::dictionary::Dictionary;
+use basil_core::exception::Exception;
+use basil_core::expression::{BinaryOperator, UnaryOperator};
+use basil_core::object::Object;
+use basil_core::primitive::Primitive;
+
+/// Applies an arithmetic or comparison operator to two primitives.
+///
+/// `and` and `or` aren't handled here, because they need to short circuit.
+pub fn binary_operation(
+    op: BinaryOperator,
+    lhs: &Primitive,
+    rhs: &Primitive,
+) -> Result<Primitive, Exception> {
+    match op {
+        BinaryOperator::Eq => Ok(Primitive::Boolean(primitive_eq(lhs, rhs))),
+        BinaryOperator::Neq => Ok(Primitive::Boolean(!primitive_eq(lhs, rhs))),
+        BinaryOperator::Lt | BinaryOperator::Gt | BinaryOperator::Lte | BinaryOperator::Gte => {
+            let ordering = compare(lhs, rhs).ok_or_else(|| unsupported(op, lhs, rhs))?;
+            let result = match op {
+                BinaryOperator::Lt => ordering == Ordering::Less,
+                BinaryOperator::Gt => ordering == Ordering::Greater,
+                BinaryOperator::Lte => ordering != Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            };
+            Ok(Primitive::Boolean(result))
+        }
+        BinaryOperator::And | BinaryOperator::Or => Err(unsupported(op, lhs, rhs)),
+        _ => arithmetic(op, lhs, rhs),
+    }
+}
+
+pub fn unary_operation(op: UnaryOperator, value: &Primitive) -> Result<Primitive, Exception> {
+    match (op, value) {
+        (UnaryOperator::Neg, Primitive::Integer(i)) => Ok(Primitive::Integer(-i)),
+        (UnaryOperator::Neg, Primitive::Float(f)) => Rational::zero()
+            .checked_sub(f)
+            .map(Primitive::Float)
+            .ok_or_else(overflow),
+        (UnaryOperator::Not, Primitive::Boolean(b)) => Ok(Primitive::Boolean(!b)),
+        (op, value) => Err(Exception::from(format!(
+            "unsupported operand type for {}: {}",
+            operator_str(op),
+            value.type_name()
+        ))),
+    }
+}
+
+/// Checks whether two primitives are equal. Numbers are compared by value regardless of whether
+/// they are integers or floats, lists are compared element by element, dictionaries are equal if
+/// they have the same keys with equal values, sets if they have the same keys, and anything else
+/// is only equal to itself.
+pub fn primitive_eq(lhs: &Primitive, rhs: &Primitive) -> bool {
+    match (lhs, rhs) {
+        (Primitive::None, Primitive::None) => true,
+        (Primitive::Integer(l), Primitive::Integer(r)) => l == r,
+        (Primitive::Float(l), Primitive::Float(r)) => l == r,
+        (Primitive::Integer(_), Primitive::Float(_))
+        | (Primitive::Float(_), Primitive::Integer(_)) => {
+            compare(lhs, rhs) == Some(Ordering::Equal)
+        }
+        (Primitive::String(l), Primitive::String(r)) => l == r,
+        (Primitive::Boolean(l), Primitive::Boolean(r)) => l == r,
+        (Primitive::List(l), Primitive::List(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .zip(r)
+                    .all(|(l, r)| primitive_eq(l.as_primitive(), r.as_primitive()))
+        }
+        (Primitive::Dictionary(l), Primitive::Dictionary(r)) => dictionary_eq(l, r),
+        (l, r) => std::ptr::eq(l, r),
+    }
+}
+
+fn dictionary_eq(lhs: &Dictionary, rhs: &Dictionary) -> bool {
+    // looking a key up borrows the keys of the dictionary, which iterating over it already does
+    if std::ptr::eq(lhs, rhs) {
+        return true;
+    }
+    lhs.len() == rhs.len()
+        && lhs.is_set() == rhs.is_set()
+        && lhs.iter().all(|(key, value)| {
+            let mut key = key.clone();
+            match rhs.get(&mut key, Object::basic_hash, Object::basic_eq) {
+                Some(other) => primitive_eq(
+                    value.get_object().get().as_primitive(),
+                    other.get_object().get().as_primitive(),
+                ),
+                None => false,
+            }
+        })
+}
+
+/// Whether a primitive counts as true in a condition. None, zero, and empty strings, lists,
+/// dictionaries and ranges are false, and everything else is true.
+pub fn truthy(value: &Primitive) -> bool {
+    match value {
+        Primitive::None => false,
+        Primitive::Integer(i) => !i.is_zero(),
+        Primitive::Float(f) => !f.is_zero(),
+        Primitive::String(string) => !string.is_empty(),
+        Primitive::Boolean(b) => *b,
+        Primitive::List(list) => !list.is_empty(),
+        Primitive::Dictionary(dictionary) => !dictionary.is_empty(),
+        Primitive::Range { start, stop, step } => {
+            if step.is_positive() {
+                start < stop
+            } else {
+                start > stop
+            }
+        }
+        Primitive::Function(_) | Primitive::Class(_) => true,
+    }
+}
+
+/// Orders two primitives, if they can be ordered
+pub fn compare(lhs: &Primitive, rhs: &Primitive) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Primitive::Integer(l), Primitive::Integer(r)) => Some(l.cmp(r)),
+        (Primitive::Float(l), Primitive::Float(r)) => Some(l.cmp(r)),
+        (Primitive::Integer(_), Primitive::Float(_))
+        | (Primitive::Float(_), Primitive::Integer(_)) => {
+            Some(big_rational(lhs)?.cmp(&big_rational(rhs)?))
+        }
+        (Primitive::String(l), Primitive::String(r)) => Some(l.cmp(r)),
+        (Primitive::List(l), Primitive::List(r)) => {
+            for (l, r) in l.iter().zip(r) {
+                match compare(l.as_primitive(), r.as_primitive())? {
+                    Ordering::Equal => continue,
+                    ordering => return Some(ordering),
+                }
+            }
+            Some(l.len().cmp(&r.len()))
+        }
+        _ => None,
+    }
+}
+
+fn arithmetic(
+    op: BinaryOperator,
+    lhs: &Primitive,
+    rhs: &Primitive,
+) -> Result<Primitive, Exception> {
+    match (lhs, rhs) {
+        (Primitive::Integer(l), Primitive::Integer(r)) => integer_arithmetic(op, l, r),
+        (Primitive::Integer(_), Primitive::Float(_))
+        | (Primitive::Float(_), Primitive::Integer(_))
+        | (Primitive::Float(_), Primitive::Float(_)) => {
+            float_arithmetic(op, &to_rational(lhs)?, &to_rational(rhs)?).map(Primitive::Float)
+        }
+        (Primitive::String(l), Primitive::String(r)) if op == BinaryOperator::Add => {
+            Ok(Primitive::String(format!("{}{}", l, r)))
+        }
+        (Primitive::String(s), Primitive::Integer(n))
+        | (Primitive::Integer(n), Primitive::String(s))
+            if op == BinaryOperator::Mul =>
+        {
+            let count = repetitions(n)?;
+            repeated_length(s.len(), count)?;
+            Ok(Primitive::String(s.repeat(count)))
+        }
+        (Primitive::List(l), Primitive::List(r)) if op == BinaryOperator::Add => {
+            Ok(Primitive::List(l.iter().chain(r).cloned().collect()))
+        }
+        (Primitive::List(l), Primitive::Integer(n))
+        | (Primitive::Integer(n), Primitive::List(l))
+            if op == BinaryOperator::Mul =>
+        {
+            let repeated: Vec<Object> = l
+                .iter()
+                .cycle()
+                .take(repeated_length(l.len(), repetitions(n)?)?)
+                .cloned()
+                .collect();
+            Ok(Primitive::List(repeated))
+        }
+        _ => Err(unsupported(op, lhs, rhs)),
+    }
+}
+
+fn integer_arithmetic(
+    op: BinaryOperator,
+    lhs: &BigInt,
+    rhs: &BigInt,
+) -> Result<Primitive, Exception> {
+    let result = match op {
+        BinaryOperator::Add => lhs + rhs,
+        BinaryOperator::Sub => lhs - rhs,
+        BinaryOperator::Mul => lhs * rhs,
+        BinaryOperator::Div => {
+            if rhs.is_zero() {
+                return Err(division_by_zero());
+            }
+            let quotient = BigRational::new(lhs.clone(), rhs.clone());
+            return from_big_rational(&quotient).map(Primitive::Float);
+        }
+        _ => {
+            if rhs.is_zero() {
+                return Err(division_by_zero());
+            }
+            // the result takes the sign of the divisor
+            let remainder = lhs % rhs;
+            if !remainder.is_zero() && remainder.is_negative() != rhs.is_negative() {
+                remainder + rhs
+            } else {
+                remainder
+            }
+        }
+    };
+    Ok(Primitive::Integer(result))
+}
+
+fn float_arithmetic(
+    op: BinaryOperator,
+    lhs: &Rational,
+    rhs: &Rational,
+) -> Result<Rational, Exception> {
+    let result = match op {
+        BinaryOperator::Add => lhs.checked_add(rhs),
+        BinaryOperator::Sub => lhs.checked_sub(rhs),
+        BinaryOperator::Mul => lhs.checked_mul(rhs),
+        BinaryOperator::Div => {
+            if rhs.is_zero() {
+                return Err(division_by_zero());
+            }
+            lhs.checked_div(rhs)
+        }
+        _ => {
+            if rhs.is_zero() {
+                return Err(division_by_zero());
+            }
+            lhs.checked_div(rhs)
+                .map(|quotient| quotient.floor())
+                .and_then(|quotient| rhs.checked_mul(&quotient))
+                .and_then(|multiple| lhs.checked_sub(&multiple))
+        }
+    };
+    result.ok_or_else(overflow)
+}
+
+fn big_rational(value: &Primitive) -> Option<BigRational> {
+    match value {
+        Primitive::Integer(i) => Some(BigRational::from_integer(i.clone())),
+        Primitive::Float(f) => Some(BigRational::new(
+            BigInt::from(*f.numer()),
+            BigInt::from(*f.denom()),
+        )),
+        _ => None,
+    }
+}
+
+fn to_rational(value: &Primitive) -> Result<Rational, Exception> {
+    match value {
+        Primitive::Float(f) => Ok(*f),
+        Primitive::Integer(i) => i
+            .to_isize()
+            .map(Rational::from_integer)
+            .ok_or_else(overflow),
+        other => Err(Exception::from(format!(
+            "{} is not a number",
+            other.type_name()
+        ))),
+    }
+}
+
+fn from_big_rational(value: &BigRational) -> Result<Rational, Exception> {
+    match (value.numer().to_isize(), value.denom().to_isize()) {
+        (Some(numerator), Some(denominator)) => Ok(Rational::new(numerator, denominator)),
+        _ => Err(overflow()),
+    }
+}
+
+/// The most bytes of a string, or elements of a list, that repeating one can create
+const MAX_REPEATED_LENGTH: usize = 1 << 30;
+
+fn repetitions(count: &BigInt) -> Result<usize, Exception> {
+    if count.is_negative() {
+        return Ok(0);
+    }
+    count.to_usize().ok_or_else(repetition_too_large)
+}
+
+/// The length of a string or list of `length` once it's repeated `count` times
+fn repeated_length(length: usize, count: usize) -> Result<usize, Exception> {
+    length
+        .checked_mul(count)
+        .filter(|&repeated| repeated <= MAX_REPEATED_LENGTH)
+        .ok_or_else(repetition_too_large)
+}
+
+fn repetition_too_large() -> Exception {
+    Exception::from("repetition count is too large")
+}
+
+fn division_by_zero() -> Exception {
+    Exception::from("division by zero")
+}
+
+fn overflow() -> Exception {
+    Exception::from("float result is too large to represent")
+}
+
+fn unsupported(op: BinaryOperator, lhs: &Primitive, rhs: &Primitive) -> Exception {
+    Exception::from(format!(
+        "unsupported operand types for {}: {} and {}",
+        binary_operator_str(op),
+        lhs.type_name(),
+        rhs.type_name()
+    ))
+}
+
+fn binary_operator_str(op: BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Sub => "-",
+        BinaryOperator::Mul => "*",
+        BinaryOperator::Div => "/",
+        BinaryOperator::Rem => "%",
+        BinaryOperator::Eq => "==",
+        BinaryOperator::Neq => "!=",
+        BinaryOperator::Lt => "<",
+        BinaryOperator::Gt => ">",
+        BinaryOperator::Lte => "<=",
+        BinaryOperator::Gte => ">=",
+        BinaryOperator::And => "and",
+        BinaryOperator::Or => "or",
+    }
+}
+
+fn operator_str(op: UnaryOperator) -> &'static str {
+    match op {
+        UnaryOperator::Neg => "-",
+        UnaryOperator::Not => "not",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use basil_core::variable::IntoVariable;
+
+    fn int(i: i64) -> Primitive {
+        Primitive::Integer(BigInt::from(i))
+    }
+
+    fn float(n: isize, d: isize) -> Primitive {
+        Primitive::Float(Rational::new(n, d))
+    }
+
+    fn eval(op: BinaryOperator, lhs: Primitive, rhs: Primitive) -> Primitive {
+        binary_operation(op, &lhs, &rhs).unwrap()
+    }
+
+    #[test]
+    fn numbers() {
+        assert!(primitive_eq(
+            &eval(BinaryOperator::Add, int(2), int(3)),
+            &int(5)
+        ));
+        assert!(primitive_eq(
+            &eval(BinaryOperator::Div, int(1), int(4)),
+            &float(1, 4)
+        ));
+        assert!(primitive_eq(
+            &eval(BinaryOperator::Rem, int(-7), int(3)),
+            &int(2)
+        ));
+        assert!(primitive_eq(
+            &eval(BinaryOperator::Mul, float(1, 2), int(3)),
+            &float(3, 2)
+        ));
+        assert!(primitive_eq(
+            &eval(BinaryOperator::Rem, float(7, 2), int(2)),
+            &float(3, 2)
+        ));
+        assert!(primitive_eq(&float(4, 2), &int(2)));
+        assert!(binary_operation(BinaryOperator::Div, &int(1), &int(0)).is_err());
+        assert!(binary_operation(
+            BinaryOperator::Mul,
+            &float(isize::MAX, 1),
+            &float(isize::MAX, 1)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn sequences() {
+        let hello = Primitive::from("hello");
+        let doubled = eval(BinaryOperator::Mul, hello.clone(), int(2));
+        assert!(primitive_eq(&doubled, &Primitive::from("hellohello")));
+        let list = Primitive::List(vec![Object::from(1), Object::from("a")]);
+        let concatenated = eval(BinaryOperator::Add, list.clone(), list.clone());
+        assert!(primitive_eq(
+            &concatenated,
+            &eval(BinaryOperator::Mul, int(2), list.clone())
+        ));
+        assert!(primitive_eq(
+            &eval(BinaryOperator::Lt, list, concatenated),
+            &Primitive::Boolean(true)
+        ));
+        assert!(binary_operation(BinaryOperator::Sub, &hello, &hello).is_err());
+    }
+
+    #[test]
+    fn dictionaries() {
+        let dictionary = |entries: &[(&str, i64)]| {
+            let mut dictionary = Dictionary::new();
+            for (key, value) in entries {
+                dictionary.insert(
+                    Object::from(*key),
+                    Object::from(*value).into_variable(),
+                    Object::basic_hash,
+                    Object::basic_eq,
+                );
+            }
+            Primitive::Dictionary(dictionary)
+        };
+        let d = dictionary(&[("k", 1), ("j", 2)]);
+        assert!(primitive_eq(&d, &d));
+        assert!(primitive_eq(&d, &dictionary(&[("j", 2), ("k", 1)])));
+        assert!(!primitive_eq(&d, &dictionary(&[("k", 1), ("j", 3)])));
+        assert!(!primitive_eq(&d, &dictionary(&[("k", 1)])));
+        assert!(!primitive_eq(&d, &dictionary(&[("k", 1), ("i", 2)])));
+
+        // sets are dictionaries whose values are all None, but aren't equal to those dictionaries
+        let set = |keys: &[&str]| Primitive::Dictionary(Dictionary::with_entries(keys).into_set());
+        assert!(primitive_eq(&set(&["a", "b"]), &set(&["b", "a"])));
+        assert!(!primitive_eq(&set(&["a", "b"]), &set(&["a", "c"])));
+        assert!(primitive_eq(&set(&[]), &set(&[])));
+        assert!(!primitive_eq(
+            &set(&["a"]),
+            &Primitive::Dictionary(Dictionary::with_entries(["a"]))
+        ));
+    }
+
+    #[test]
+    fn repetition_limits() {
+        let huge = int(i64::MAX);
+        let list = Primitive::List(vec![Object::from(1)]);
+        let string = Primitive::from("ab");
+        for sequence in [list, string] {
+            for (lhs, rhs) in [(&sequence, &huge), (&huge, &sequence)] {
+                let error = binary_operation(BinaryOperator::Mul, lhs, rhs).unwrap_err();
+                assert!(matches!(
+                    error.inner().get_object().get().as_primitive(),
+                    Primitive::String(message) if message == "repetition count is too large"
+                ));
+            }
+        }
+        let empty = Primitive::List(vec![]);
+        let repeated = eval(BinaryOperator::Mul, empty.clone(), huge);
+        assert!(primitive_eq(&repeated, &empty));
+    }
+}
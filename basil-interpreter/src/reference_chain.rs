@@ -1,6 +1,3 @@
-use std::cell::{Ref, RefMut};
-use std::ops::{Deref, DerefMut};
-
 /*
 pub trait RefChain {
     type Target;